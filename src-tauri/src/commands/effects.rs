@@ -1,11 +1,20 @@
 //! Effect-related Tauri commands
 use crate::effects;
+use crate::media::MediaAnalyzer;
+use crate::render::{forget_finished_job, RenderProgress, RenderStatus};
 use crate::{Error, Result};
+use ffmpeg::Rescale;
 use ffmpeg_next as ffmpeg;
+use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
-use tauri::command;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{command, AppHandle, Emitter};
 use tempfile::Builder;
+use uuid::Uuid;
 
 /// Effect definition for the frontend
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -37,6 +46,15 @@ pub struct EffectParameter {
     pub min: Option<f64>,
     pub max: Option<f64>,
     pub step: Option<f64>,
+    /// Choices for a `Select` parameter
+    pub options: Option<Vec<SelectOption>>,
+}
+
+/// A choice for a `Select` parameter
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelectOption {
+    pub label: String,
+    pub value: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -46,11 +64,57 @@ pub enum ParameterType {
     Boolean,
     Color,
     Select,
+    File,
+    Text,
+}
+
+lazy_static! {
+    /// Effect jobs by ID, kept for a while after they finish so their
+    /// outcome can be read
+    static ref EFFECT_JOBS: Mutex<HashMap<String, Arc<Mutex<EffectJob>>>> =
+        Mutex::new(HashMap::new());
+}
+
+/// Least time between two `effect-progress` events of a job
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// An effect being applied in the background
+struct EffectJob {
+    progress: RenderProgress,
+    started_at: Instant,
+    /// Set by `cancel_effect`; the transcode checks it between packets
+    cancelled: Arc<AtomicBool>,
+    /// Receives an `effect-progress` event on every progress update
+    app: AppHandle,
+}
+
+impl EffectJob {
+    fn update_progress(&mut self, status: RenderStatus, progress: f64, message: &str) {
+        let finishing = status.is_finished() && !self.progress.status.is_finished();
+        let elapsed = self.started_at.elapsed().as_secs_f64();
+        self.progress.advance(elapsed, status, progress, message);
+        let _ = self.app.emit("effect-progress", &self.progress);
+        if finishing {
+            forget_finished_job(&EFFECT_JOBS, self.progress.job_id.clone());
+        }
+    }
 }
 
-/// Apply an effect to a media file (preview or render)
+/// Start applying an effect to a media file (preview or render), returning
+/// the job ID
+///
+/// The best video stream is re-encoded with H.264 when the effect has a
+/// video filter, and the best audio stream with AAC when it has an audio
+/// filter; a stream the effect doesn't touch is copied.  Other streams are
+/// dropped.  Without `output_path` the result goes to a new temporary
+/// directory that is left for the caller to clean up.
+///
+/// Progress is emitted as `effect-progress` events with the same payload as
+/// `get_effect_progress`, shaped like a render's; `outputPath` is set once
+/// the job completes.
 #[command]
 pub async fn apply_effect(
+    app: AppHandle,
     input_path: String,
     effect_id: String,
     parameters: serde_json::Value,
@@ -60,110 +124,531 @@ pub async fn apply_effect(
     let output = match output_path {
         Some(p) => PathBuf::from(p),
         None => {
-            let temp_dir = Builder::new().prefix("waldiez_").tempdir()?;
-            temp_dir.path().join(format!("effect_{}.mp4", effect_id))
+            let temp_dir = Builder::new().prefix("waldiez_").tempdir()?.keep();
+            temp_dir.join(format!("effect_{}.mp4", effect_id))
         }
     };
 
-    let filter_str = effects::get_ffmpeg_filter(&effect_id, &parameters)?;
+    let parameters = if effects::InputFormat::needed_by(&effect_id) {
+        let probe = input.clone();
+        let format = tauri::async_runtime::spawn_blocking(move || input_format(&probe))
+            .await
+            .map_err(|e| Error::Internal(format!("Probe task failed: {}", e)))??;
+        format.fill(&effect_id, &parameters)
+    } else {
+        parameters
+    };
+
+    // Audio effects only have an audio filter and leave the video stream
+    // untouched; speed has both a video and an audio filter
+    let filters = effects::get_effect_filters(&effect_id, &parameters)?;
 
-    let mut ictx = ffmpeg::format::input(&input)?;
-    let mut octx = ffmpeg::format::output(&output)?;
+    let job_id = Uuid::new_v4().to_string();
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let job = Arc::new(Mutex::new(EffectJob {
+        progress: RenderProgress::new(job_id.clone(), "Waiting to start"),
+        started_at: Instant::now(),
+        cancelled: cancelled.clone(),
+        app,
+    }));
+    EFFECT_JOBS
+        .lock()
+        .unwrap()
+        .insert(job_id.clone(), job.clone());
+    let message = format!("Applying {}", effect_id);
+    job.lock()
+        .unwrap()
+        .update_progress(RenderStatus::Rendering, 0.0, &message);
 
-    let mut stream_mapping = vec![usize::MAX; ictx.nb_streams() as usize];
-    let mut best_video_stream: Option<usize> = None;
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = transcode_with_filters(
+            &input,
+            &output,
+            &filters,
+            &cancelled,
+            |progress, frames_done, frames_total| {
+                let mut job = job.lock().unwrap();
+                if job.progress.status == RenderStatus::Rendering {
+                    job.progress.frames_done = frames_done;
+                    job.progress.frames_total = frames_total;
+                    job.update_progress(RenderStatus::Rendering, progress, &message);
+                }
+            },
+        );
 
-    for (i, ist) in ictx.streams().enumerate() {
-        // Pick the first video stream as "best" (you can improve later)
-        if best_video_stream.is_none() && ist.parameters().medium() == ffmpeg::media::Type::Video {
-            best_video_stream = Some(i);
+        let mut job = job.lock().unwrap();
+        match result {
+            Ok(()) => {
+                // Set first so the final event carries the path
+                job.progress.output_path = Some(output.to_string_lossy().to_string());
+                job.update_progress(RenderStatus::Completed, 1.0, "Effect applied");
+            }
+            Err(e) => {
+                let _ = std::fs::remove_file(&output);
+                // Keep the status and message set by `cancel_effect`
+                if job.progress.status != RenderStatus::Cancelled {
+                    job.update_progress(RenderStatus::Failed, 0.0, &e.to_string());
+                }
+            }
         }
+    });
 
-        // Create an output stream without relying on `ist.codec()`.
-        // In ffmpeg-next 8.x, the safe way is to add a stream by codec id when you actually encode.
-        // For "copy/remux" scaffolding, we can add a stream using the same codec id from parameters.
-        let codec_id = ist.parameters().id();
+    Ok(job_id)
+}
 
-        // `find(codec_id)` returns a codec descriptor (decoder/encoder). We just need something
-        // to satisfy add_stream; parameters are set right after.
-        let codec = ffmpeg::codec::decoder::find(codec_id)
-            .or_else(|| ffmpeg::codec::encoder::find(codec_id))
-            .ok_or_else(|| Error::Media(format!("Unsupported codec id: {:?}", codec_id)))?;
+/// Cancel a running effect job, deleting its partial output
+#[command]
+pub async fn cancel_effect(job_id: String) -> Result<()> {
+    let job = EFFECT_JOBS.lock().unwrap().get(&job_id).cloned();
+    if let Some(job) = job {
+        let mut job = job.lock().unwrap();
+        if job.progress.status == RenderStatus::Rendering {
+            let progress = job.progress.progress;
+            job.update_progress(
+                RenderStatus::Cancelled,
+                progress,
+                "Effect cancelled by user",
+            );
+            job.cancelled.store(true, Ordering::Relaxed);
+            return Ok(());
+        }
+    }
+    Err(Error::NotFound("Effect job not found".to_string()))
+}
 
-        let mut ost = octx.add_stream(codec)?;
-        ost.set_parameters(ist.parameters());
+/// Get the progress of an effect job
+///
+/// A finished job can be looked up for ten minutes, then it is forgotten.
+#[command]
+pub async fn get_effect_progress(job_id: String) -> Result<RenderProgress> {
+    EFFECT_JOBS
+        .lock()
+        .unwrap()
+        .get(&job_id)
+        .map(|job| job.lock().unwrap().progress.clone())
+        .ok_or_else(|| Error::NotFound("Effect job not found".to_string()))
+}
 
-        stream_mapping[i] = ost.index();
+/// Transcode `input` into `output`, running the best video and audio streams
+/// through the filters of `filters`
+///
+/// `on_progress` is called every [`PROGRESS_INTERVAL`] or so with the
+/// fraction done (the position of the last packet read over the duration)
+/// and the video frames read so far and in total.  Setting `cancelled`
+/// stops the transcode with [`Error::Cancelled`].
+fn transcode_with_filters(
+    input: &Path,
+    output: &Path,
+    filters: &effects::FilterChain,
+    cancelled: &AtomicBool,
+    mut on_progress: impl FnMut(f64, u64, u64),
+) -> Result<()> {
+    let mut ictx = ffmpeg::format::input(input)?;
+    let mut octx = ffmpeg::format::output(output)?;
+    let global_header = octx
+        .format()
+        .flags()
+        .contains(ffmpeg::format::Flags::GLOBAL_HEADER);
+
+    let video_index = ictx
+        .streams()
+        .best(ffmpeg::media::Type::Video)
+        .map(|s| s.index());
+    let audio_index = ictx
+        .streams()
+        .best(ffmpeg::media::Type::Audio)
+        .map(|s| s.index());
+    if video_index.is_none() && filters.video.is_some() {
+        return Err(Error::Media("No video stream found".into()));
+    }
+    if audio_index.is_none() && filters.audio.is_some() {
+        return Err(Error::Media("No audio stream found".into()));
     }
 
-    let video_stream_index =
-        best_video_stream.ok_or_else(|| Error::Media("No video stream found".into()))?;
+    // Output stream of each input stream, and how its packets get there
+    let mut routes: Vec<Option<StreamRoute>> = (0..ictx.nb_streams()).map(|_| None).collect();
+    for ist in ictx.streams() {
+        let index = ist.index();
+        let filter = if Some(index) == video_index {
+            filters.video.as_deref()
+        } else if Some(index) == audio_index {
+            filters.audio.as_deref()
+        } else {
+            continue;
+        };
+        let output_index = octx.nb_streams() as usize;
+        routes[index] = Some(match filter {
+            Some(filter) if Some(index) == video_index => StreamRoute::Filter(Box::new(
+                FilteredStream::video(&ist, &mut octx, output_index, filter, global_header)?,
+            )),
+            Some(filter) => StreamRoute::Filter(Box::new(FilteredStream::audio(
+                &ist,
+                &mut octx,
+                output_index,
+                filter,
+                global_header,
+            )?)),
+            None => {
+                let mut ost = octx.add_stream(ffmpeg::encoder::find(ffmpeg::codec::Id::None))?;
+                ost.set_parameters(ist.parameters());
+                // The input container's codec tag may mean nothing in the
+                // output container; let the muxer pick one
+                unsafe {
+                    (*ost.parameters().as_mut_ptr()).codec_tag = 0;
+                }
+                StreamRoute::Copy {
+                    output_index,
+                    time_base: ist.time_base(),
+                }
+            }
+        });
+    }
 
-    let filter = format!("[in]{}[out]", filter_str);
-    let mut graph = ffmpeg::filter::Graph::new();
-    let stream = ictx.stream(video_stream_index).unwrap();
-    let params = stream.parameters();
-    let ctx = ffmpeg::codec::context::Context::from_parameters(params)?;
-    let decoder = ctx.decoder();
+    let duration = (ictx.duration() as f64 / ffmpeg::ffi::AV_TIME_BASE as f64).max(0.0);
+    let frames_total = video_index.map_or(0, |index| {
+        let stream = ictx.stream(index).unwrap();
+        if stream.frames() > 0 {
+            stream.frames() as u64
+        } else {
+            (duration * f64::from(stream.avg_frame_rate())).ceil() as u64
+        }
+    });
+    let mut frames_done = 0;
+    let mut last_report = Instant::now();
 
-    let (w, h) = if let Ok(v) = decoder.video() {
-        (v.width(), v.height())
-    } else {
-        (0, 0)
-    };
+    octx.write_header()?;
 
-    graph.add(
-        &ffmpeg::filter::find("buffer").unwrap(),
-        "in",
-        &format!(
-            "video_size={}x{}:pix_fmt={}:time_base={}:pixel_aspect={}",
-            w, h, "yuv420p", "1/25", "1/1"
-        ),
-    )?;
-    graph.add(&ffmpeg::filter::find("buffersink").unwrap(), "out", "")?;
-    graph.parse(&filter)?;
-    graph.validate()?;
+    for (stream, mut packet) in ictx.packets() {
+        if cancelled.load(Ordering::Relaxed) {
+            return Err(Error::Cancelled);
+        }
+        if Some(stream.index()) == video_index {
+            frames_done += 1;
+        }
+        if duration > 0.0 && last_report.elapsed() >= PROGRESS_INTERVAL {
+            if let Some(pts) = packet.pts() {
+                let start = match stream.start_time() {
+                    ffmpeg::ffi::AV_NOPTS_VALUE => 0,
+                    start => start,
+                };
+                let time = (pts - start) as f64 * f64::from(stream.time_base());
+                on_progress(
+                    (time / duration).clamp(0.0, 1.0),
+                    frames_done,
+                    frames_total.max(frames_done),
+                );
+                last_report = Instant::now();
+            }
+        }
 
-    octx.write_header()?;
+        match routes[stream.index()].as_mut() {
+            Some(StreamRoute::Filter(filtered)) => filtered.send_packet(&packet, &mut octx)?,
+            Some(StreamRoute::Copy {
+                output_index,
+                time_base,
+            }) => {
+                let output_time_base = octx.stream(*output_index).unwrap().time_base();
+                packet.rescale_ts(*time_base, output_time_base);
+                packet.set_position(-1);
+                packet.set_stream(*output_index);
+                packet.write_interleaved(&mut octx)?;
+            }
+            None => {}
+        }
+    }
 
-    for (stream, packet) in ictx.packets() {
-        if stream.index() == video_stream_index {
-            // How to apply filter graph? This is getting complicated.
-            // For a single command, it's easier to use std::process::Command
+    for route in routes.iter_mut().flatten() {
+        if let StreamRoute::Filter(filtered) = route {
+            filtered.finish(&mut octx)?;
         }
+    }
+    octx.write_trailer()?;
+    Ok(())
+}
 
-        let mut packet = packet;
-        packet.rescale_ts(
-            stream.time_base(),
-            octx.stream(stream_mapping[stream.index()])
-                .unwrap()
-                .time_base(),
+/// How packets of an input stream reach the output
+enum StreamRoute {
+    /// Decoded, filtered and encoded again
+    Filter(Box<FilteredStream>),
+    /// Copied as they are
+    Copy {
+        output_index: usize,
+        time_base: ffmpeg::Rational,
+    },
+}
+
+/// An input stream running through a filter graph into an encoder
+struct FilteredStream {
+    output_index: usize,
+    decoder: ffmpeg::decoder::Opened,
+    /// Graph with the source `in` and the sink `out`
+    graph: ffmpeg::filter::Graph,
+    encoder: ffmpeg::encoder::Encoder,
+    /// Time base of the frames leaving the graph
+    filtered_time_base: ffmpeg::Rational,
+    /// Time base the encoder stamps packets with
+    encoder_time_base: ffmpeg::Rational,
+}
+
+impl FilteredStream {
+    /// Filter a video stream with `filter` and encode it with H.264,
+    /// preferring libx264
+    fn video(
+        ist: &ffmpeg::Stream,
+        octx: &mut ffmpeg::format::context::Output,
+        output_index: usize,
+        filter: &str,
+        global_header: bool,
+    ) -> Result<Self> {
+        let decoder = ffmpeg::codec::context::Context::from_parameters(ist.parameters())?
+            .decoder()
+            .video()?;
+        let aspect = match decoder.aspect_ratio() {
+            r if r.numerator() > 0 => r,
+            _ => ffmpeg::Rational(1, 1),
+        };
+        let pixel_format: ffmpeg::ffi::AVPixelFormat = decoder.format().into();
+        let mut args = format!(
+            "video_size={}x{}:pix_fmt={}:time_base={}:pixel_aspect={}",
+            decoder.width(),
+            decoder.height(),
+            pixel_format as i32,
+            ist.time_base(),
+            aspect
         );
-        packet.set_stream(stream_mapping[stream.index()]);
-        packet.write_interleaved(&mut octx)?;
+        let frame_rate = ist.avg_frame_rate();
+        if frame_rate.numerator() > 0 && frame_rate.denominator() > 0 {
+            args.push_str(&format!(":frame_rate={}", frame_rate));
+        }
+
+        let mut graph = ffmpeg::filter::Graph::new();
+        graph.add(&find_filter("buffer")?, "in", &args)?;
+        let mut sink = graph.add(&find_filter("buffersink")?, "out", "")?;
+        sink.set_pixel_format(ffmpeg::format::Pixel::YUV420P);
+        graph.output("in", 0)?.input("out", 0)?.parse(filter)?;
+        graph.validate()?;
+
+        // The filter may resize or retime the video, so the encoder follows
+        // what leaves the graph rather than the input
+        let sink = graph.get("out").unwrap();
+        let (width, height, sample_aspect, frame_rate, filtered_time_base) = unsafe {
+            let ctx = sink.as_ptr();
+            (
+                ffmpeg::ffi::av_buffersink_get_w(ctx),
+                ffmpeg::ffi::av_buffersink_get_h(ctx),
+                ffmpeg::Rational::from(ffmpeg::ffi::av_buffersink_get_sample_aspect_ratio(ctx)),
+                ffmpeg::Rational::from(ffmpeg::ffi::av_buffersink_get_frame_rate(ctx)),
+                ffmpeg::Rational::from(ffmpeg::ffi::av_buffersink_get_time_base(ctx)),
+            )
+        };
+
+        let codec = ffmpeg::encoder::find_by_name("libx264")
+            .or_else(|| ffmpeg::encoder::find(ffmpeg::codec::Id::H264))
+            .ok_or_else(|| Error::FFmpeg("No H.264 encoder available".into()))?;
+        let mut ost = octx.add_stream(codec)?;
+        let mut encoder = ffmpeg::codec::context::Context::new_with_codec(codec)
+            .encoder()
+            .video()?;
+        encoder.set_width(width as u32);
+        encoder.set_height(height as u32);
+        encoder.set_format(ffmpeg::format::Pixel::YUV420P);
+        if sample_aspect.numerator() > 0 {
+            encoder.set_aspect_ratio(sample_aspect);
+        }
+        encoder.set_time_base(filtered_time_base);
+        if frame_rate.numerator() > 0 {
+            encoder.set_frame_rate(Some(frame_rate));
+        }
+        if global_header {
+            encoder.set_flags(ffmpeg::codec::Flags::GLOBAL_HEADER);
+        }
+        let mut options = ffmpeg::Dictionary::new();
+        options.set("preset", "medium");
+        options.set("crf", "20");
+        let encoder = encoder.open_with(options)?;
+        ost.set_parameters(&encoder);
+        ost.set_time_base(filtered_time_base);
+
+        Ok(Self {
+            output_index,
+            decoder: decoder.0,
+            graph,
+            encoder: encoder.0 .0,
+            filtered_time_base,
+            encoder_time_base: filtered_time_base,
+        })
     }
 
-    octx.write_trailer()?;
+    /// Filter an audio stream with `filter` and encode it with AAC at the
+    /// source sample rate and channel layout
+    fn audio(
+        ist: &ffmpeg::Stream,
+        octx: &mut ffmpeg::format::context::Output,
+        output_index: usize,
+        filter: &str,
+        global_header: bool,
+    ) -> Result<Self> {
+        let decoder = ffmpeg::codec::context::Context::from_parameters(ist.parameters())?
+            .decoder()
+            .audio()?;
+        let layout = match decoder.channel_layout() {
+            layout if layout.is_empty() || layout.bits() == 0 => {
+                ffmpeg::ChannelLayout::default(decoder.channels() as i32)
+            }
+            layout => layout,
+        };
+        let rate = decoder.rate();
+
+        let codec = ffmpeg::encoder::find(ffmpeg::codec::Id::AAC)
+            .ok_or_else(|| Error::FFmpeg("No AAC encoder available".into()))?;
+        let sample_format = codec
+            .audio()?
+            .formats()
+            .and_then(|mut formats| formats.next())
+            .unwrap_or(ffmpeg::format::Sample::F32(
+                ffmpeg::format::sample::Type::Planar,
+            ));
+
+        let mut graph = ffmpeg::filter::Graph::new();
+        graph.add(
+            &find_filter("abuffer")?,
+            "in",
+            &format!(
+                "time_base={}:sample_rate={}:sample_fmt={}:channel_layout=0x{:x}",
+                ist.time_base(),
+                rate,
+                decoder.format().name(),
+                layout.bits()
+            ),
+        )?;
+        let mut sink = graph.add(&find_filter("abuffersink")?, "out", "")?;
+        sink.set_sample_format(sample_format);
+        sink.set_channel_layout(layout);
+        sink.set_sample_rate(rate);
+        graph.output("in", 0)?.input("out", 0)?.parse(filter)?;
+        graph.validate()?;
+
+        let mut ost = octx.add_stream(codec)?;
+        let mut encoder = ffmpeg::codec::context::Context::new_with_codec(codec)
+            .encoder()
+            .audio()?;
+        encoder.set_rate(rate as i32);
+        encoder.set_channel_layout(layout);
+        encoder.set_format(sample_format);
+        encoder.set_bit_rate(192_000);
+        encoder.set_time_base((1, rate as i32));
+        if global_header {
+            encoder.set_flags(ffmpeg::codec::Flags::GLOBAL_HEADER);
+        }
+        let encoder = encoder.open()?;
+        ost.set_parameters(&encoder);
+        ost.set_time_base((1, rate as i32));
+
+        // AAC takes fixed-size frames, so the sink hands them out that way
+        let mut sink = graph.get("out").unwrap();
+        if !codec
+            .capabilities()
+            .contains(ffmpeg::codec::capabilities::Capabilities::VARIABLE_FRAME_SIZE)
+        {
+            sink.sink().set_frame_size(encoder.frame_size());
+        }
+        let filtered_time_base = sink.sink().time_base();
+
+        Ok(Self {
+            output_index,
+            decoder: decoder.0,
+            graph,
+            encoder: encoder.0 .0,
+            filtered_time_base,
+            encoder_time_base: ffmpeg::Rational(1, rate as i32),
+        })
+    }
+
+    /// Decode a packet and encode the frames it yields
+    fn send_packet(
+        &mut self,
+        packet: &ffmpeg::Packet,
+        octx: &mut ffmpeg::format::context::Output,
+    ) -> Result<()> {
+        self.decoder.send_packet(packet)?;
+        self.drain_decoder(octx)
+    }
+
+    /// Flush the decoder, the graph and the encoder
+    fn finish(&mut self, octx: &mut ffmpeg::format::context::Output) -> Result<()> {
+        self.decoder.send_eof()?;
+        self.drain_decoder(octx)?;
+        self.graph.get("in").unwrap().source().flush()?;
+        self.drain_graph(octx)?;
+        self.encoder.send_eof()?;
+        self.drain_encoder(octx)
+    }
 
-    // The above is complex. For now, let's just return a placeholder.
-    // The real implementation will be part of the render manager.
-    // This command is more for previewing single effects.
-
-    // For now, let's use the command line ffmpeg for simplicity.
-    let status = std::process::Command::new("ffmpeg")
-        .arg("-i")
-        .arg(&input_path)
-        .arg("-vf")
-        .arg(filter_str)
-        .arg("-y")
-        .arg(&output)
-        .status()?;
-
-    if !status.success() {
-        return Err(Error::FFmpeg("Failed to apply effect".into()));
+    fn drain_decoder(&mut self, octx: &mut ffmpeg::format::context::Output) -> Result<()> {
+        let mut frame = unsafe { ffmpeg::Frame::empty() };
+        while self.decoder.receive_frame(&mut frame).is_ok() {
+            let timestamp = frame.timestamp();
+            frame.set_pts(timestamp);
+            self.graph.get("in").unwrap().source().add(&frame)?;
+            self.drain_graph(octx)?;
+        }
+        Ok(())
+    }
+
+    fn drain_graph(&mut self, octx: &mut ffmpeg::format::context::Output) -> Result<()> {
+        loop {
+            // A fresh frame each time: the sink moves its data in without
+            // releasing what the frame held
+            let mut filtered = unsafe { ffmpeg::Frame::empty() };
+            if self
+                .graph
+                .get("out")
+                .unwrap()
+                .sink()
+                .frame(&mut filtered)
+                .is_err()
+            {
+                return Ok(());
+            }
+            if let Some(pts) = filtered.pts() {
+                filtered.set_pts(Some(
+                    pts.rescale(self.filtered_time_base, self.encoder_time_base),
+                ));
+            }
+            self.encoder.send_frame(&filtered)?;
+            self.drain_encoder(octx)?;
+        }
+    }
+
+    fn drain_encoder(&mut self, octx: &mut ffmpeg::format::context::Output) -> Result<()> {
+        let output_time_base = octx.stream(self.output_index).unwrap().time_base();
+        let mut packet = ffmpeg::Packet::empty();
+        while self.encoder.receive_packet(&mut packet).is_ok() {
+            packet.set_stream(self.output_index);
+            packet.rescale_ts(self.encoder_time_base, output_time_base);
+            packet.write_interleaved(octx)?;
+        }
+        Ok(())
     }
+}
 
-    Ok(output.to_string_lossy().to_string())
+/// Size, frame rate and kind of the best video stream of `path`
+fn input_format(path: &Path) -> Result<effects::InputFormat> {
+    let info = MediaAnalyzer::new(path)?.get_info()?;
+    let video = info
+        .video
+        .ok_or_else(|| Error::InvalidFormat("Input has no video stream".to_string()))?;
+    Ok(effects::InputFormat {
+        width: video.width,
+        height: video.height,
+        frame_rate: video.frame_rate,
+        still: effects::InputFormat::is_still(&info.format, info.duration),
+    })
+}
+
+fn find_filter(name: &str) -> Result<ffmpeg::filter::Filter> {
+    ffmpeg::filter::find(name)
+        .ok_or_else(|| Error::FFmpeg(format!("FFmpeg filter {} is not available", name)))
 }
 
 /// Get all available effects
@@ -184,6 +669,7 @@ pub fn get_available_effects() -> Vec<EffectDefinition> {
                 min: Some(0.0),
                 max: Some(2.0),
                 step: Some(0.01),
+                options: None,
             }],
         },
         EffectDefinition {
@@ -199,6 +685,7 @@ pub fn get_available_effects() -> Vec<EffectDefinition> {
                 min: Some(0.0),
                 max: Some(2.0),
                 step: Some(0.01),
+                options: None,
             }],
         },
         EffectDefinition {
@@ -214,6 +701,7 @@ pub fn get_available_effects() -> Vec<EffectDefinition> {
                 min: Some(0.0),
                 max: Some(2.0),
                 step: Some(0.01),
+                options: None,
             }],
         },
         EffectDefinition {
@@ -229,6 +717,41 @@ pub fn get_available_effects() -> Vec<EffectDefinition> {
                 min: Some(-180.0),
                 max: Some(180.0),
                 step: Some(1.0),
+                options: None,
+            }],
+        },
+        EffectDefinition {
+            id: "lut".to_string(),
+            name: "LUT".to_string(),
+            description: "Apply a 3D color lookup table (.cube or .3dl)".to_string(),
+            category: EffectCategory::Color,
+            parameters: vec![EffectParameter {
+                name: "path".to_string(),
+                display_name: "LUT File".to_string(),
+                param_type: ParameterType::File,
+                default_value: serde_json::json!(""),
+                min: None,
+                max: None,
+                step: None,
+                options: None,
+            }],
+        },
+        EffectDefinition {
+            id: "lut3d".to_string(),
+            name: "3D LUT".to_string(),
+            description: "Apply a .cube or .3dl color lookup table with FFmpeg's lut3d \
+                          filter"
+                .to_string(),
+            category: EffectCategory::Color,
+            parameters: vec![EffectParameter {
+                name: "lut_path".to_string(),
+                display_name: "LUT File".to_string(),
+                param_type: ParameterType::File,
+                default_value: serde_json::json!(""),
+                min: None,
+                max: None,
+                step: None,
+                options: None,
             }],
         },
         // Blur effects
@@ -245,6 +768,7 @@ pub fn get_available_effects() -> Vec<EffectDefinition> {
                 min: Some(0.0),
                 max: Some(50.0),
                 step: Some(0.1),
+                options: None,
             }],
         },
         EffectDefinition {
@@ -260,9 +784,293 @@ pub fn get_available_effects() -> Vec<EffectDefinition> {
                 min: Some(0.0),
                 max: Some(2.0),
                 step: Some(0.01),
+                options: None,
+            }],
+        },
+        // Transform effects
+        EffectDefinition {
+            id: "crop".to_string(),
+            name: "Crop".to_string(),
+            description: "Crop to a rectangle. With Relative on, all values are \
+                          fractions (0-1) of the input size; otherwise they are pixels"
+                .to_string(),
+            category: EffectCategory::Transform,
+            parameters: vec![
+                EffectParameter {
+                    name: "x".to_string(),
+                    display_name: "X".to_string(),
+                    param_type: ParameterType::Number,
+                    default_value: serde_json::json!(0.0),
+                    min: Some(0.0),
+                    max: None,
+                    step: Some(0.01),
+                    options: None,
+                },
+                EffectParameter {
+                    name: "y".to_string(),
+                    display_name: "Y".to_string(),
+                    param_type: ParameterType::Number,
+                    default_value: serde_json::json!(0.0),
+                    min: Some(0.0),
+                    max: None,
+                    step: Some(0.01),
+                    options: None,
+                },
+            ],
+        },
+        EffectDefinition {
+            id: "flip-horizontal".to_string(),
+            name: "Flip Horizontal".to_string(),
+            description: "Mirror the image left to right".to_string(),
+            category: EffectCategory::Transform,
+            parameters: vec![],
+        },
+        EffectDefinition {
+            id: "flip-vertical".to_string(),
+            name: "Flip Vertical".to_string(),
+            description: "Flip the image upside down".to_string(),
+            category: EffectCategory::Transform,
+            parameters: vec![],
+        },
+        EffectDefinition {
+            id: "transpose".to_string(),
+            name: "Rotate 90°".to_string(),
+            description: "Rotate by 90°: 0 = counter-clockwise + flip, 1 = clockwise, \
+                          2 = counter-clockwise, 3 = clockwise + flip"
+                .to_string(),
+            category: EffectCategory::Transform,
+            parameters: vec![EffectParameter {
+                name: "direction".to_string(),
+                display_name: "Direction".to_string(),
+                param_type: ParameterType::Select,
+                default_value: serde_json::json!(1),
+                min: Some(0.0),
+                max: Some(3.0),
+                step: Some(1.0),
+                options: None,
             }],
         },
+        EffectDefinition {
+            id: "deinterlace".to_string(),
+            name: "Deinterlace".to_string(),
+            description: "Remove interlacing combs from DV and broadcast footage. \
+                          Field mode doubles the frame rate for smoother motion"
+                .to_string(),
+            category: EffectCategory::Transform,
+            parameters: vec![
+                EffectParameter {
+                    name: "algorithm".to_string(),
+                    display_name: "Algorithm".to_string(),
+                    param_type: ParameterType::Select,
+                    default_value: serde_json::json!("yadif"),
+                    min: None,
+                    max: None,
+                    step: None,
+                    options: select_options(&[("YADIF", "yadif"), ("BWDIF", "bwdif")]),
+                },
+                EffectParameter {
+                    name: "mode".to_string(),
+                    display_name: "Output".to_string(),
+                    param_type: ParameterType::Select,
+                    default_value: serde_json::json!("frame"),
+                    min: None,
+                    max: None,
+                    step: None,
+                    options: select_options(&[
+                        ("One frame per frame", "frame"),
+                        ("One frame per field", "field"),
+                    ]),
+                },
+            ],
+        },
+        EffectDefinition {
+            id: "lens_correct".to_string(),
+            name: "Lens Correction".to_string(),
+            description: "Correct lens distortion with radial coefficients K1 and K2. \
+                          Negative values fix barrel distortion from wide-angle lenses, \
+                          positive values fix pincushion distortion"
+                .to_string(),
+            category: EffectCategory::Transform,
+            parameters: vec![
+                EffectParameter {
+                    name: "k1".to_string(),
+                    display_name: "K1".to_string(),
+                    param_type: ParameterType::Number,
+                    default_value: serde_json::json!(0.0),
+                    min: Some(-1.0),
+                    max: Some(1.0),
+                    step: Some(0.01),
+                    options: None,
+                },
+                EffectParameter {
+                    name: "k2".to_string(),
+                    display_name: "K2".to_string(),
+                    param_type: ParameterType::Number,
+                    default_value: serde_json::json!(0.0),
+                    min: Some(-1.0),
+                    max: Some(1.0),
+                    step: Some(0.01),
+                    options: None,
+                },
+            ],
+        },
+        EffectDefinition {
+            id: "fisheye".to_string(),
+            name: "Fisheye Correction".to_string(),
+            description: "Remove the barrel distortion of GoPro Hero footage shot in \
+                          the wide field of view"
+                .to_string(),
+            category: EffectCategory::Transform,
+            parameters: vec![],
+        },
+        EffectDefinition {
+            id: "zoompan".to_string(),
+            name: "Zoom & Pan".to_string(),
+            description: "Slow Ken Burns zoom from Zoom Start to Zoom End over the \
+                          given number of frames, for stills or video. Pan 0.5 keeps it \
+                          centered"
+                .to_string(),
+            category: EffectCategory::Transform,
+            parameters: vec![
+                EffectParameter {
+                    name: "zoom_start".to_string(),
+                    display_name: "Zoom Start".to_string(),
+                    param_type: ParameterType::Number,
+                    default_value: serde_json::json!(1.0),
+                    min: Some(1.0),
+                    max: Some(10.0),
+                    step: Some(0.05),
+                    options: None,
+                },
+                EffectParameter {
+                    name: "zoom_end".to_string(),
+                    display_name: "Zoom End".to_string(),
+                    param_type: ParameterType::Number,
+                    default_value: serde_json::json!(1.2),
+                    min: Some(1.0),
+                    max: Some(10.0),
+                    step: Some(0.05),
+                    options: None,
+                },
+                EffectParameter {
+                    name: "pan_x".to_string(),
+                    display_name: "Pan X".to_string(),
+                    param_type: ParameterType::Number,
+                    default_value: serde_json::json!(0.5),
+                    min: Some(0.0),
+                    max: Some(1.0),
+                    step: Some(0.01),
+                    options: None,
+                },
+                EffectParameter {
+                    name: "pan_y".to_string(),
+                    display_name: "Pan Y".to_string(),
+                    param_type: ParameterType::Number,
+                    default_value: serde_json::json!(0.5),
+                    min: Some(0.0),
+                    max: Some(1.0),
+                    step: Some(0.01),
+                    options: None,
+                },
+                EffectParameter {
+                    name: "duration_frames".to_string(),
+                    display_name: "Duration (frames)".to_string(),
+                    param_type: ParameterType::Number,
+                    default_value: serde_json::json!(125),
+                    min: Some(1.0),
+                    max: None,
+                    step: Some(1.0),
+                    options: None,
+                },
+                EffectParameter {
+                    name: "width".to_string(),
+                    display_name: "Width".to_string(),
+                    param_type: ParameterType::Number,
+                    default_value: serde_json::json!(1920),
+                    min: Some(16.0),
+                    max: Some(8192.0),
+                    step: Some(2.0),
+                    options: None,
+                },
+                EffectParameter {
+                    name: "height".to_string(),
+                    display_name: "Height".to_string(),
+                    param_type: ParameterType::Number,
+                    default_value: serde_json::json!(1080),
+                    min: Some(16.0),
+                    max: Some(8192.0),
+                    step: Some(2.0),
+                    options: None,
+                },
+                EffectParameter {
+                    name: "fps".to_string(),
+                    display_name: "FPS".to_string(),
+                    param_type: ParameterType::Number,
+                    default_value: serde_json::json!(30),
+                    min: Some(1.0),
+                    max: Some(240.0),
+                    step: Some(1.0),
+                    options: None,
+                },
+            ],
+        },
         // Stylize effects
+        EffectDefinition {
+            id: "sepia".to_string(),
+            name: "Sepia".to_string(),
+            description: "Apply a warm sepia tone".to_string(),
+            category: EffectCategory::Stylize,
+            parameters: vec![EffectParameter {
+                name: "intensity".to_string(),
+                display_name: "Intensity".to_string(),
+                param_type: ParameterType::Number,
+                default_value: serde_json::json!(0.5),
+                min: Some(0.0),
+                max: Some(1.0),
+                step: Some(0.01),
+                options: None,
+            }],
+        },
+        EffectDefinition {
+            id: "split_tone".to_string(),
+            name: "Split Tone".to_string(),
+            description: "Tint shadows and highlights with different colors. Balance \
+                          moves the split towards the shadows or highlights"
+                .to_string(),
+            category: EffectCategory::Stylize,
+            parameters: vec![
+                EffectParameter {
+                    name: "highlight_color".to_string(),
+                    display_name: "Highlights".to_string(),
+                    param_type: ParameterType::Color,
+                    default_value: serde_json::json!("#ffb060"),
+                    min: None,
+                    max: None,
+                    step: None,
+                    options: None,
+                },
+                EffectParameter {
+                    name: "shadow_color".to_string(),
+                    display_name: "Shadows".to_string(),
+                    param_type: ParameterType::Color,
+                    default_value: serde_json::json!("#3070c0"),
+                    min: None,
+                    max: None,
+                    step: None,
+                    options: None,
+                },
+                EffectParameter {
+                    name: "balance".to_string(),
+                    display_name: "Balance".to_string(),
+                    param_type: ParameterType::Number,
+                    default_value: serde_json::json!(0.0),
+                    min: Some(-1.0),
+                    max: Some(1.0),
+                    step: Some(0.01),
+                    options: None,
+                },
+            ],
+        },
         EffectDefinition {
             id: "vignette".to_string(),
             name: "Vignette".to_string(),
@@ -277,6 +1085,7 @@ pub fn get_available_effects() -> Vec<EffectDefinition> {
                     min: Some(0.0),
                     max: Some(1.0),
                     step: Some(0.01),
+                    options: None,
                 },
                 EffectParameter {
                     name: "radius".to_string(),
@@ -286,23 +1095,500 @@ pub fn get_available_effects() -> Vec<EffectDefinition> {
                     min: Some(0.1),
                     max: Some(1.0),
                     step: Some(0.01),
+                    options: None,
+                },
+            ],
+        },
+        EffectDefinition {
+            id: "denoise".to_string(),
+            name: "Denoise".to_string(),
+            description: "Reduce video noise. HQDN3D is fast; NLMeans is slow but \
+                          keeps more detail; ATADenoise suits temporal noise"
+                .to_string(),
+            category: EffectCategory::Stylize,
+            parameters: vec![
+                EffectParameter {
+                    name: "algorithm".to_string(),
+                    display_name: "Algorithm".to_string(),
+                    param_type: ParameterType::Select,
+                    default_value: serde_json::json!("hqdn3d"),
+                    min: None,
+                    max: None,
+                    step: None,
+                    options: select_options(&[
+                        ("HQDN3D", "hqdn3d"),
+                        ("NLMeans", "nlmeans"),
+                        ("ATADenoise", "atadenoise"),
+                    ]),
+                },
+                EffectParameter {
+                    name: "strength".to_string(),
+                    display_name: "Strength".to_string(),
+                    param_type: ParameterType::Number,
+                    default_value: serde_json::json!(0.5),
+                    min: Some(0.0),
+                    max: Some(1.0),
+                    step: Some(0.01),
+                    options: None,
+                },
+            ],
+        },
+        EffectDefinition {
+            id: "denoise_nl".to_string(),
+            name: "Denoise (NLMeans)".to_string(),
+            description: "Non-local means denoising with full control. Larger patch and \
+                          research sizes remove more noise but are much slower"
+                .to_string(),
+            category: EffectCategory::Stylize,
+            parameters: vec![
+                EffectParameter {
+                    name: "sigma".to_string(),
+                    display_name: "Strength".to_string(),
+                    param_type: ParameterType::Number,
+                    default_value: serde_json::json!(1.0),
+                    min: Some(1.0),
+                    max: Some(30.0),
+                    step: Some(0.1),
+                    options: None,
+                },
+                EffectParameter {
+                    name: "patch".to_string(),
+                    display_name: "Patch Size".to_string(),
+                    param_type: ParameterType::Number,
+                    default_value: serde_json::json!(7),
+                    min: Some(1.0),
+                    max: Some(99.0),
+                    step: Some(2.0),
+                    options: None,
+                },
+                EffectParameter {
+                    name: "research".to_string(),
+                    display_name: "Research Size".to_string(),
+                    param_type: ParameterType::Number,
+                    default_value: serde_json::json!(15),
+                    min: Some(1.0),
+                    max: Some(99.0),
+                    step: Some(2.0),
+                    options: None,
                 },
             ],
         },
+        EffectDefinition {
+            id: "text".to_string(),
+            name: "Text".to_string(),
+            description: "Draw text over the video. X and Y place it within the frame \
+                          (0.5 centers it); Font is optional"
+                .to_string(),
+            category: EffectCategory::Stylize,
+            parameters: text_parameters(),
+        },
+        EffectDefinition {
+            id: "text_overlay".to_string(),
+            name: "Text Overlay".to_string(),
+            description: "Text that can be switched off without losing its settings. X \
+                          and Y place it within the frame (0.5 centers it); Font is \
+                          optional"
+                .to_string(),
+            category: EffectCategory::Stylize,
+            parameters: {
+                let mut parameters = vec![EffectParameter {
+                    name: "enabled".to_string(),
+                    display_name: "Enabled".to_string(),
+                    param_type: ParameterType::Boolean,
+                    default_value: serde_json::json!(true),
+                    min: None,
+                    max: None,
+                    step: None,
+                    options: None,
+                }];
+                parameters.extend(text_parameters());
+                parameters
+            },
+        },
+        EffectDefinition {
+            id: "pixelate".to_string(),
+            name: "Pixelate".to_string(),
+            description: "Break the picture into square blocks (mosaic)".to_string(),
+            category: EffectCategory::Stylize,
+            parameters: vec![EffectParameter {
+                name: "block_size".to_string(),
+                display_name: "Block Size".to_string(),
+                param_type: ParameterType::Number,
+                default_value: serde_json::json!(16),
+                min: Some(2.0),
+                max: Some(128.0),
+                step: Some(1.0),
+                options: None,
+            }],
+        },
+        EffectDefinition {
+            id: "chromatic_aberration".to_string(),
+            name: "Chromatic Aberration".to_string(),
+            description: "Shift the red and blue channels by separate pixel offsets \
+                          for a colour fringing look"
+                .to_string(),
+            category: EffectCategory::Stylize,
+            parameters: vec![
+                EffectParameter {
+                    name: "rh".to_string(),
+                    display_name: "Red Horizontal".to_string(),
+                    param_type: ParameterType::Number,
+                    default_value: serde_json::json!(3),
+                    min: Some(-20.0),
+                    max: Some(20.0),
+                    step: Some(1.0),
+                    options: None,
+                },
+                EffectParameter {
+                    name: "rv".to_string(),
+                    display_name: "Red Vertical".to_string(),
+                    param_type: ParameterType::Number,
+                    default_value: serde_json::json!(0),
+                    min: Some(-20.0),
+                    max: Some(20.0),
+                    step: Some(1.0),
+                    options: None,
+                },
+                EffectParameter {
+                    name: "bh".to_string(),
+                    display_name: "Blue Horizontal".to_string(),
+                    param_type: ParameterType::Number,
+                    default_value: serde_json::json!(-3),
+                    min: Some(-20.0),
+                    max: Some(20.0),
+                    step: Some(1.0),
+                    options: None,
+                },
+                EffectParameter {
+                    name: "bv".to_string(),
+                    display_name: "Blue Vertical".to_string(),
+                    param_type: ParameterType::Number,
+                    default_value: serde_json::json!(0),
+                    min: Some(-20.0),
+                    max: Some(20.0),
+                    step: Some(1.0),
+                    options: None,
+                },
+            ],
+        },
+        EffectDefinition {
+            id: "fringe".to_string(),
+            name: "Color Fringe".to_string(),
+            description: "Pull red and blue apart sideways by Amount pixels".to_string(),
+            category: EffectCategory::Stylize,
+            parameters: vec![EffectParameter {
+                name: "amount".to_string(),
+                display_name: "Amount".to_string(),
+                param_type: ParameterType::Number,
+                default_value: serde_json::json!(3),
+                min: Some(0.0),
+                max: Some(20.0),
+                step: Some(1.0),
+                options: None,
+            }],
+        },
         EffectDefinition {
             id: "grain".to_string(),
             name: "Film Grain".to_string(),
-            description: "Add film grain noise".to_string(),
+            description: "Add film grain noise. Temporal grain changes every frame; \
+                          Color adds noise to the chroma as well as the brightness"
+                .to_string(),
             category: EffectCategory::Stylize,
+            parameters: vec![
+                EffectParameter {
+                    name: "intensity".to_string(),
+                    display_name: "Intensity".to_string(),
+                    param_type: ParameterType::Number,
+                    default_value: serde_json::json!(0.0),
+                    min: Some(0.0),
+                    max: Some(1.0),
+                    step: Some(0.01),
+                    options: None,
+                },
+                EffectParameter {
+                    name: "temporal".to_string(),
+                    display_name: "Temporal".to_string(),
+                    param_type: ParameterType::Boolean,
+                    default_value: serde_json::json!(true),
+                    min: None,
+                    max: None,
+                    step: None,
+                    options: None,
+                },
+                EffectParameter {
+                    name: "color".to_string(),
+                    display_name: "Color".to_string(),
+                    param_type: ParameterType::Boolean,
+                    default_value: serde_json::json!(true),
+                    min: None,
+                    max: None,
+                    step: None,
+                    options: None,
+                },
+            ],
+        },
+        EffectDefinition {
+            id: "chromakey".to_string(),
+            name: "Chroma Key".to_string(),
+            description: "Make a solid background color transparent (green screen)".to_string(),
+            category: EffectCategory::Stylize,
+            parameters: vec![
+                EffectParameter {
+                    name: "color".to_string(),
+                    display_name: "Key Color".to_string(),
+                    param_type: ParameterType::Color,
+                    default_value: serde_json::json!("#00ff00"),
+                    min: None,
+                    max: None,
+                    step: None,
+                    options: None,
+                },
+                EffectParameter {
+                    name: "similarity".to_string(),
+                    display_name: "Similarity".to_string(),
+                    param_type: ParameterType::Number,
+                    default_value: serde_json::json!(0.1),
+                    min: Some(0.0),
+                    max: Some(1.0),
+                    step: Some(0.01),
+                    options: None,
+                },
+                EffectParameter {
+                    name: "blend".to_string(),
+                    display_name: "Blend".to_string(),
+                    param_type: ParameterType::Number,
+                    default_value: serde_json::json!(0.0),
+                    min: Some(0.0),
+                    max: Some(1.0),
+                    step: Some(0.01),
+                    options: None,
+                },
+            ],
+        },
+        EffectDefinition {
+            id: "speed".to_string(),
+            name: "Speed".to_string(),
+            description: "Slow motion or time-lapse; the audio is retimed to match \
+                          without changing its pitch"
+                .to_string(),
+            category: EffectCategory::Transform,
             parameters: vec![EffectParameter {
-                name: "intensity".to_string(),
-                display_name: "Intensity".to_string(),
+                name: "speed".to_string(),
+                display_name: "Speed".to_string(),
                 param_type: ParameterType::Number,
-                default_value: serde_json::json!(0.0),
-                min: Some(0.0),
-                max: Some(1.0),
+                default_value: serde_json::json!(1.0),
+                min: Some(0.25),
+                max: Some(4.0),
                 step: Some(0.01),
+                options: None,
+            }],
+        },
+        // Audio effects
+        EffectDefinition {
+            id: "audio-gain".to_string(),
+            name: "Gain".to_string(),
+            description: "Boost or cut the audio volume".to_string(),
+            category: EffectCategory::Audio,
+            parameters: vec![EffectParameter {
+                name: "gain".to_string(),
+                display_name: "Gain (dB)".to_string(),
+                param_type: ParameterType::Number,
+                default_value: serde_json::json!(0.0),
+                min: Some(-60.0),
+                max: Some(30.0),
+                step: Some(0.5),
+                options: None,
+            }],
+        },
+        EffectDefinition {
+            id: "loudnorm".to_string(),
+            name: "Loudness Normalization".to_string(),
+            description: "Normalize loudness to a target level (EBU R128)".to_string(),
+            category: EffectCategory::Audio,
+            parameters: vec![
+                EffectParameter {
+                    name: "integrated".to_string(),
+                    display_name: "Integrated Loudness (LUFS)".to_string(),
+                    param_type: ParameterType::Number,
+                    default_value: serde_json::json!(-16.0),
+                    min: Some(-70.0),
+                    max: Some(-5.0),
+                    step: Some(0.5),
+                    options: None,
+                },
+                EffectParameter {
+                    name: "range".to_string(),
+                    display_name: "Loudness Range (LU)".to_string(),
+                    param_type: ParameterType::Number,
+                    default_value: serde_json::json!(11.0),
+                    min: Some(1.0),
+                    max: Some(20.0),
+                    step: Some(0.5),
+                    options: None,
+                },
+                EffectParameter {
+                    name: "true_peak".to_string(),
+                    display_name: "True Peak (dBTP)".to_string(),
+                    param_type: ParameterType::Number,
+                    default_value: serde_json::json!(-1.5),
+                    min: Some(-9.0),
+                    max: Some(0.0),
+                    step: Some(0.1),
+                    options: None,
+                },
+                EffectParameter {
+                    name: "dual_mono".to_string(),
+                    display_name: "Dual Mono".to_string(),
+                    param_type: ParameterType::Boolean,
+                    default_value: serde_json::json!(false),
+                    min: None,
+                    max: None,
+                    step: None,
+                    options: None,
+                },
+            ],
+        },
+        EffectDefinition {
+            id: "atempo".to_string(),
+            name: "Tempo".to_string(),
+            description: "Change the audio speed without changing its pitch".to_string(),
+            category: EffectCategory::Audio,
+            parameters: vec![EffectParameter {
+                name: "factor".to_string(),
+                display_name: "Factor".to_string(),
+                param_type: ParameterType::Number,
+                default_value: serde_json::json!(1.0),
+                min: Some(0.25),
+                max: Some(4.0),
+                step: Some(0.05),
+                options: None,
             }],
         },
+        EffectDefinition {
+            id: "pitch".to_string(),
+            name: "Pitch Shift".to_string(),
+            description: "Shift the pitch by semitones without changing the duration. \
+                          Set Sample Rate to the file's audio sample rate"
+                .to_string(),
+            category: EffectCategory::Audio,
+            parameters: vec![
+                EffectParameter {
+                    name: "semitones".to_string(),
+                    display_name: "Semitones".to_string(),
+                    param_type: ParameterType::Number,
+                    default_value: serde_json::json!(0.0),
+                    min: Some(-12.0),
+                    max: Some(12.0),
+                    step: Some(1.0),
+                    options: None,
+                },
+                EffectParameter {
+                    name: "sample_rate".to_string(),
+                    display_name: "Sample Rate (Hz)".to_string(),
+                    param_type: ParameterType::Number,
+                    default_value: serde_json::json!(44100),
+                    min: Some(8000.0),
+                    max: Some(192000.0),
+                    step: Some(1.0),
+                    options: None,
+                },
+            ],
+        },
     ]
 }
+
+/// Parameters of the `text` and `text_overlay` effects
+fn text_parameters() -> Vec<EffectParameter> {
+    vec![
+        EffectParameter {
+            name: "text".to_string(),
+            display_name: "Text".to_string(),
+            param_type: ParameterType::Text,
+            default_value: serde_json::json!("Text"),
+            min: None,
+            max: None,
+            step: None,
+            options: None,
+        },
+        EffectParameter {
+            name: "font_size".to_string(),
+            display_name: "Font Size".to_string(),
+            param_type: ParameterType::Number,
+            default_value: serde_json::json!(48),
+            min: Some(8.0),
+            max: Some(400.0),
+            step: Some(1.0),
+            options: None,
+        },
+        EffectParameter {
+            name: "color".to_string(),
+            display_name: "Color".to_string(),
+            param_type: ParameterType::Color,
+            default_value: serde_json::json!("#ffffff"),
+            min: None,
+            max: None,
+            step: None,
+            options: None,
+        },
+        EffectParameter {
+            name: "x".to_string(),
+            display_name: "X".to_string(),
+            param_type: ParameterType::Number,
+            default_value: serde_json::json!(0.5),
+            min: Some(0.0),
+            max: Some(1.0),
+            step: Some(0.01),
+            options: None,
+        },
+        EffectParameter {
+            name: "y".to_string(),
+            display_name: "Y".to_string(),
+            param_type: ParameterType::Number,
+            default_value: serde_json::json!(0.9),
+            min: Some(0.0),
+            max: Some(1.0),
+            step: Some(0.01),
+            options: None,
+        },
+        EffectParameter {
+            name: "font_file".to_string(),
+            display_name: "Font".to_string(),
+            param_type: ParameterType::File,
+            default_value: serde_json::json!(""),
+            min: None,
+            max: None,
+            step: None,
+            options: None,
+        },
+    ]
+}
+
+/// Build `Select` options from `(label, value)` pairs
+fn select_options(pairs: &[(&str, &str)]) -> Option<Vec<SelectOption>> {
+    Some(
+        pairs
+            .iter()
+            .map(|(label, value)| SelectOption {
+                label: label.to_string(),
+                value: value.to_string(),
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn audio_category_matches_the_filter_routing() {
+        for definition in get_available_effects() {
+            assert_eq!(
+                matches!(definition.category, EffectCategory::Audio),
+                effects::is_audio_effect(&definition.id),
+                "category of {}",
+                definition.id
+            );
+        }
+    }
+}
@@ -1,25 +1,78 @@
 //! Media-related Tauri commands
 
-use crate::media::{MediaAnalyzer, MediaInfo, WaveformData};
-use crate::Result;
+use crate::media::{
+    AudioExtractOptions, AudioFileFormat, ExtractedAudio, FrameTiming, IntegrityReport,
+    InterlaceReport, MediaAnalyzer, MediaInfo, MediaInfoCache, ProxyCodec, ProxyResult,
+    QualityThresholds, SceneCut, TempoAnalysis, ThumbnailFit, VideoQualityReport, WaveformCache,
+    WaveformData,
+};
+use crate::project::{AudioAsset, ProjectManager};
+use crate::{Error, Result};
 use std::path::PathBuf;
-use tauri::command;
+use std::time::Duration;
+use tauri::{command, AppHandle, Emitter};
+use uuid::Uuid;
 
-/// Get detailed information about a media file
+/// Get detailed information about a media file or http(s)/rtsp URL
+///
+/// `probe_timeout` (seconds) limits how long a remote URL may take to
+/// respond before the command fails.  Local files are cached until they
+/// change (see `invalidate_media_cache`).
 #[command]
-pub async fn get_media_info(path: String) -> Result<MediaInfo> {
+pub async fn get_media_info(path: String, probe_timeout: Option<f64>) -> Result<MediaInfo> {
+    let path = PathBuf::from(&path);
+    if let Some(info) = MediaInfoCache::load(&path) {
+        return Ok(info);
+    }
+
+    let mut analyzer = MediaAnalyzer::new(&path)?;
+    if let Some(seconds) = probe_timeout.filter(|s| s.is_finite() && *s > 0.0) {
+        analyzer = analyzer.with_probe_timeout(Duration::from_secs_f64(seconds));
+    }
+    let info = analyzer.get_info()?;
+    if let Err(e) = MediaInfoCache::store(&path, &info) {
+        log::warn!("Failed to cache media info for {}: {}", path.display(), e);
+    }
+    Ok(info)
+}
+
+/// Forget the cached media info of a file, so the next `get_media_info`
+/// probes it again
+#[command]
+pub async fn invalidate_media_cache(path: String) -> Result<()> {
+    MediaInfoCache::invalidate(&PathBuf::from(&path))
+}
+
+/// Forget all cached media info
+#[command]
+pub async fn clear_media_cache() -> Result<()> {
+    MediaInfoCache::clear()
+}
+
+/// Get a content fingerprint for a media file, for relinking moved assets
+#[command]
+pub async fn get_media_fingerprint(path: String) -> Result<String> {
     let path = PathBuf::from(&path);
     let analyzer = MediaAnalyzer::new(&path)?;
-    analyzer.get_info()
+
+    // Hashes up to 12 MB of the file
+    tauri::async_runtime::spawn_blocking(move || analyzer.fingerprint())
+        .await
+        .map_err(|e| Error::Internal(format!("Fingerprint task failed: {}", e)))?
 }
 
 /// Extract a thumbnail from a video at a specific timestamp
+///
+/// `fit` is "stretch" (default), "contain" (letterboxed with `background`,
+/// a `#RRGGBB` color defaulting to black) or "cover" (center cropped).
 #[command]
 pub async fn extract_thumbnail(
     path: String,
     timestamp: f64,
     width: Option<u32>,
     height: Option<u32>,
+    fit: Option<ThumbnailFit>,
+    background: Option<String>,
 ) -> Result<String> {
     let path = PathBuf::from(&path);
     let analyzer = MediaAnalyzer::new(&path)?;
@@ -27,18 +80,426 @@ pub async fn extract_thumbnail(
     // Default to 320x180 if not specified
     let width = width.unwrap_or(320);
     let height = height.unwrap_or(180);
+    let background = match background {
+        Some(color) => parse_rgb(&color)?,
+        None => [0, 0, 0],
+    };
 
-    analyzer.extract_thumbnail(timestamp, width, height)
+    analyzer.extract_thumbnail(
+        timestamp,
+        width,
+        height,
+        fit.unwrap_or_default(),
+        background,
+    )
+}
+
+/// Extract thumbnails at several timestamps in one pass over the file
+///
+/// Returns data URLs in the order of `timestamps`.  Takes the same options as
+/// [`extract_thumbnail`] and is much faster than calling it repeatedly.
+#[command]
+pub async fn extract_thumbnails(
+    path: String,
+    timestamps: Vec<f64>,
+    width: Option<u32>,
+    height: Option<u32>,
+    fit: Option<ThumbnailFit>,
+    background: Option<String>,
+) -> Result<Vec<String>> {
+    let analyzer = MediaAnalyzer::new(&PathBuf::from(&path))?;
+    let width = width.unwrap_or(320);
+    let height = height.unwrap_or(180);
+    let background = match background {
+        Some(color) => parse_rgb(&color)?,
+        None => [0, 0, 0],
+    };
+    let fit = fit.unwrap_or_default();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        analyzer.extract_thumbnails(&timestamps, width, height, fit, background)
+    })
+    .await
+    .map_err(|e| Error::Internal(format!("Thumbnail task failed: {}", e)))?
+}
+
+/// Tile evenly spaced thumbnails into one PNG sprite sheet for scrubbing
+/// previews, returned as a data URL
+///
+/// The grid defaults to 10x10 tiles of 160x90.
+#[command]
+pub async fn extract_storyboard(
+    path: String,
+    cols: Option<u32>,
+    rows: Option<u32>,
+    thumb_width: Option<u32>,
+    thumb_height: Option<u32>,
+) -> Result<String> {
+    let analyzer = MediaAnalyzer::new(&PathBuf::from(&path))?;
+    let cols = cols.unwrap_or(10);
+    let rows = rows.unwrap_or(10);
+    let thumb_width = thumb_width.unwrap_or(160);
+    let thumb_height = thumb_height.unwrap_or(90);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        analyzer.extract_storyboard(cols, rows, thumb_width, thumb_height)
+    })
+    .await
+    .map_err(|e| Error::Internal(format!("Storyboard task failed: {}", e)))?
+}
+
+/// Encode a short silent preview clip for hovering over an asset
+///
+/// The clip joins segments from 20%, 50% and 80% of the source, so it is
+/// the same every time.  `output_path` ends in `.webp` (an animated,
+/// looping WebP) or `.mp4`.  `duration` (seconds, default 2) is the total
+/// length and `width` (pixels, default 320) the frame width.  Returns the
+/// output path.
+#[command]
+pub async fn generate_preview_clip(
+    path: String,
+    output_path: String,
+    duration: Option<f64>,
+    width: Option<u32>,
+) -> Result<String> {
+    let duration = duration.unwrap_or(2.0).clamp(0.5, 10.0);
+    // Encoders need an even width
+    let width = width.unwrap_or(320).clamp(32, 1920) / 2 * 2;
+    crate::media::generate_preview_clip(
+        &PathBuf::from(&path),
+        &PathBuf::from(&output_path),
+        duration,
+        width,
+    )
+    .await?;
+    Ok(output_path)
+}
+
+/// Parse a `#RRGGBB` color
+fn parse_rgb(color: &str) -> Result<[u8; 3]> {
+    let hex = color.trim().trim_start_matches('#');
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(Error::InvalidFormat(format!("Invalid color: {}", color)));
+    }
+    let value = u32::from_str_radix(hex, 16).unwrap_or(0);
+    Ok([(value >> 16) as u8, (value >> 8) as u8, value as u8])
+}
+
+/// Extract embedded cover art (e.g. from MP3, FLAC or M4A files)
+///
+/// Returns `None` when the file has no artwork.  With `max_size`, larger art
+/// is downscaled to fit a `max_size` square.
+#[command]
+pub async fn extract_cover_art(path: String, max_size: Option<u32>) -> Result<Option<String>> {
+    let path = PathBuf::from(&path);
+    let analyzer = MediaAnalyzer::new(&path)?;
+
+    tauri::async_runtime::spawn_blocking(move || analyzer.extract_cover_art(max_size))
+        .await
+        .map_err(|e| Error::Internal(format!("Cover art task failed: {}", e)))?
+}
+
+/// Extract a text subtitle stream to an SRT file, e.g. for a caption track
+///
+/// Returns the number of cues written.
+#[command]
+pub async fn extract_subtitles(
+    path: String,
+    stream_index: usize,
+    output_path: String,
+) -> Result<usize> {
+    let path = PathBuf::from(&path);
+    let analyzer = MediaAnalyzer::new(&path)?;
+    let output_path = PathBuf::from(output_path);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        analyzer.extract_subtitles(stream_index, &output_path)
+    })
+    .await
+    .map_err(|e| Error::Internal(format!("Subtitle task failed: {}", e)))?
 }
 
 /// Extract audio waveform data for visualization
+///
+/// `stream_index` selects a specific audio stream; the best one is used when
+/// omitted.  Results are cached on disk until the source file changes.
 #[command]
-pub async fn extract_audio_waveform(path: String, samples: Option<usize>) -> Result<WaveformData> {
+pub async fn extract_audio_waveform(
+    path: String,
+    samples: Option<usize>,
+    stream_index: Option<usize>,
+) -> Result<WaveformData> {
     let path = PathBuf::from(&path);
     let analyzer = MediaAnalyzer::new(&path)?;
 
     // Default to 1000 samples if not specified
     let samples = samples.unwrap_or(1000);
 
-    analyzer.extract_waveform(samples)
+    if let Some(waveform) = WaveformCache::load(&path, samples, stream_index) {
+        return Ok(waveform);
+    }
+
+    let waveform = analyzer.extract_waveform(samples, stream_index)?;
+    if let Err(e) = WaveformCache::store(&path, samples, stream_index, &waveform) {
+        log::warn!("Failed to cache waveform for {}: {}", path.display(), e);
+    }
+    Ok(waveform)
+}
+
+/// Delete all cached waveforms
+#[command]
+pub async fn clear_waveform_cache() -> Result<()> {
+    WaveformCache::clear()
+}
+
+/// Detect scene cuts in a video, for suggesting split points
+///
+/// This walks the whole file, so it runs off the async runtime and emits
+/// `scene-detect-progress` events (`{ path, progress }`, progress 0.0 to 1.0)
+/// as it goes.  `threshold` defaults to 0.3; higher values report fewer cuts.
+#[command]
+pub async fn detect_scenes(
+    app: AppHandle,
+    path: String,
+    threshold: Option<f64>,
+) -> Result<Vec<SceneCut>> {
+    let analyzer = MediaAnalyzer::new(&PathBuf::from(&path))?;
+    let threshold = threshold.unwrap_or(0.3).clamp(0.0, 1.0);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        // Emit at most once per percent
+        let mut last_percent = None;
+        analyzer.detect_scenes(threshold, |progress| {
+            let percent = (progress * 100.0) as u32;
+            if last_percent != Some(percent) {
+                last_percent = Some(percent);
+                let _ = app.emit(
+                    "scene-detect-progress",
+                    serde_json::json!({ "path": path, "progress": progress }),
+                );
+            }
+        })
+    })
+    .await
+    .map_err(|e| Error::Internal(format!("Scene detection task failed: {}", e)))?
+}
+
+/// Check a media file for damage, e.g. an unfinished download
+///
+/// By default only packets are read, which takes seconds even for large
+/// files; `full_decode` also decodes them, which finds more damage but takes
+/// about as long as playing the file at full speed.  Emits
+/// `verify-media-progress` events (`{ path, progress }`, progress 0.0 to
+/// 1.0) as it goes.
+#[command]
+pub async fn verify_media(
+    app: AppHandle,
+    path: String,
+    full_decode: Option<bool>,
+) -> Result<IntegrityReport> {
+    let analyzer = MediaAnalyzer::new(&PathBuf::from(&path))?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        // Emit at most once per percent
+        let mut last_percent = None;
+        analyzer.verify_integrity(full_decode.unwrap_or(false), |progress| {
+            let percent = (progress * 100.0) as u32;
+            if last_percent != Some(percent) {
+                last_percent = Some(percent);
+                let _ = app.emit(
+                    "verify-media-progress",
+                    serde_json::json!({ "path": path, "progress": progress }),
+                );
+            }
+        })
+    })
+    .await
+    .map_err(|e| Error::Internal(format!("Media verification task failed: {}", e)))?
+}
+
+/// Find black and frozen segments in a video for quality control
+///
+/// Unset `thresholds` fields use their defaults.
+#[command]
+pub async fn analyze_video_quality(
+    path: String,
+    thresholds: Option<QualityThresholds>,
+) -> Result<VideoQualityReport> {
+    let analyzer = MediaAnalyzer::new(&PathBuf::from(&path))?;
+    let thresholds = thresholds.unwrap_or_default();
+
+    tauri::async_runtime::spawn_blocking(move || analyzer.analyze_video_quality(thresholds))
+        .await
+        .map_err(|e| Error::Internal(format!("Quality analysis task failed: {}", e)))?
+}
+
+/// Decode a sample of frames to tell whether a video is interlaced and in
+/// which field order
+///
+/// Use this when `get_media_info` reports no `fieldOrder`; it is slower since
+/// it decodes video.
+#[command]
+pub async fn detect_interlacing(path: String) -> Result<InterlaceReport> {
+    let analyzer = MediaAnalyzer::new(&PathBuf::from(&path))?;
+
+    tauri::async_runtime::spawn_blocking(move || analyzer.detect_interlacing())
+        .await
+        .map_err(|e| Error::Internal(format!("Interlace detection task failed: {}", e)))?
+}
+
+/// Count the frames of a video exactly and check whether its frame rate is
+/// variable (e.g. screen recordings, phone footage)
+///
+/// Reads the whole file, so it is slower than `get_media_info`.
+#[command]
+pub async fn analyze_frame_timing(path: String) -> Result<FrameTiming> {
+    let analyzer = MediaAnalyzer::new(&PathBuf::from(&path))?;
+
+    tauri::async_runtime::spawn_blocking(move || analyzer.analyze_frame_timing())
+        .await
+        .map_err(|e| Error::Internal(format!("Frame timing task failed: {}", e)))?
+}
+
+/// Render a spectrogram of a file's audio as a PNG data URL
+///
+/// `max_freq` (Hz) defaults to half the sample rate; `log_frequency` gives
+/// low frequencies more room.
+#[command]
+pub async fn extract_spectrogram(
+    path: String,
+    width: u32,
+    height: u32,
+    max_freq: Option<f64>,
+    log_frequency: Option<bool>,
+) -> Result<String> {
+    let analyzer = MediaAnalyzer::new(&PathBuf::from(&path))?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        analyzer.extract_spectrogram(width, height, max_freq, log_frequency.unwrap_or(false))
+    })
+    .await
+    .map_err(|e| Error::Internal(format!("Spectrogram task failed: {}", e)))?
+}
+
+/// Detect the tempo (BPM) of a file's audio and the beat times within the
+/// first `beat_window` seconds (default 60), e.g. to place timeline markers
+#[command]
+pub async fn analyze_tempo(path: String, beat_window: Option<f64>) -> Result<TempoAnalysis> {
+    let analyzer = MediaAnalyzer::new(&PathBuf::from(&path))?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        analyzer.analyze_tempo(beat_window.unwrap_or(60.0))
+    })
+    .await
+    .map_err(|e| Error::Internal(format!("Tempo analysis task failed: {}", e)))?
+}
+
+/// Transcode a video into a low resolution proxy for smooth preview editing
+///
+/// `max_height` defaults to 540 and `codec` to H.264.  Progress is emitted
+/// as `proxy-progress` events (`{ jobId, framesDone, framesTotal }`);
+/// `job_id` (a new UUID if not given) identifies the job for
+/// `cancel_proxy`.  Returns the proxy path and its media info.
+#[command]
+pub async fn generate_proxy(
+    app: AppHandle,
+    path: String,
+    output_dir: String,
+    max_height: Option<u32>,
+    codec: Option<ProxyCodec>,
+    job_id: Option<String>,
+) -> Result<ProxyResult> {
+    let job_id = job_id.unwrap_or_else(|| Uuid::new_v4().to_string());
+    let max_height = max_height.unwrap_or(540).clamp(144, 2160);
+    crate::media::generate_proxy(
+        &job_id,
+        &PathBuf::from(&path),
+        &PathBuf::from(&output_dir),
+        max_height,
+        codec.unwrap_or_default(),
+        |progress| {
+            let _ = app.emit("proxy-progress", progress);
+        },
+    )
+    .await
+}
+
+/// Stop a proxy job started with `generate_proxy`
+#[command]
+pub async fn cancel_proxy(job_id: String) -> Result<()> {
+    crate::media::cancel_proxy(&job_id)
+}
+
+/// Extract an audio stream to a WAV (default) or FLAC file
+///
+/// `stream_index` is the container index of the stream (the best audio
+/// stream by default) and `start`/`end` (seconds) limit the range.  The
+/// sample rate and channel layout are kept unless `channels` asks for a
+/// downmix.  Emits `extract-audio-progress` events (`{ path, progress }`,
+/// progress 0.0 to 1.0).  With `project_path`, the file is also added to
+/// that project's audio assets and the project saved.
+#[command]
+#[allow(clippy::too_many_arguments)]
+pub async fn extract_audio(
+    app: AppHandle,
+    path: String,
+    output_path: String,
+    format: Option<AudioFileFormat>,
+    stream_index: Option<usize>,
+    start: Option<f64>,
+    end: Option<f64>,
+    channels: Option<u32>,
+    project_path: Option<String>,
+) -> Result<ExtractedAudio> {
+    let options = AudioExtractOptions {
+        format: format.unwrap_or_default(),
+        stream_index,
+        start,
+        end,
+        channels,
+    };
+    // Emit at most once per percent
+    let mut last_percent = None;
+    let mut extracted = crate::media::extract_audio(
+        &PathBuf::from(&path),
+        &PathBuf::from(&output_path),
+        &options,
+        |progress| {
+            let percent = (progress * 100.0) as u32;
+            if last_percent != Some(percent) {
+                last_percent = Some(percent);
+                let _ = app.emit(
+                    "extract-audio-progress",
+                    serde_json::json!({ "path": path, "progress": progress }),
+                );
+            }
+        },
+    )
+    .await?;
+
+    if let Some(project_path) = project_path {
+        let project_path = PathBuf::from(&project_path);
+        let mut project = ProjectManager::load(&project_path)?;
+        let output = PathBuf::from(&extracted.path);
+        let id = Uuid::new_v4().to_string();
+        project.assets.audio.push(AudioAsset {
+            id: id.clone(),
+            name: output
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_else(|| extracted.path.clone()),
+            path: extracted.path.clone(),
+            duration: extracted.duration,
+            sample_rate: extracted.sample_rate,
+            channels: extracted.channels,
+            format: options.format.extension().to_string(),
+            size: extracted.size,
+            fingerprint: None,
+        });
+        project.updated_at = chrono::Utc::now();
+        ProjectManager::fill_fingerprints(&mut project);
+        ProjectManager::save(&project, Some(&project_path))?;
+        extracted.asset_id = Some(id);
+    }
+    Ok(extracted)
 }
@@ -1,21 +1,30 @@
 //! mpv integration — a full-featured media player controlled via IPC.
 //!
-//! mpv is started as a background daemon (`--idle --no-video`) with a Unix
-//! socket IPC server.  Rust reads mpv's JSON event stream and re-emits the
+//! mpv is started as a background daemon (`--idle --no-video`) with a JSON
+//! IPC server.  Rust reads mpv's JSON event stream and re-emits the
 //! relevant events to the Tauri window so the React frontend can stay in sync.
 //!
+//! Playback is audio only until [`mpv_set_video`] turns video on, which
+//! opens mpv's own video window.  The video commands (screenshots, zoom,
+//! pan, deinterlacing) fail with a clear error while video is off, and
+//! hardware decoding and [`MpvEvent::Stats`] only apply while it is on.
+//!
 //! mpv accepts anything it can play: YouTube URLs (via its built-in yt-dlp
 //! support), local files, HLS/RTSP/DASH streams, FLAC, etc.
 //!
 //! Platform notes
 //! ──────────────
-//! IPC uses a Unix domain socket on macOS/Linux.  Windows support would
-//! require a named-pipe implementation and is left as a future addition.
+//! IPC uses a Unix domain socket on macOS/Linux and a named pipe
+//! (`\\.\pipe\waldiez-mpv-<pid>`) on Windows.  Both transports feed the
+//! same reader/writer tasks, so every `mpv_*` command behaves identically.
 
 use crate::error::{Error, Result};
+use std::collections::HashMap;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use tauri::Emitter;
+use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::sync::Mutex;
 
 // ── State managed by Tauri ─────────────────────────────────────────────────
@@ -24,11 +33,37 @@ use tokio::sync::Mutex;
 pub struct MpvState(pub Arc<Mutex<Option<MpvInner>>>);
 
 pub struct MpvInner {
-    _child: tokio::process::Child,
+    /// Tells the watchdog task, which owns the mpv process, to kill it.
+    /// Dropping it has the same effect.
+    kill_tx: tokio::sync::oneshot::Sender<()>,
+    /// Unix socket path, or the `\\.\pipe\...` name on Windows.
     socket_path: PathBuf,
     cmd_tx: tokio::sync::mpsc::Sender<String>,
+    /// Replies awaited by [`request`], keyed by `request_id`.
+    pending: PendingReplies,
+    next_request_id: AtomicU64,
+    /// Restart mpv and reload [`MpvInner::last_path`] if it crashes.
+    auto_restart: bool,
+    /// What mpv is playing, kept up to date by the reader task.
+    last_path: Arc<std::sync::Mutex<Option<String>>>,
 }
 
+type PendingReplies =
+    Arc<std::sync::Mutex<HashMap<u64, tokio::sync::oneshot::Sender<serde_json::Value>>>>;
+
+/// How long [`request`] waits for mpv to answer before giving up.
+const REQUEST_TIMEOUT: tokio::time::Duration = tokio::time::Duration::from_secs(5);
+
+/// How often the reader task polls playback statistics.
+const STATS_INTERVAL: tokio::time::Duration = tokio::time::Duration::from_secs(2);
+
+/// Properties polled for [`MpvEvent::Stats`], in reply order.
+const STATS_PROPS: [&str; 3] = ["avsync", "frame-drop-count", "video-bitrate"];
+
+/// Stats queries use `request_id`s from here up, far above the ids handed out
+/// by [`request`], so the reader task can recognise their replies.
+const STATS_REQUEST_ID_BASE: u64 = 1 << 62;
+
 // ── Events emitted to the frontend ────────────────────────────────────────
 
 /// Events forwarded from the mpv IPC socket to the Tauri window as "mpv-event".
@@ -43,27 +78,291 @@ pub enum MpvEvent {
     Paused(bool),
     /// Volume, 0.0–1.0.
     Volume(f64),
+    /// `true` = muted.
+    Muted(bool),
     /// Playback reached the end of the current file.
     Ended,
+    /// Zero-based index of the current playlist entry.
+    PlaylistPos(usize),
+    /// Number of entries in the playlist.
+    PlaylistCount(usize),
+    /// Current loop behaviour.
+    Loop(MpvLoopMode),
+    /// Stream cache fill level while buffering, 0–100.
+    Buffering(u8),
+    /// Video zoom (log2 scale: 0 = none, 1 = 2×).
+    Zoom(f64),
+    /// Video pan offsets, relative to the video size.
+    Pan { x: f64, y: f64 },
+    /// mpv exited unexpectedly.  It is restarted automatically if enabled
+    /// with [`mpv_reconnect_on_crash`]; otherwise the next `mpv_load` starts it.
+    Crashed,
+    /// Playback statistics, polled every [`STATS_INTERVAL`] while video plays
+    /// (see [`mpv_set_video`]).
+    Stats {
+        /// A/V sync error in seconds.
+        avsync: f64,
+        dropped_frames: u64,
+        /// Bits per second.
+        video_bitrate: u64,
+    },
+}
+
+/// Loop behaviour for [`mpv_set_loop`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MpvLoopMode {
+    Off,
+    /// Repeat the current file forever.
+    File,
+    /// Repeat the whole playlist forever.
+    Playlist,
+}
+
+/// Hardware decoding API for [`mpv_load`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MpvHwdec {
+    /// Let mpv pick a safe hardware decoder, falling back to software.
+    Auto,
+    VideoToolbox,
+    Nvdec,
+    Vaapi,
+    Dxva2,
+    /// Software decoding only.
+    Off,
+}
+
+impl MpvHwdec {
+    /// Value accepted by mpv's `hwdec` property.
+    fn as_mpv(self) -> &'static str {
+        match self {
+            MpvHwdec::Auto => "auto",
+            MpvHwdec::VideoToolbox => "videotoolbox",
+            MpvHwdec::Nvdec => "nvdec",
+            MpvHwdec::Vaapi => "vaapi",
+            MpvHwdec::Dxva2 => "dxva2",
+            MpvHwdec::Off => "no",
+        }
+    }
+}
+
+/// Deinterlacing filter for [`mpv_set_deinterlace_mode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeinterlaceMode {
+    /// One frame per frame (keeps the frame rate).
+    Yadif,
+    /// One frame per field (doubles the frame rate, smoother motion).
+    Yadif2x,
+    /// Line-doubles each field; fast but flickery.
+    Bob,
+    /// No deinterlacing filter.
+    None,
+}
+
+impl DeinterlaceMode {
+    /// Filter for mpv's `vf` list, or `None` to remove it.
+    fn as_mpv_filter(self) -> Option<&'static str> {
+        match self {
+            DeinterlaceMode::Yadif => Some("yadif=mode=send_frame"),
+            DeinterlaceMode::Yadif2x => Some("yadif=mode=send_field"),
+            DeinterlaceMode::Bob => Some("lavfi=[separatefields,scale=w=iw:h=ih*2]"),
+            DeinterlaceMode::None => None,
+        }
+    }
+}
+
+/// Tracks properties that mpv reports separately but the frontend sees as a
+/// single event (`loop-file` + `loop-playlist`, `video-pan-x` + `video-pan-y`),
+/// so a change to either half can be emitted with the other's last value.
+#[derive(Default)]
+struct PairedProps {
+    loop_file: bool,
+    loop_playlist: bool,
+    pan_x: f64,
+    pan_y: f64,
+}
+
+impl PairedProps {
+    fn update(&mut self, v: &serde_json::Value) -> Option<MpvEvent> {
+        if v.get("event")?.as_str()? != "property-change" {
+            return None;
+        }
+        let data = v.get("data")?;
+        // Loop off is reported as `false` (or "no"); on as "inf", "force" or a count.
+        let loop_on =
+            || !matches!(data, serde_json::Value::Bool(false)) && data.as_str() != Some("no");
+        match v.get("name")?.as_str()? {
+            "loop-file" => self.loop_file = loop_on(),
+            "loop-playlist" => self.loop_playlist = loop_on(),
+            "video-pan-x" => {
+                self.pan_x = data.as_f64()?;
+                return Some(self.pan());
+            }
+            "video-pan-y" => {
+                self.pan_y = data.as_f64()?;
+                return Some(self.pan());
+            }
+            _ => return None,
+        }
+        let mode = if self.loop_file {
+            MpvLoopMode::File
+        } else if self.loop_playlist {
+            MpvLoopMode::Playlist
+        } else {
+            MpvLoopMode::Off
+        };
+        Some(MpvEvent::Loop(mode))
+    }
+
+    fn pan(&self) -> MpvEvent {
+        MpvEvent::Pan {
+            x: self.pan_x,
+            y: self.pan_y,
+        }
+    }
+}
+
+/// Collects the replies to one round of [`STATS_PROPS`] queries.
+#[derive(Default)]
+struct StatsQuery {
+    /// `None` until answered; `Some(None)` if the property was unavailable.
+    replies: [Option<Option<f64>>; 3],
+}
+
+impl StatsQuery {
+    /// Record the reply for `STATS_PROPS[idx]`, returning the event once all
+    /// three have answered.
+    fn record(&mut self, idx: usize, reply: &serde_json::Value) -> Option<MpvEvent> {
+        let value = match reply.get("error").and_then(|e| e.as_str()) {
+            Some("success") => reply.get("data").and_then(|d| d.as_f64()),
+            _ => None,
+        };
+        *self.replies.get_mut(idx)? = Some(value);
+        let [Some(avsync), Some(dropped), Some(bitrate)] = self.replies else {
+            return None;
+        };
+        self.replies = Default::default();
+        // Nothing is available while idle or playing audio only.
+        if avsync.is_none() && dropped.is_none() && bitrate.is_none() {
+            return None;
+        }
+        Some(MpvEvent::Stats {
+            avsync: avsync.unwrap_or(0.0),
+            dropped_frames: dropped.unwrap_or(0.0) as u64,
+            video_bitrate: bitrate.unwrap_or(0.0) as u64,
+        })
+    }
 }
 
-// ── Internal helpers (Unix only) ──────────────────────────────────────────
+// ── Query results ─────────────────────────────────────────────────────────
 
+/// One entry of mpv's `track-list` property.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct MpvTrack {
+    /// Track id as used by `aid` / `sid` / `vid`.
+    pub id: i64,
+    /// `"video"`, `"audio"` or `"sub"`.
+    #[serde(rename = "type")]
+    pub type_: String,
+    pub codec: Option<String>,
+    pub lang: Option<String>,
+    pub title: Option<String>,
+    #[serde(default)]
+    pub selected: bool,
+}
+
+/// One entry of mpv's `chapter-list` property.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct MpvChapter {
+    #[serde(default)]
+    pub title: String,
+    /// Chapter start in seconds.
+    pub time: f64,
+}
+
+// ── Internal helpers ──────────────────────────────────────────────────────
+
+/// Path of the IPC endpoint handed to `--input-ipc-server`.
+#[cfg(unix)]
+fn ipc_path() -> PathBuf {
+    std::env::temp_dir().join(format!("waldiez-mpv-{}.sock", std::process::id()))
+}
+
+/// Path of the IPC endpoint handed to `--input-ipc-server`.
+#[cfg(windows)]
+fn ipc_path() -> PathBuf {
+    PathBuf::from(format!(r"\\.\pipe\waldiez-mpv-{}", std::process::id()))
+}
+
+/// Connect to the mpv Unix socket, waiting up to 2.5 s for it to appear.
 #[cfg(unix)]
+async fn connect_ipc(
+    path: &std::path::Path,
+) -> Result<(
+    impl AsyncRead + Send + Unpin + 'static,
+    impl AsyncWrite + Send + Unpin + 'static,
+)> {
+    let mut connected = false;
+    for _ in 0..25 {
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        if path.exists() {
+            connected = true;
+            break;
+        }
+    }
+    if !connected {
+        return Err(Error::Internal("mpv did not start in time".into()));
+    }
+
+    let stream = tokio::net::UnixStream::connect(path)
+        .await
+        .map_err(|e| Error::Internal(format!("mpv IPC connect: {e}")))?;
+    Ok(stream.into_split())
+}
+
+/// Connect to the mpv named pipe, retrying for up to 2.5 s.
+///
+/// The pipe does not exist until mpv has created its IPC server, and it may
+/// briefly report `ERROR_PIPE_BUSY` while mpv is still accepting, so every
+/// failure is retried until the deadline.
+#[cfg(windows)]
+async fn connect_ipc(
+    path: &std::path::Path,
+) -> Result<(
+    impl AsyncRead + Send + Unpin + 'static,
+    impl AsyncWrite + Send + Unpin + 'static,
+)> {
+    use tokio::net::windows::named_pipe::ClientOptions;
+
+    let mut last_err = None;
+    for _ in 0..25 {
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        match ClientOptions::new().open(path) {
+            Ok(client) => return Ok(tokio::io::split(client)),
+            Err(e) => last_err = Some(e),
+        }
+    }
+    Err(Error::Internal(match last_err {
+        Some(e) => format!("mpv IPC connect: {e}"),
+        None => "mpv did not start in time".into(),
+    }))
+}
+
+#[cfg(any(unix, windows))]
 async fn start_mpv_impl(app: &tauri::AppHandle, arc: &Arc<Mutex<Option<MpvInner>>>) -> Result<()> {
-    use tokio::{
-        io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
-        net::UnixStream,
-    };
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 
     // Another concurrent call may have already started mpv.
     if arc.lock().await.is_some() {
         return Ok(());
     }
 
-    let socket_path = std::env::temp_dir().join(format!("waldiez-mpv-{}.sock", std::process::id()));
+    let socket_path = ipc_path();
 
     // Remove stale socket from a previous run.
+    #[cfg(unix)]
     let _ = std::fs::remove_file(&socket_path);
 
     let child = tokio::process::Command::new("mpv")
@@ -79,24 +378,7 @@ async fn start_mpv_impl(app: &tauri::AppHandle, arc: &Arc<Mutex<Option<MpvInner>
         .spawn()
         .map_err(|e| Error::Internal(format!("mpv not found: {e}")))?;
 
-    // Wait up to 2.5 s for the socket to appear.
-    let mut connected = false;
-    for _ in 0..25 {
-        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-        if socket_path.exists() {
-            connected = true;
-            break;
-        }
-    }
-    if !connected {
-        return Err(Error::Internal("mpv did not start in time".into()));
-    }
-
-    let stream = UnixStream::connect(&socket_path)
-        .await
-        .map_err(|e| Error::Internal(format!("mpv IPC connect: {e}")))?;
-
-    let (reader, mut writer) = stream.into_split();
+    let (reader, mut writer) = connect_ipc(&socket_path).await?;
     let (cmd_tx, mut cmd_rx) = tokio::sync::mpsc::channel::<String>(64);
 
     // Writer task: drain the channel into the socket.
@@ -110,9 +392,25 @@ async fn start_mpv_impl(app: &tauri::AppHandle, arc: &Arc<Mutex<Option<MpvInner>
     });
 
     // Subscribe to the properties we care about.
-    for (id, prop) in ["time-pos", "duration", "pause", "volume", "eof-reached"]
-        .iter()
-        .enumerate()
+    for (id, prop) in [
+        "time-pos",
+        "duration",
+        "pause",
+        "volume",
+        "mute",
+        "eof-reached",
+        "playlist-pos",
+        "playlist-count",
+        "loop-file",
+        "loop-playlist",
+        "cache-buffering-state",
+        "video-zoom",
+        "video-pan-x",
+        "video-pan-y",
+        "path",
+    ]
+    .iter()
+    .enumerate()
     {
         let _ = cmd_tx
             .send(format!(
@@ -123,38 +421,154 @@ async fn start_mpv_impl(app: &tauri::AppHandle, arc: &Arc<Mutex<Option<MpvInner>
             .await;
     }
 
-    // Reader task: parse mpv events and emit to the Tauri window.
+    // Reader task: route command replies to their waiters, parse mpv events
+    // and emit them to the Tauri window, and poll playback statistics.
     let app2 = app.clone();
+    let pending: PendingReplies = Arc::new(std::sync::Mutex::new(HashMap::new()));
+    let pending2 = pending.clone();
+    let stats_tx = cmd_tx.clone();
+    let last_path = Arc::new(std::sync::Mutex::new(None));
+    let last_path2 = last_path.clone();
     tokio::spawn(async move {
+        let mut paired = PairedProps::default();
+        let mut stats = StatsQuery::default();
+        let mut stats_timer = tokio::time::interval(STATS_INTERVAL);
+        stats_timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
         let mut lines = BufReader::new(reader).lines();
-        while let Ok(Some(line)) = lines.next_line().await {
+        loop {
+            let line = tokio::select! {
+                line = lines.next_line() => match line {
+                    Ok(Some(line)) => line,
+                    _ => break,
+                },
+                _ = stats_timer.tick() => {
+                    for (i, prop) in STATS_PROPS.iter().enumerate() {
+                        let cmd = serde_json::json!({
+                            "command": ["get_property", prop],
+                            "request_id": STATS_REQUEST_ID_BASE + i as u64,
+                        });
+                        // Skip a round rather than stall the reader if the queue is full.
+                        let _ = stats_tx.try_send(cmd.to_string());
+                    }
+                    continue;
+                }
+            };
             if let Ok(v) = serde_json::from_str::<serde_json::Value>(&line) {
-                if let Some(evt) = parse_mpv_event(&v) {
+                if let Some(id) = v.get("request_id").and_then(|id| id.as_u64()) {
+                    if id >= STATS_REQUEST_ID_BASE {
+                        if let Some(evt) = stats.record((id - STATS_REQUEST_ID_BASE) as usize, &v) {
+                            let _ = app2.emit("mpv-event", evt);
+                        }
+                        continue;
+                    }
+                    let waiter = pending2.lock().unwrap().remove(&id);
+                    if let Some(tx) = waiter {
+                        let _ = tx.send(v);
+                    }
+                } else if v.get("name").and_then(|n| n.as_str()) == Some("path") {
+                    *last_path2.lock().unwrap() =
+                        v.get("data").and_then(|d| d.as_str()).map(str::to_string);
+                } else if let Some(evt) = parse_mpv_event(&v).or_else(|| paired.update(&v)) {
                     let _ = app2.emit("mpv-event", evt);
                 }
             }
         }
     });
 
+    // Watchdog task: owns the process, kills it on request and reports
+    // unexpected exits.
+    let (kill_tx, kill_rx) = tokio::sync::oneshot::channel();
+    let (app3, arc2, cmd_tx2) = (app.clone(), arc.clone(), cmd_tx.clone());
+    tokio::spawn(async move {
+        let mut child = child;
+        let status = tokio::select! {
+            status = child.wait() => status,
+            _ = kill_rx => {
+                let _ = child.kill().await;
+                return;
+            }
+        };
+        drop(child);
+
+        // Only clean up if this is still the live instance.
+        let inner = {
+            let mut lock = arc2.lock().await;
+            match lock.as_ref() {
+                Some(inner) if inner.cmd_tx.same_channel(&cmd_tx2) => lock.take(),
+                _ => None,
+            }
+        };
+        drop(cmd_tx2);
+        let Some(inner) = inner else { return };
+        if matches!(&status, Ok(s) if s.success()) {
+            return;
+        }
+
+        log::warn!("mpv exited unexpectedly: {status:?}");
+        let _ = app3.emit("mpv-event", MpvEvent::Crashed);
+        if inner.auto_restart {
+            let path = inner.last_path.lock().unwrap().clone();
+            drop(inner);
+            restart_after_crash(app3, arc2, path).await;
+        }
+    });
+
     // Store the handle — re-check to avoid overwriting a concurrent start.
+    // A losing instance is killed when its `kill_tx` is dropped.
     let mut lock = arc.lock().await;
     if lock.is_none() {
         *lock = Some(MpvInner {
-            _child: child,
+            kill_tx,
             socket_path,
             cmd_tx,
+            pending,
+            next_request_id: AtomicU64::new(1),
+            auto_restart: false,
+            last_path,
         });
     }
     Ok(())
 }
 
-#[cfg(not(unix))]
+/// Start a fresh mpv after a crash and reload what was playing.
+///
+/// Returns a boxed future because `start_mpv_impl` spawns the watchdog that
+/// calls this; the explicit `Send` bound breaks the type cycle.
+#[cfg(any(unix, windows))]
+fn restart_after_crash(
+    app: tauri::AppHandle,
+    arc: Arc<Mutex<Option<MpvInner>>>,
+    path: Option<String>,
+) -> std::pin::Pin<Box<dyn std::future::Future<Output = ()> + Send>> {
+    Box::pin(async move {
+        if let Err(e) = start_mpv_impl(&app, &arc).await {
+            log::error!("mpv restart failed: {e}");
+            return;
+        }
+
+        let mut lock = arc.lock().await;
+        if let Some(inner) = lock.as_mut() {
+            inner.auto_restart = true;
+            if let Some(path) = path {
+                let _ = inner
+                    .cmd_tx
+                    .send(format!(
+                        r#"{{"command":["loadfile",{},"replace"]}}"#,
+                        json_str(&path)
+                    ))
+                    .await;
+            }
+        }
+    })
+}
+
+#[cfg(not(any(unix, windows)))]
 async fn start_mpv_impl(
     _app: &tauri::AppHandle,
     _arc: &Arc<Mutex<Option<MpvInner>>>,
 ) -> Result<()> {
     Err(Error::Internal(
-        "mpv IPC is not yet supported on Windows".into(),
+        "mpv IPC is not supported on this platform".into(),
     ))
 }
 
@@ -180,10 +594,65 @@ async fn send_cmd(state: &MpvState, cmd: String) -> Result<()> {
     }
 }
 
+/// Send a command that expects a reply and wait for it.
+///
+/// The command is tagged with a fresh `request_id`; the reader task hands the
+/// matching reply back through a oneshot channel.  Returns the reply's `data`
+/// field (`null` when mpv sends none), or an error if mpv reports a failure or
+/// does not answer within [`REQUEST_TIMEOUT`].
+async fn request(state: &MpvState, command: serde_json::Value) -> Result<serde_json::Value> {
+    let (id, cmd_tx, pending) = {
+        let lock = state.0.lock().await;
+        let inner = lock
+            .as_ref()
+            .ok_or_else(|| Error::Internal("mpv not running — call mpv_load first".into()))?;
+        (
+            inner.next_request_id.fetch_add(1, Ordering::Relaxed),
+            inner.cmd_tx.clone(),
+            inner.pending.clone(),
+        )
+    };
+
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    pending.lock().unwrap().insert(id, tx);
+
+    let cmd = serde_json::json!({ "command": command, "request_id": id }).to_string();
+    if let Err(e) = cmd_tx.send(cmd).await {
+        pending.lock().unwrap().remove(&id);
+        return Err(Error::Internal(format!("mpv send: {e}")));
+    }
+
+    let mut reply = match tokio::time::timeout(REQUEST_TIMEOUT, rx).await {
+        Ok(Ok(reply)) => reply,
+        Ok(Err(_)) => return Err(Error::Internal("mpv IPC closed".into())),
+        Err(_) => {
+            pending.lock().unwrap().remove(&id);
+            return Err(Error::Internal(format!("mpv request {id} timed out")));
+        }
+    };
+
+    match reply.get("error").and_then(|e| e.as_str()) {
+        Some("success") | None => Ok(reply
+            .get_mut("data")
+            .map(serde_json::Value::take)
+            .unwrap_or(serde_json::Value::Null)),
+        Some(err) => Err(Error::Internal(format!("mpv: {err}"))),
+    }
+}
+
 fn json_str(s: &str) -> String {
     serde_json::to_string(s).unwrap_or_else(|_| format!("\"{}\"", s.replace('"', "\\\"")))
 }
 
+/// mpv track ids start at 1; anything lower maps to `"no"` (track disabled).
+fn track_id(id: i64) -> String {
+    if id > 0 {
+        id.to_string()
+    } else {
+        json_str("no")
+    }
+}
+
 fn parse_mpv_event(v: &serde_json::Value) -> Option<MpvEvent> {
     let event = v.get("event")?.as_str()?;
     if event != "property-change" {
@@ -197,6 +666,7 @@ fn parse_mpv_event(v: &serde_json::Value) -> Option<MpvEvent> {
         "pause" => data.as_bool().map(MpvEvent::Paused),
         // mpv volume is 0–100; we normalise to 0–1 for the frontend.
         "volume" => data.as_f64().map(|v| MpvEvent::Volume(v / 100.0)),
+        "mute" => data.as_bool().map(MpvEvent::Muted),
         "eof-reached" => {
             if data.as_bool() == Some(true) {
                 Some(MpvEvent::Ended)
@@ -204,6 +674,11 @@ fn parse_mpv_event(v: &serde_json::Value) -> Option<MpvEvent> {
                 None
             }
         }
+        // playlist-pos is -1 when nothing is playing; skip that.
+        "playlist-pos" => data.as_u64().map(|n| MpvEvent::PlaylistPos(n as usize)),
+        "playlist-count" => data.as_u64().map(|n| MpvEvent::PlaylistCount(n as usize)),
+        "video-zoom" => data.as_f64().map(MpvEvent::Zoom),
+        "cache-buffering-state" => data.as_u64().map(|n| MpvEvent::Buffering(n.min(100) as u8)),
         _ => None,
     }
 }
@@ -228,18 +703,253 @@ pub async fn mpv_check() -> bool {
 ///   - Local file paths
 ///   - HLS/RTSP/RTMP streams
 ///
+/// `hwdec` selects the hardware decoder (defaults to [`MpvHwdec::Auto`]);
+/// use [`mpv_get_hwdec_current`] to see which one is actually in use.  It
+/// only takes effect while video is on (see [`mpv_set_video`]).
+///
 /// Auto-starts the mpv daemon if it is not already running.
 #[tauri::command]
 pub async fn mpv_load(
     app: tauri::AppHandle,
     state: tauri::State<'_, MpvState>,
     url: String,
+    hwdec: Option<MpvHwdec>,
 ) -> Result<()> {
     ensure_running(&app, &state).await?;
     send_cmd(
         &state,
         format!(r#"{{"command":["loadfile",{},"replace"]}}"#, json_str(&url)),
     )
+    .await?;
+    send_cmd(
+        &state,
+        format!(
+            r#"{{"command":["set_property","hwdec","{}"]}}"#,
+            hwdec.unwrap_or(MpvHwdec::Auto).as_mpv()
+        ),
+    )
+    .await
+}
+
+/// Returns the hardware decoder mpv is currently using (`"no"` for software,
+/// or while video is off).
+#[tauri::command]
+pub async fn mpv_get_hwdec_current(state: tauri::State<'_, MpvState>) -> Result<String> {
+    let data = request(&state, serde_json::json!(["get_property", "hwdec-current"])).await?;
+    Ok(data.as_str().unwrap_or("no").to_string())
+}
+
+/// Query an arbitrary mpv property (e.g. `"time-pos"`, `"playlist-pos"`).
+///
+/// Returns the raw JSON value mpv reports for the property.
+#[tauri::command]
+pub async fn mpv_get_property(
+    state: tauri::State<'_, MpvState>,
+    property: String,
+) -> Result<serde_json::Value> {
+    request(&state, serde_json::json!(["get_property", property])).await
+}
+
+/// Append a URL or file path to the playlist.
+///
+/// Starts playback right away if nothing is currently playing.
+#[tauri::command]
+pub async fn mpv_playlist_append(state: tauri::State<'_, MpvState>, url: String) -> Result<()> {
+    send_cmd(
+        &state,
+        format!(
+            r#"{{"command":["loadfile",{},"append-play"]}}"#,
+            json_str(&url)
+        ),
+    )
+    .await
+}
+
+/// Remove the playlist entry at `index` (zero-based).
+#[tauri::command]
+pub async fn mpv_playlist_remove(state: tauri::State<'_, MpvState>, index: usize) -> Result<()> {
+    send_cmd(
+        &state,
+        format!(r#"{{"command":["playlist-remove",{}]}}"#, index),
+    )
+    .await
+}
+
+/// Jump to the playlist entry at `index` (zero-based).
+#[tauri::command]
+pub async fn mpv_playlist_goto(state: tauri::State<'_, MpvState>, index: usize) -> Result<()> {
+    send_cmd(
+        &state,
+        format!(r#"{{"command":["playlist-play-index",{}]}}"#, index),
+    )
+    .await
+}
+
+/// Select the audio track by mpv track id (see [`mpv_get_track_list`]).
+///
+/// An `id` of 0 or below disables audio.
+#[tauri::command]
+pub async fn mpv_set_audio_track(state: tauri::State<'_, MpvState>, id: i64) -> Result<()> {
+    send_cmd(
+        &state,
+        format!(r#"{{"command":["set_property","aid",{}]}}"#, track_id(id)),
+    )
+    .await
+}
+
+/// Select the subtitle track by mpv track id (see [`mpv_get_track_list`]).
+///
+/// An `id` of 0 or below hides subtitles.
+#[tauri::command]
+pub async fn mpv_set_subtitle_track(state: tauri::State<'_, MpvState>, id: i64) -> Result<()> {
+    send_cmd(
+        &state,
+        format!(r#"{{"command":["set_property","sid",{}]}}"#, track_id(id)),
+    )
+    .await
+}
+
+/// Load an external subtitle file (e.g. `.srt`, `.ass`) for the current file.
+///
+/// `flags` is passed to mpv's `sub-add` (`"select"`, `"auto"` or `"cached"`)
+/// and defaults to `"select"`, which shows the new track immediately.
+#[tauri::command]
+pub async fn mpv_add_subtitle_file(
+    state: tauri::State<'_, MpvState>,
+    path: String,
+    flags: Option<String>,
+) -> Result<()> {
+    send_cmd(
+        &state,
+        format!(
+            r#"{{"command":["sub-add",{},{}]}}"#,
+            json_str(&path),
+            json_str(flags.as_deref().unwrap_or("select"))
+        ),
+    )
+    .await
+}
+
+/// Shift subtitles by `delay_secs` (positive = later).
+#[tauri::command]
+pub async fn mpv_set_subtitle_delay(
+    state: tauri::State<'_, MpvState>,
+    delay_secs: f64,
+) -> Result<()> {
+    send_cmd(
+        &state,
+        format!(
+            r#"{{"command":["set_property","sub-delay",{}]}}"#,
+            delay_secs
+        ),
+    )
+    .await
+}
+
+/// Shift audio by `delay_secs` (positive = later).
+#[tauri::command]
+pub async fn mpv_set_audio_delay(state: tauri::State<'_, MpvState>, delay_secs: f64) -> Result<()> {
+    send_cmd(
+        &state,
+        format!(
+            r#"{{"command":["set_property","audio-delay",{}]}}"#,
+            delay_secs
+        ),
+    )
+    .await
+}
+
+/// List the video, audio and subtitle tracks of the current file.
+#[tauri::command]
+pub async fn mpv_get_track_list(state: tauri::State<'_, MpvState>) -> Result<Vec<MpvTrack>> {
+    let data = request(&state, serde_json::json!(["get_property", "track-list"])).await?;
+    serde_json::from_value(data).map_err(|e| Error::Internal(format!("mpv track-list: {e}")))
+}
+
+/// List the chapters of the current file.
+#[tauri::command]
+pub async fn mpv_get_chapter_list(state: tauri::State<'_, MpvState>) -> Result<Vec<MpvChapter>> {
+    let data = request(&state, serde_json::json!(["get_property", "chapter-list"])).await?;
+    serde_json::from_value(data).map_err(|e| Error::Internal(format!("mpv chapter-list: {e}")))
+}
+
+/// Jump to the chapter at `index` (zero-based).
+#[tauri::command]
+pub async fn mpv_goto_chapter(state: tauri::State<'_, MpvState>, index: i64) -> Result<()> {
+    send_cmd(
+        &state,
+        format!(r#"{{"command":["set_property","chapter",{}]}}"#, index),
+    )
+    .await
+}
+
+/// Show or hide video in mpv's own window.
+///
+/// mpv starts audio only; turning video on selects the file's video track
+/// and is needed by [`mpv_screenshot`], zoom, pan and deinterlacing.
+#[tauri::command]
+pub async fn mpv_set_video(state: tauri::State<'_, MpvState>, enabled: bool) -> Result<()> {
+    let vid = if enabled { "auto" } else { "no" };
+    request(&state, serde_json::json!(["set_property", "vid", vid])).await?;
+    Ok(())
+}
+
+/// Fail unless mpv is showing a video track.
+async fn require_video(state: &MpvState) -> Result<()> {
+    let vid = request(state, serde_json::json!(["get_property", "vid"])).await?;
+    if vid.is_null() || vid == serde_json::Value::Bool(false) || vid == "no" {
+        return Err(Error::InvalidFormat(
+            "mpv is playing audio only; turn video on with mpv_set_video".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// Save the current video frame to a PNG in the temp directory.
+///
+/// Returns the absolute path of the written file.  The file is left in place;
+/// the caller is responsible for deleting it.  Fails while video is off (see
+/// [`mpv_set_video`]) or when the current file has no video track.
+#[tauri::command]
+pub async fn mpv_screenshot(state: tauri::State<'_, MpvState>) -> Result<String> {
+    require_video(&state).await?;
+    let path =
+        std::env::temp_dir().join(format!("waldiez-screenshot-{}.png", uuid::Uuid::new_v4()));
+    let path = path.to_string_lossy().to_string();
+    request(
+        &state,
+        serde_json::json!(["screenshot-to-file", path, "video"]),
+    )
+    .await?;
+    Ok(path)
+}
+
+/// Set the loop behaviour.
+///
+/// `File` repeats the current file and `Playlist` repeats the whole
+/// playlist; each mode turns the other kind of loop off.
+#[tauri::command]
+pub async fn mpv_set_loop(state: tauri::State<'_, MpvState>, mode: MpvLoopMode) -> Result<()> {
+    let (loop_file, loop_playlist) = match mode {
+        MpvLoopMode::Off => ("no", "no"),
+        MpvLoopMode::File => ("inf", "no"),
+        MpvLoopMode::Playlist => ("no", "inf"),
+    };
+    send_cmd(
+        &state,
+        format!(
+            r#"{{"command":["set_property","loop-file","{}"]}}"#,
+            loop_file
+        ),
+    )
+    .await?;
+    send_cmd(
+        &state,
+        format!(
+            r#"{{"command":["set_property","loop-playlist","{}"]}}"#,
+            loop_playlist
+        ),
+    )
     .await
 }
 
@@ -284,6 +994,124 @@ pub async fn mpv_set_volume(state: tauri::State<'_, MpvState>, volume: f64) -> R
     .await
 }
 
+/// Mute or unmute audio.
+#[tauri::command]
+pub async fn mpv_mute(state: tauri::State<'_, MpvState>, muted: bool) -> Result<()> {
+    send_cmd(
+        &state,
+        format!(r#"{{"command":["set_property","mute",{}]}}"#, muted),
+    )
+    .await
+}
+
+/// Toggle mute.
+#[tauri::command]
+pub async fn mpv_toggle_mute(state: tauri::State<'_, MpvState>) -> Result<()> {
+    send_cmd(&state, r#"{"command":["cycle","mute"]}"#.into()).await
+}
+
+/// Configure stream buffering.
+///
+/// `cache_secs` is how far ahead mpv buffers; with `pause_on_low` playback
+/// pauses while the cache refills instead of stuttering.  Only takes effect
+/// for streams opened after the call, so call it before or immediately after
+/// `mpv_load`.
+#[tauri::command]
+pub async fn mpv_set_cache(
+    state: tauri::State<'_, MpvState>,
+    cache_secs: f64,
+    pause_on_low: bool,
+) -> Result<()> {
+    send_cmd(
+        &state,
+        format!(
+            r#"{{"command":["set_property","cache-secs",{}]}}"#,
+            cache_secs.max(0.0)
+        ),
+    )
+    .await?;
+    send_cmd(
+        &state,
+        format!(
+            r#"{{"command":["set_property","cache-pause",{}]}}"#,
+            pause_on_low
+        ),
+    )
+    .await
+}
+
+/// Set the video zoom on mpv's log2 scale (0 = none, 1 = 2×), clamped to 0–5.
+/// Fails while video is off.
+#[tauri::command]
+pub async fn mpv_set_video_zoom(state: tauri::State<'_, MpvState>, zoom: f64) -> Result<()> {
+    require_video(&state).await?;
+    send_cmd(
+        &state,
+        format!(
+            r#"{{"command":["set_property","video-zoom",{}]}}"#,
+            zoom.clamp(0.0, 5.0)
+        ),
+    )
+    .await
+}
+
+/// Pan the video.  `x` and `y` are offsets relative to the video size
+/// (e.g. 0.5 moves it by half its width).  Fails while video is off.
+#[tauri::command]
+pub async fn mpv_set_pan(state: tauri::State<'_, MpvState>, x: f64, y: f64) -> Result<()> {
+    require_video(&state).await?;
+    send_cmd(
+        &state,
+        format!(r#"{{"command":["set_property","video-pan-x",{}]}}"#, x),
+    )
+    .await?;
+    send_cmd(
+        &state,
+        format!(r#"{{"command":["set_property","video-pan-y",{}]}}"#, y),
+    )
+    .await
+}
+
+/// Turn mpv's automatic deinterlacing (yadif) on or off.  Fails while video
+/// is off.
+#[tauri::command]
+pub async fn mpv_set_deinterlace(state: tauri::State<'_, MpvState>, enabled: bool) -> Result<()> {
+    require_video(&state).await?;
+    send_cmd(
+        &state,
+        format!(
+            r#"{{"command":["set_property","deinterlace",{}]}}"#,
+            enabled
+        ),
+    )
+    .await
+}
+
+/// Choose an explicit deinterlacing filter.  It is added to mpv's `vf` list
+/// under the `@deint` label so other video filters are left alone.  Fails
+/// while video is off.
+#[tauri::command]
+pub async fn mpv_set_deinterlace_mode(
+    state: tauri::State<'_, MpvState>,
+    mode: DeinterlaceMode,
+) -> Result<()> {
+    require_video(&state).await?;
+    send_cmd(&state, r#"{"command":["vf","remove","@deint"]}"#.into()).await?;
+    match mode.as_mpv_filter() {
+        Some(filter) => {
+            send_cmd(
+                &state,
+                format!(
+                    r#"{{"command":["vf","add",{}]}}"#,
+                    json_str(&format!("@deint:{filter}"))
+                ),
+            )
+            .await
+        }
+        None => Ok(()),
+    }
+}
+
 /// Set playback speed (e.g. 1.5 for 1.5×).
 #[tauri::command]
 pub async fn mpv_set_speed(state: tauri::State<'_, MpvState>, rate: f64) -> Result<()> {
@@ -300,14 +1128,30 @@ pub async fn mpv_stop(state: tauri::State<'_, MpvState>) -> Result<()> {
     send_cmd(&state, r#"{"command":["stop"]}"#.into()).await
 }
 
+/// Enable or disable restarting mpv if it crashes.  When enabled, the last
+/// file or URL is reloaded after the restart (paused, from the start).
+#[tauri::command]
+pub async fn mpv_reconnect_on_crash(
+    state: tauri::State<'_, MpvState>,
+    enabled: bool,
+) -> Result<()> {
+    let mut lock = state.0.lock().await;
+    let inner = lock
+        .as_mut()
+        .ok_or_else(|| Error::Internal("mpv not running — call mpv_load first".into()))?;
+    inner.auto_restart = enabled;
+    Ok(())
+}
+
 /// Quit the mpv daemon entirely and clean up the socket.
 #[tauri::command]
 pub async fn mpv_quit(state: tauri::State<'_, MpvState>) -> Result<()> {
     let mut lock = state.0.lock().await;
-    if let Some(mut inner) = lock.take() {
+    if let Some(inner) = lock.take() {
         let _ = inner.cmd_tx.try_send(r#"{"command":["quit"]}"#.into());
         tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
-        let _ = inner._child.kill().await;
+        let _ = inner.kill_tx.send(());
+        #[cfg(unix)]
         let _ = tokio::fs::remove_file(&inner.socket_path).await;
     }
     Ok(())
@@ -20,8 +20,11 @@ pub async fn load_project(path: String) -> Result<Project> {
 }
 
 /// Save the current project
+///
+/// Assets added since the last save are fingerprinted first.
 #[command]
-pub async fn save_project(project: Project, path: Option<String>) -> Result<()> {
+pub async fn save_project(mut project: Project, path: Option<String>) -> Result<()> {
     let path = path.map(PathBuf::from);
+    ProjectManager::fill_fingerprints(&mut project);
     ProjectManager::save(&project, path.as_deref())
 }
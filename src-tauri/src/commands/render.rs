@@ -1,17 +1,23 @@
 //! Render-related Tauri commands
 
-use crate::render::{RenderManager, RenderProgress, RenderSettings};
-use crate::Result;
-use tauri::command;
+use crate::render::{
+    RenderManager, RenderPreset, RenderPresetLibrary, RenderProgress, RenderSettings,
+};
+use crate::{Error, Result};
+use tauri::{command, AppHandle, Manager};
 
 /// Start a render job
+///
+/// Progress is emitted as `render-progress` events with the same payload as
+/// `get_render_progress`, whose `jobId` tells concurrent jobs apart.
 #[command]
 pub async fn start_render(
+    app: AppHandle,
     project_path: String,
     settings: RenderSettings,
     output_path: String,
 ) -> Result<String> {
-    RenderManager::start_render(&project_path, settings, &output_path).await
+    RenderManager::start_render(app, &project_path, settings, &output_path).await
 }
 
 /// Cancel a running render job
@@ -20,8 +26,47 @@ pub async fn cancel_render(job_id: String) -> Result<()> {
     RenderManager::cancel_render(&job_id)
 }
 
+/// Set how many render jobs may run at once; the rest wait in a queue
+#[command]
+pub async fn set_max_concurrent_renders(max: usize) -> Result<()> {
+    RenderManager::set_max_concurrent(max);
+    Ok(())
+}
+
 /// Get the progress of a render job
+///
+/// A finished job can be looked up for ten minutes, then it is forgotten.
 #[command]
 pub async fn get_render_progress(job_id: String) -> Result<RenderProgress> {
     RenderManager::get_progress(&job_id)
 }
+
+/// List the render presets for one-click export
+///
+/// The library starts out with the built-in presets (YouTube, Twitter,
+/// Discord, lossless and so on).
+#[command]
+pub async fn get_render_presets(app: AppHandle) -> Result<Vec<RenderPreset>> {
+    preset_library(&app)?.list()
+}
+
+/// Add a render preset, replacing the one with the same name
+#[command]
+pub async fn save_render_preset(app: AppHandle, preset: RenderPreset) -> Result<()> {
+    preset_library(&app)?.save(preset)
+}
+
+/// Delete a render preset
+#[command]
+pub async fn delete_render_preset(app: AppHandle, name: String) -> Result<()> {
+    preset_library(&app)?.delete(&name)
+}
+
+/// The preset library in the app data directory
+fn preset_library(app: &AppHandle) -> Result<RenderPresetLibrary> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| Error::Internal(format!("No app data directory: {}", e)))?;
+    Ok(RenderPresetLibrary::new(&dir))
+}
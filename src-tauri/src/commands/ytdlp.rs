@@ -1,19 +1,130 @@
-//! yt-dlp integration — extract direct audio stream URLs from YouTube.
+//! yt-dlp integration — extract direct stream URLs, metadata and downloads from YouTube.
 //!
 //! All commands run `yt-dlp` as a subprocess (must be on PATH).
 //! The caller is responsible for falling back to the IFrame API if yt-dlp is
 //! unavailable or returns an error.
 
 use crate::error::{Error, Result};
-use tokio::process::Command;
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::Mutex;
+use tauri::Emitter;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
+use tokio::process::{Child, Command};
+
+lazy_static! {
+    /// Running `yt_download_file` processes, keyed by video ID.
+    static ref DOWNLOADS: Mutex<HashMap<String, Child>> = Mutex::new(HashMap::new());
+}
+
+/// Netscape-format cookie file passed to every yt-dlp call, for
+/// age-restricted and members-only videos.  Set with [`yt_set_cookies_path`].
+static YT_COOKIES_PATH: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// `--cookies <path>` if a cookie file is configured.
+fn cookie_args() -> Vec<OsString> {
+    match YT_COOKIES_PATH.lock().unwrap().as_ref() {
+        Some(path) => vec!["--cookies".into(), path.into()],
+        None => Vec::new(),
+    }
+}
 
 /// Information about a YouTube video, retrieved without downloading.
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct YtVideoInfo {
+    pub id: String,
     pub title: String,
     pub duration: f64,
 }
 
+/// One downloadable format of a YouTube video, as reported by yt-dlp.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct YtFormat {
+    pub format_id: String,
+    pub ext: String,
+    /// e.g. `"1920x1080"`, or `"audio only"`.
+    pub resolution: Option<String>,
+    pub fps: Option<f64>,
+    /// `"none"` for audio-only formats.
+    #[serde(default = "no_codec")]
+    pub vcodec: String,
+    /// `"none"` for video-only formats.
+    #[serde(default = "no_codec")]
+    pub acodec: String,
+    /// Exact size in bytes if known, otherwise yt-dlp's estimate.
+    pub filesize: Option<u64>,
+    /// Total bitrate in kbit/s.
+    pub tbr: Option<f64>,
+}
+
+fn no_codec() -> String {
+    "none".into()
+}
+
+/// Emitted as "yt-download-progress" for each progress line yt-dlp prints.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct YtDownloadProgress {
+    pub video_id: String,
+    /// 0–100.
+    pub percent: f64,
+    /// e.g. `"1.23MiB/s"`, if known.
+    pub speed: Option<String>,
+    /// e.g. `"00:05"`, if known.
+    pub eta: Option<String>,
+}
+
+/// Emitted as "yt-download-done" once a download finishes, fails or is
+/// cancelled.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct YtDownloadDone {
+    pub video_id: String,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+/// Parses a `[download]  42.3% of 10.00MiB at 1.23MiB/s ETA 00:05` line.
+fn parse_download_progress(video_id: &str, line: &str) -> Option<YtDownloadProgress> {
+    let mut tokens = line.strip_prefix("[download]")?.split_whitespace();
+    let percent = tokens.next()?.strip_suffix('%')?.parse().ok()?;
+    let (mut speed, mut eta) = (None, None);
+    while let Some(token) = tokens.next() {
+        let slot = match token {
+            "at" => &mut speed,
+            "ETA" => &mut eta,
+            _ => continue,
+        };
+        *slot = tokens
+            .next()
+            .filter(|v| !v.starts_with("Unknown"))
+            .map(str::to_string);
+    }
+    Some(YtDownloadProgress {
+        video_id: video_id.to_string(),
+        percent,
+        speed,
+        eta,
+    })
+}
+
+/// Runs `yt-dlp` with `args` and returns its stdout.
+async fn run_ytdlp(args: &[&str]) -> Result<String> {
+    let output = Command::new("yt-dlp")
+        .args(cookie_args())
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| Error::Internal(format!("yt-dlp not found: {e}")))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(Error::Internal(format!("yt-dlp: {stderr}")));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
 /// Returns `true` if `yt-dlp` is installed and reachable on PATH.
 #[tauri::command]
 pub async fn yt_check() -> bool {
@@ -25,6 +136,28 @@ pub async fn yt_check() -> bool {
         .unwrap_or(false)
 }
 
+/// Uses the cookie file at `path` for all later yt-dlp calls, e.g. one
+/// exported from a browser where the user is signed in to YouTube.
+#[tauri::command]
+pub async fn yt_set_cookies_path(path: String) -> Result<()> {
+    let path = PathBuf::from(path);
+    if !path.is_file() {
+        return Err(Error::NotFound(format!(
+            "Cookie file not found: {}",
+            path.display()
+        )));
+    }
+    *YT_COOKIES_PATH.lock().unwrap() = Some(path);
+    Ok(())
+}
+
+/// Stops passing a cookie file to yt-dlp.
+#[tauri::command]
+pub async fn yt_clear_cookies_path() -> Result<()> {
+    *YT_COOKIES_PATH.lock().unwrap() = None;
+    Ok(())
+}
+
 /// Returns the best-audio direct CDN URL for the given YouTube video ID.
 ///
 /// The returned URL is a time-limited `googlevideo.com` link (~6 h) that can
@@ -36,27 +169,18 @@ pub async fn yt_check() -> bool {
 #[tauri::command]
 pub async fn yt_get_audio_url(video_id: String) -> Result<String> {
     let yt_url = format!("https://www.youtube.com/watch?v={video_id}");
-    let output = Command::new("yt-dlp")
-        .args([
-            "--format",
-            // Prefer m4a (native browser support) → webm/opus → best available audio
-            "bestaudio[ext=m4a]/bestaudio[ext=webm]/bestaudio",
-            "--get-url",
-            "--no-playlist",
-            "--no-warnings",
-            "--",
-            &yt_url,
-        ])
-        .output()
-        .await
-        .map_err(|e| Error::Internal(format!("yt-dlp not found: {e}")))?;
+    let stdout = run_ytdlp(&[
+        "--format",
+        // Prefer m4a (native browser support) → webm/opus → best available audio
+        "bestaudio[ext=m4a]/bestaudio[ext=webm]/bestaudio",
+        "--get-url",
+        "--no-playlist",
+        "--no-warnings",
+        "--",
+        &yt_url,
+    ])
+    .await?;
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(Error::Internal(format!("yt-dlp: {stderr}")));
-    }
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
     // Take the first non-empty line (DASH gives multiple URLs)
     let url = stdout
         .lines()
@@ -75,32 +199,343 @@ pub async fn yt_get_audio_url(video_id: String) -> Result<String> {
 #[tauri::command]
 pub async fn yt_get_video_info(video_id: String) -> Result<YtVideoInfo> {
     let yt_url = format!("https://www.youtube.com/watch?v={video_id}");
-    let output = Command::new("yt-dlp")
+    let stdout = run_ytdlp(&[
+        "--print",
+        "%(title)s\n%(duration)s",
+        "--no-playlist",
+        "--no-warnings",
+        "--skip-download",
+        "--",
+        &yt_url,
+    ])
+    .await?;
+
+    let mut lines = stdout.lines();
+    let title = lines.next().unwrap_or("Unknown").trim().to_string();
+    let duration = lines
+        .next()
+        .and_then(|s| s.trim().parse::<f64>().ok())
+        .unwrap_or(0.0);
+
+    Ok(YtVideoInfo {
+        id: video_id,
+        title,
+        duration,
+    })
+}
+
+/// Returns `true` if the video is a live stream that is currently broadcasting.
+#[tauri::command]
+pub async fn yt_check_live(video_id: String) -> Result<bool> {
+    let yt_url = format!("https://www.youtube.com/watch?v={video_id}");
+    let stdout = run_ytdlp(&[
+        "--print",
+        "is_live",
+        "--no-playlist",
+        "--skip-download",
+        "--no-warnings",
+        "--",
+        &yt_url,
+    ])
+    .await?;
+
+    // yt-dlp prints Python booleans, or "NA" when unknown
+    Ok(stdout.trim() == "True")
+}
+
+/// Returns the URL of the highest-resolution thumbnail of a YouTube video.
+#[tauri::command]
+pub async fn yt_get_thumbnail_url(video_id: String) -> Result<String> {
+    let yt_url = format!("https://www.youtube.com/watch?v={video_id}");
+    let stdout = run_ytdlp(&[
+        "--print",
+        "thumbnail",
+        "--no-playlist",
+        "--skip-download",
+        "--no-warnings",
+        "--",
+        &yt_url,
+    ])
+    .await?;
+
+    stdout
+        .lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty() && *l != "NA")
+        .map(str::to_string)
+        .ok_or_else(|| Error::NotFound(format!("No thumbnail for {video_id}")))
+}
+
+/// Saves the highest-resolution thumbnail of a YouTube video as a JPEG at
+/// `output_path`.
+#[tauri::command]
+pub async fn yt_download_thumbnail(video_id: String, output_path: String) -> Result<()> {
+    let yt_url = format!("https://www.youtube.com/watch?v={video_id}");
+    run_ytdlp(&[
+        "--write-thumbnail",
+        "--convert-thumbnails",
+        "jpg",
+        "--skip-download",
+        // `-o` takes an output template, so a literal `%` must be doubled
+        "-o",
+        &output_path.replace('%', "%%"),
+        "--no-playlist",
+        "--no-warnings",
+        "--",
+        &yt_url,
+    ])
+    .await?;
+    Ok(())
+}
+
+/// Most entries [`yt_get_playlist_info`] returns, to guard against huge or
+/// endless (e.g. channel "mix") playlists.
+const MAX_PLAYLIST_ENTRIES: usize = 500;
+
+/// Lists the videos in a playlist without resolving each one, so it is fast
+/// even for long playlists.  Durations may be 0 when yt-dlp cannot tell
+/// without a full lookup.
+#[tauri::command]
+pub async fn yt_get_playlist_info(playlist_url: String) -> Result<Vec<YtVideoInfo>> {
+    let max = MAX_PLAYLIST_ENTRIES.to_string();
+    let stdout = run_ytdlp(&[
+        "--flat-playlist",
+        "--dump-json",
+        "--playlist-end",
+        &max,
+        "--no-warnings",
+        "--",
+        &playlist_url,
+    ])
+    .await?;
+
+    // One JSON object per line
+    let mut videos = Vec::new();
+    for line in stdout.lines().filter(|l| !l.trim().is_empty()) {
+        let entry: serde_json::Value = serde_json::from_str(line)?;
+        let Some(id) = entry.get("id").and_then(|v| v.as_str()) else {
+            continue;
+        };
+        videos.push(YtVideoInfo {
+            id: id.to_string(),
+            title: entry
+                .get("title")
+                .and_then(|v| v.as_str())
+                .unwrap_or("Unknown")
+                .to_string(),
+            duration: entry
+                .get("duration")
+                .and_then(|v| v.as_f64())
+                .unwrap_or(0.0),
+        });
+        if videos.len() == MAX_PLAYLIST_ENTRIES {
+            break;
+        }
+    }
+    Ok(videos)
+}
+
+/// Downloads the subtitles of a YouTube video in `lang` (e.g. `"en"`) as SRT
+/// into `output_dir`, preferring manual captions over auto-generated ones,
+/// and returns the absolute path of the `.srt` file.
+#[tauri::command]
+pub async fn yt_download_subtitles(
+    video_id: String,
+    lang: String,
+    output_dir: String,
+) -> Result<String> {
+    let yt_url = format!("https://www.youtube.com/watch?v={video_id}");
+    let dir = std::path::Path::new(&output_dir);
+    // `-o` takes an output template, so a literal `%` must be doubled
+    let template = std::path::Path::new(&output_dir.replace('%', "%%"))
+        .join("%(id)s.%(ext)s")
+        .to_string_lossy()
+        .into_owned();
+    run_ytdlp(&[
+        "--write-subs",
+        "--write-auto-subs",
+        "--sub-langs",
+        &lang,
+        "--sub-format",
+        "srt",
+        // YouTube rarely serves SRT itself, so convert whatever it has
+        "--convert-subs",
+        "srt",
+        "--skip-download",
+        "-o",
+        &template,
+        "--no-playlist",
+        "--no-warnings",
+        "--",
+        &yt_url,
+    ])
+    .await?;
+
+    // yt-dlp names the file `<id>.<lang>.srt`, or `<id>.<lang>-<variant>.srt`
+    // (e.g. `en-orig`) when only a regional or original track exists; other
+    // files in the directory, such as older downloads in another language,
+    // must not be picked up
+    let exact = format!("{video_id}.{lang}.srt");
+    let variant = format!("{video_id}.{lang}-");
+    let mut found = None;
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        if name == exact {
+            found = Some(path);
+            break;
+        }
+        if found.is_none() && name.starts_with(&variant) && name.ends_with(".srt") {
+            found = Some(path);
+        }
+    }
+    if let Some(path) = found {
+        return Ok(std::fs::canonicalize(&path)?.to_string_lossy().into_owned());
+    }
+    Err(Error::NotFound(format!(
+        "No {lang} subtitles found for {video_id}"
+    )))
+}
+
+/// Lists every format available for a YouTube video (e.g. to offer 1080p,
+/// 4K or audio-only before playback), sorted by total bitrate, highest first.
+///
+/// Uses `--dump-json` rather than `--list-formats`, which prints a table.
+#[tauri::command]
+pub async fn yt_list_formats(video_id: String) -> Result<Vec<YtFormat>> {
+    let yt_url = format!("https://www.youtube.com/watch?v={video_id}");
+    let stdout = run_ytdlp(&[
+        "--dump-json",
+        "--no-playlist",
+        "--no-warnings",
+        "--",
+        &yt_url,
+    ])
+    .await?;
+
+    let info: serde_json::Value = serde_json::from_str(&stdout)?;
+    let raw = info
+        .get("formats")
+        .and_then(|f| f.as_array())
+        .ok_or_else(|| Error::Internal("yt-dlp returned no formats".into()))?;
+
+    let mut formats = Vec::with_capacity(raw.len());
+    for f in raw {
+        let mut format: YtFormat = serde_json::from_value(f.clone())?;
+        if format.filesize.is_none() {
+            format.filesize = f.get("filesize_approx").and_then(|s| s.as_u64());
+        }
+        formats.push(format);
+    }
+
+    // Formats without a known bitrate sort last
+    formats.sort_by(|a, b| {
+        b.tbr
+            .partial_cmp(&a.tbr)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    Ok(formats)
+}
+
+/// Downloads a YouTube video in the given format (see [`yt_list_formats`]) to
+/// `output_path`.
+///
+/// Returns once yt-dlp has started; progress is reported through
+/// "yt-download-progress" events and the outcome through a final
+/// "yt-download-done" event.  Only one download per video ID can run at a time.
+#[tauri::command]
+pub async fn yt_download_file(
+    app: tauri::AppHandle,
+    video_id: String,
+    format_id: String,
+    output_path: String,
+) -> Result<()> {
+    let yt_url = format!("https://www.youtube.com/watch?v={video_id}");
+    let mut downloads = DOWNLOADS.lock().unwrap();
+    if downloads.contains_key(&video_id) {
+        return Err(Error::Internal(format!(
+            "{video_id} is already being downloaded"
+        )));
+    }
+
+    let mut child = Command::new("yt-dlp")
+        .args(cookie_args())
         .args([
-            "--print",
-            "%(title)s\n%(duration)s",
+            "-f",
+            &format_id,
+            // `-o` takes an output template, so a literal `%` must be doubled
+            "-o",
+            &output_path.replace('%', "%%"),
+            "--newline",
             "--no-playlist",
             "--no-warnings",
-            "--skip-download",
             "--",
             &yt_url,
         ])
-        .output()
-        .await
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
         .map_err(|e| Error::Internal(format!("yt-dlp not found: {e}")))?;
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    downloads.insert(video_id.clone(), child);
+    drop(downloads);
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(Error::Internal(format!("yt-dlp: {stderr}")));
-    }
+    tokio::spawn(async move {
+        let stderr_task = tokio::spawn(async move {
+            let mut text = String::new();
+            if let Some(mut stderr) = stderr {
+                let _ = stderr.read_to_string(&mut text).await;
+            }
+            text
+        });
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let mut lines = stdout.lines();
-    let title = lines.next().unwrap_or("Unknown").trim().to_string();
-    let duration = lines
-        .next()
-        .and_then(|s| s.trim().parse::<f64>().ok())
-        .unwrap_or(0.0);
+        if let Some(stdout) = stdout {
+            let mut lines = BufReader::new(stdout).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                if let Some(progress) = parse_download_progress(&video_id, &line) {
+                    let _ = app.emit("yt-download-progress", progress);
+                }
+            }
+        }
 
-    Ok(YtVideoInfo { title, duration })
+        // A cancelled download has already been removed (and killed).
+        let child = DOWNLOADS.lock().unwrap().remove(&video_id);
+        let error = match child {
+            None => Some("Cancelled".to_string()),
+            Some(mut child) => match child.wait().await {
+                Ok(status) if status.success() => None,
+                Ok(_) => Some(format!(
+                    "yt-dlp: {}",
+                    stderr_task.await.unwrap_or_default().trim()
+                )),
+                Err(e) => Some(format!("yt-dlp: {e}")),
+            },
+        };
+        let _ = app.emit(
+            "yt-download-done",
+            YtDownloadDone {
+                video_id,
+                success: error.is_none(),
+                error,
+            },
+        );
+    });
+    Ok(())
+}
+
+/// Cancels a download started with [`yt_download_file`].  yt-dlp may leave a
+/// `.part` file behind.
+#[tauri::command]
+pub async fn yt_cancel_download(video_id: String) -> Result<()> {
+    let child = DOWNLOADS.lock().unwrap().remove(&video_id);
+    match child {
+        Some(mut child) => child
+            .kill()
+            .await
+            .map_err(|e| Error::Internal(format!("Failed to stop yt-dlp: {e}"))),
+        None => Err(Error::NotFound(format!(
+            "No download running for {video_id}"
+        ))),
+    }
 }
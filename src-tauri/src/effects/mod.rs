@@ -2,6 +2,110 @@
 
 use crate::Error;
 use serde_json::Value;
+use std::path::Path;
+
+/// Effects that produce audio filters (applied with `-af`)
+const AUDIO_EFFECTS: &[&str] = &["audio-gain", "loudnorm", "atempo", "pitch"];
+
+/// Whether `effect_id` is an audio effect rather than a video one
+pub fn is_audio_effect(effect_id: &str) -> bool {
+    AUDIO_EFFECTS.contains(&effect_id)
+}
+
+/// Video and audio filter chains built from a list of effects
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FilterChain {
+    /// Comma-joined video filters, or `None` if there are none
+    pub video: Option<String>,
+    /// Comma-joined audio filters, or `None` if there are none
+    pub audio: Option<String>,
+}
+
+/// Parameters [`InputFormat::fill`] adds for effects that need them
+const INPUT_WIDTH: &str = "input_width";
+const INPUT_HEIGHT: &str = "input_height";
+const INPUT_FPS: &str = "input_fps";
+const INPUT_STILL: &str = "input_still";
+
+/// Size, frame rate and kind of the video an effect is applied to, which
+/// some filters (`zoompan`) can't work out themselves
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InputFormat {
+    pub width: u32,
+    pub height: u32,
+    pub frame_rate: f64,
+    /// A single image rather than a video
+    pub still: bool,
+}
+
+impl InputFormat {
+    /// Whether `effect_id` needs the [`InputFormat`] in its parameters
+    pub fn needed_by(effect_id: &str) -> bool {
+        effect_id == "zoompan"
+    }
+
+    /// Whether a file of container `format` lasting `duration` seconds is a
+    /// still image (e.g. "image2" or "png_pipe", or no duration)
+    pub fn is_still(format: &str, duration: f64) -> bool {
+        duration <= 0.0 || format == "image2" || format.ends_with("_pipe")
+    }
+
+    /// `parameters` of `effect_id` with this input's format added, if the
+    /// effect needs it
+    pub fn fill(&self, effect_id: &str, parameters: &Value) -> Value {
+        if !Self::needed_by(effect_id) {
+            return parameters.clone();
+        }
+        let mut map = parameters.as_object().cloned().unwrap_or_default();
+        map.insert(INPUT_WIDTH.into(), self.width.into());
+        map.insert(INPUT_HEIGHT.into(), self.height.into());
+        map.insert(INPUT_FPS.into(), self.frame_rate.into());
+        map.insert(INPUT_STILL.into(), self.still.into());
+        Value::Object(map)
+    }
+}
+
+/// Video and audio filters for a single effect
+///
+/// Most effects only touch one stream, but `speed` retimes both: its video
+/// half (what [`get_ffmpeg_filter`] returns) goes to `-vf` and its audio
+/// half to `-af`.
+pub fn get_effect_filters(effect_id: &str, parameters: &Value) -> Result<FilterChain, Error> {
+    let filter = get_ffmpeg_filter(effect_id, parameters)?;
+    if is_audio_effect(effect_id) {
+        return Ok(FilterChain {
+            video: None,
+            audio: Some(filter),
+        });
+    }
+    let audio = match effect_id {
+        "speed" => Some(atempo_chain(speed_factor(parameters))),
+        _ => None,
+    };
+    Ok(FilterChain {
+        video: Some(filter),
+        audio,
+    })
+}
+
+/// Build filter chains for several effects applied in order
+///
+/// Each effect is converted with [`get_effect_filters`] and its halves
+/// appended to the video and audio chains, preserving relative order.
+pub fn build_filter_chain(effects: &[(String, Value)]) -> Result<FilterChain, Error> {
+    let mut video = Vec::new();
+    let mut audio = Vec::new();
+    for (effect_id, parameters) in effects {
+        let filters = get_effect_filters(effect_id, parameters)?;
+        video.extend(filters.video);
+        audio.extend(filters.audio);
+    }
+    let join = |filters: Vec<String>| (!filters.is_empty()).then(|| filters.join(","));
+    Ok(FilterChain {
+        video: join(video),
+        audio: join(audio),
+    })
+}
 
 pub fn get_ffmpeg_filter(effect_id: &str, parameters: &Value) -> Result<String, Error> {
     match effect_id {
@@ -17,6 +121,34 @@ pub fn get_ffmpeg_filter(effect_id: &str, parameters: &Value) -> Result<String,
             let value = parameters["value"].as_f64().unwrap_or(1.0);
             Ok(format!("eq=saturation={}", value))
         }
+        "sepia" => {
+            let intensity = parameters["intensity"]
+                .as_f64()
+                .unwrap_or(0.5)
+                .clamp(0.0, 1.0);
+            if intensity == 0.0 {
+                return Ok("null".to_string());
+            }
+            // Blend each row of the classic sepia matrix with the identity row
+            // so intensity 1.0 is full sepia and 0.0 leaves the image untouched.
+            const SEPIA: [[f64; 3]; 3] = [
+                [0.393, 0.769, 0.189],
+                [0.349, 0.686, 0.168],
+                [0.272, 0.534, 0.131],
+            ];
+            let mut coeffs = Vec::with_capacity(12);
+            for (row, sepia_row) in SEPIA.iter().enumerate() {
+                for (col, sepia) in sepia_row.iter().enumerate() {
+                    let identity = if row == col { 1.0 } else { 0.0 };
+                    coeffs.push(format!("{:.3}", identity + (sepia - identity) * intensity));
+                }
+                coeffs.push("0".to_string());
+            }
+            Ok(format!("colorchannelmixer={}", coeffs.join(":")))
+        }
+        "lut" => lut_filter(parameters["path"].as_str().unwrap_or("")),
+        // Same filter under FFmpeg's name, with its own parameter name
+        "lut3d" => lut_filter(parameters["lut_path"].as_str().unwrap_or("")),
         "hue" => {
             let value = parameters["value"].as_f64().unwrap_or(0.0);
             Ok(format!("hue=h={}", value))
@@ -29,6 +161,69 @@ pub fn get_ffmpeg_filter(effect_id: &str, parameters: &Value) -> Result<String,
             let amount = parameters["amount"].as_f64().unwrap_or(0.0);
             Ok(format!("unsharp=luma_amount={}", amount))
         }
+        "split_tone" => {
+            let highlight =
+                rgb_fractions(parameters["highlight_color"].as_str().unwrap_or("#ffb060"))?;
+            let shadow = rgb_fractions(parameters["shadow_color"].as_str().unwrap_or("#3070c0"))?;
+            let balance = parameters["balance"]
+                .as_f64()
+                .unwrap_or(0.0)
+                .clamp(-1.0, 1.0);
+            // Balance moves the split between shadows and highlights: towards
+            // 1.0 more of the image gets the highlight tint
+            let pivot = 0.5 - 0.25 * balance;
+            let (shadow_x, highlight_x) = (pivot / 2.0, (1.0 + pivot) / 2.0);
+            // Each channel curve is lifted or lowered at the shadow and
+            // highlight points by how far the tint strays from neutral grey
+            let curve = |channel: usize| {
+                let shadow_y =
+                    (shadow_x + (shadow[channel] - 0.5) * SPLIT_TONE_STRENGTH).clamp(0.0, 1.0);
+                let highlight_y = (highlight_x + (highlight[channel] - 0.5) * SPLIT_TONE_STRENGTH)
+                    .clamp(0.0, 1.0);
+                format!(
+                    "'0/0 {:.3}/{:.3} {:.3}/{:.3} 1/1'",
+                    shadow_x, shadow_y, highlight_x, highlight_y
+                )
+            };
+            Ok(format!(
+                "curves=r={}:g={}:b={}",
+                curve(0),
+                curve(1),
+                curve(2)
+            ))
+        }
+        "pixelate" => {
+            let block = parameters["block_size"]
+                .as_u64()
+                .unwrap_or(16)
+                .clamp(2, 128);
+            // Fill each block with its average colour.  Unlike shrinking and
+            // scaling back up, this keeps the exact input size, with partial
+            // blocks at the right and bottom edges.
+            Ok(format!("pixelize=w={block}:h={block}"))
+        }
+        "chromatic_aberration" => {
+            let offset = |name: &str| {
+                parameters[name]
+                    .as_f64()
+                    .unwrap_or(0.0)
+                    .clamp(-MAX_CHANNEL_SHIFT, MAX_CHANNEL_SHIFT)
+                    .round() as i32
+            };
+            Ok(rgbashift(
+                (offset("rh"), offset("rv")),
+                (offset("bh"), offset("bv")),
+            ))
+        }
+        "fringe" => {
+            // Red and blue pulled apart horizontally, like a cheap lens
+            let amount = parameters["amount"]
+                .as_f64()
+                .unwrap_or(3.0)
+                .clamp(0.0, MAX_CHANNEL_SHIFT)
+                .round() as i32;
+            Ok(rgbashift((amount, 0), (-amount, 0)))
+        }
         "vignette" => {
             let intensity = parameters["intensity"].as_f64().unwrap_or(0.0);
             // A simple way to map intensity to FFmpeg's vignette angle
@@ -38,8 +233,644 @@ pub fn get_ffmpeg_filter(effect_id: &str, parameters: &Value) -> Result<String,
         "grain" => {
             let intensity = parameters["intensity"].as_f64().unwrap_or(0.0);
             let strength = (intensity * 50.0) as u32;
-            Ok(format!("noise=all_s={}:all_f=t", strength))
+            // t draws a new pattern every frame, like real grain; without it
+            // the same pattern sits over the picture.  u spreads the values
+            // evenly rather than clustering them around zero.
+            let flags = if parameters["temporal"].as_bool().unwrap_or(true) {
+                "t+u"
+            } else {
+                "u"
+            };
+            // Component 0 is luma; `all` adds chroma noise too, as the
+            // effect always did before it could be turned off
+            let components = if parameters["color"].as_bool().unwrap_or(true) {
+                "all"
+            } else {
+                "c0"
+            };
+            Ok(format!(
+                "noise={components}_s={strength}:{components}_f={flags}"
+            ))
+        }
+        "denoise" => {
+            let strength = parameters["strength"]
+                .as_f64()
+                .unwrap_or(0.5)
+                .clamp(0.0, 1.0);
+            let algorithm = parameters["algorithm"].as_str().unwrap_or("hqdn3d");
+            // hqdn3d values set directly still apply at strength 0
+            let explicit = algorithm == "hqdn3d"
+                && HQDN3D_VALUES
+                    .iter()
+                    .any(|name| parameters[*name].is_number());
+            if strength == 0.0 && !explicit {
+                return Ok("null".to_string());
+            }
+            // Strength 0.5 maps to the defaults of hqdn3d and atadenoise.
+            // nlmeans has nothing weaker than its default (s=1), so it starts
+            // there and reaches s=10 at full strength
+            match algorithm {
+                "hqdn3d" => {
+                    // Each value can also be set directly (0 to 10)
+                    let value = |name: &str, scale: f64| {
+                        parameters[name]
+                            .as_f64()
+                            .map_or(strength * scale, |v| v.clamp(0.0, 10.0))
+                    };
+                    Ok(format!(
+                        "hqdn3d={}:{}:{}:{}",
+                        value("luma_spatial", 8.0),
+                        value("chroma_spatial", 6.0),
+                        value("luma_temporal", 12.0),
+                        value("chroma_temporal", 9.0)
+                    ))
+                }
+                "nlmeans" => Ok(format!("nlmeans=s={}", 1.0 + strength * 9.0)),
+                "atadenoise" => {
+                    let a = strength * 0.04;
+                    let b = strength * 0.08;
+                    Ok(format!(
+                        "atadenoise=0a={a}:1a={a}:2a={a}:0b={b}:1b={b}:2b={b}"
+                    ))
+                }
+                other => Err(Error::Effect(format!(
+                    "Unknown denoise algorithm: {}",
+                    other
+                ))),
+            }
+        }
+        "denoise_nl" => {
+            let sigma = parameters["sigma"].as_f64().unwrap_or(1.0).clamp(1.0, 30.0);
+            // Patch and research sizes must be odd
+            let odd = |name: &str, default: u64| {
+                (parameters[name].as_u64().unwrap_or(default) | 1).min(99)
+            };
+            Ok(format!(
+                "nlmeans=s={}:p={}:r={}",
+                sigma,
+                odd("patch", 7),
+                odd("research", 15)
+            ))
+        }
+        "text" => text_filter(parameters),
+        // The same text, with a switch to hide it without losing the settings
+        "text_overlay" => {
+            if parameters["enabled"].as_bool().unwrap_or(true) {
+                text_filter(parameters)
+            } else {
+                Ok("null".to_string())
+            }
+        }
+        "chromakey" => {
+            let color = ffmpeg_color(parameters["color"].as_str().unwrap_or("#00ff00"))?;
+            let similarity = parameters["similarity"]
+                .as_f64()
+                .unwrap_or(0.1)
+                .clamp(0.0, 1.0);
+            let blend = parameters["blend"].as_f64().unwrap_or(0.0).clamp(0.0, 1.0);
+            Ok(format!(
+                "chromakey=color={}:similarity={}:blend={}",
+                color, similarity, blend
+            ))
+        }
+        "crop" => {
+            let relative = parameters["relative"].as_bool().unwrap_or(true);
+            let x = parameters["x"].as_f64().unwrap_or(0.0).max(0.0);
+            let y = parameters["y"].as_f64().unwrap_or(0.0).max(0.0);
+            if relative {
+                // Fractions of the input size, so the crop works at any resolution
+                let width = parameters["width"].as_f64().unwrap_or(1.0).clamp(0.0, 1.0);
+                let height = parameters["height"].as_f64().unwrap_or(1.0).clamp(0.0, 1.0);
+                Ok(format!(
+                    "crop=iw*{}:ih*{}:iw*{}:ih*{}",
+                    width,
+                    height,
+                    x.min(1.0),
+                    y.min(1.0)
+                ))
+            } else {
+                let width = parameters["width"].as_f64().unwrap_or(0.0).max(0.0) as u32;
+                let height = parameters["height"].as_f64().unwrap_or(0.0).max(0.0) as u32;
+                Ok(format!(
+                    "crop={}:{}:{}:{}",
+                    width, height, x as u32, y as u32
+                ))
+            }
+        }
+        "flip-horizontal" => Ok("hflip".to_string()),
+        "flip-vertical" => Ok("vflip".to_string()),
+        "transpose" => {
+            // 0 = 90° counter-clockwise + vertical flip, 1 = 90° clockwise,
+            // 2 = 90° counter-clockwise, 3 = 90° clockwise + vertical flip
+            let direction = parameters["direction"].as_u64().unwrap_or(1).min(3);
+            Ok(format!("transpose={}", direction))
+        }
+        "deinterlace" => {
+            // mode 0 outputs one frame per frame, 1 one frame per field
+            let mode = match parameters["mode"].as_str().unwrap_or("frame") {
+                "field" => 1,
+                _ => 0,
+            };
+            match parameters["algorithm"].as_str().unwrap_or("yadif") {
+                "yadif" => Ok(format!("yadif=mode={}", mode)),
+                "bwdif" => Ok(format!("bwdif=mode={}", mode)),
+                other => Err(Error::Effect(format!(
+                    "Unknown deinterlace algorithm: {}",
+                    other
+                ))),
+            }
+        }
+        "lens_correct" => {
+            let k1 = parameters["k1"].as_f64().unwrap_or(0.0).clamp(-1.0, 1.0);
+            let k2 = parameters["k2"].as_f64().unwrap_or(0.0).clamp(-1.0, 1.0);
+            Ok(lens_correction(k1, k2))
+        }
+        "fisheye" => Ok(lens_correction(GOPRO_K1, GOPRO_K2)),
+        "zoompan" => {
+            let zoom_start = parameters["zoom_start"]
+                .as_f64()
+                .unwrap_or(1.0)
+                .clamp(1.0, 10.0);
+            let zoom_end = parameters["zoom_end"]
+                .as_f64()
+                .unwrap_or(1.2)
+                .clamp(1.0, 10.0);
+            // Where the zoomed window sits: 0 = left/top, 0.5 = centered, 1 = right/bottom
+            let pan_x = parameters["pan_x"].as_f64().unwrap_or(0.5).clamp(0.0, 1.0);
+            let pan_y = parameters["pan_y"].as_f64().unwrap_or(0.5).clamp(0.0, 1.0);
+            let frames = parameters["duration_frames"]
+                .as_f64()
+                .unwrap_or(125.0)
+                .round()
+                .max(1.0);
+            let span = (frames - 1.0).max(1.0);
+            // A still becomes `duration_frames` output frames, counted by
+            // `on`; video gets one output frame per input frame, counted by
+            // `in`, so it keeps its length
+            let (counter, repeat) = if parameters[INPUT_STILL].as_bool().unwrap_or(false) {
+                ("on", frames)
+            } else {
+                ("in", 1.0)
+            };
+            let mut filter = format!(
+                "zoompan=z='{0}+({1}-{0})*min({2}/{3},1)':x='(iw-iw/zoom)*{4}':y='(ih-ih/zoom)*{5}':d={6}",
+                zoom_start, zoom_end, counter, span, pan_x, pan_y, repeat
+            );
+            // zoompan can't keep the input's size and rate by itself, so the
+            // caller supplies them (see `InputFormat`)
+            if let (Some(width), Some(height)) = (
+                parameters[INPUT_WIDTH].as_u64(),
+                parameters[INPUT_HEIGHT].as_u64(),
+            ) {
+                filter.push_str(&format!(":s={}x{}", width, height));
+            }
+            if let Some(fps) = parameters[INPUT_FPS].as_f64().filter(|f| *f > 0.0) {
+                filter.push_str(&format!(":fps={}", fps));
+            }
+            Ok(filter)
+        }
+        "audio-gain" => {
+            let gain = parameters["gain"]
+                .as_f64()
+                .unwrap_or(0.0)
+                .clamp(-60.0, 30.0);
+            Ok(format!("volume={}dB", gain))
+        }
+        "loudnorm" => {
+            let integrated = parameters["integrated"]
+                .as_f64()
+                .unwrap_or(-16.0)
+                .clamp(-70.0, -5.0);
+            let range = parameters["range"]
+                .as_f64()
+                .unwrap_or(11.0)
+                .clamp(1.0, 20.0);
+            let true_peak = parameters["true_peak"]
+                .as_f64()
+                .unwrap_or(-1.5)
+                .clamp(-9.0, 0.0);
+            let mut filter = format!("loudnorm=I={}:LRA={}:TP={}", integrated, range, true_peak);
+            if parameters["dual_mono"].as_bool().unwrap_or(false) {
+                filter.push_str(":dual_mono=true");
+            }
+            Ok(filter)
+        }
+        "atempo" => {
+            let factor = parameters["factor"]
+                .as_f64()
+                .unwrap_or(1.0)
+                .clamp(0.25, 4.0);
+            Ok(atempo_chain(factor))
+        }
+        // Video half only; see get_effect_filters
+        "speed" => Ok(format!("setpts={}*PTS", 1.0 / speed_factor(parameters))),
+        "pitch" => {
+            let semitones = parameters["semitones"]
+                .as_f64()
+                .unwrap_or(0.0)
+                .clamp(-12.0, 12.0);
+            if semitones == 0.0 {
+                return Ok("anull".to_string());
+            }
+            let sample_rate = parameters["sample_rate"]
+                .as_u64()
+                .unwrap_or(44100)
+                .clamp(8000, 192000);
+            // Resampling at a scaled rate shifts pitch and length together;
+            // atempo then restores the original length.
+            let ratio = 2f64.powf(semitones / 12.0);
+            Ok(format!(
+                "asetrate={},aresample={},{}",
+                (sample_rate as f64 * ratio).round() as u64,
+                sample_rate,
+                atempo_chain(1.0 / ratio)
+            ))
         }
         _ => Err(Error::Effect(format!("Unknown effect: {}", effect_id))),
     }
 }
+
+/// How much faster than normal an effect plays its input: the factor of a
+/// `speed` effect, 1.0 for everything else.  A clip with the effect lasts
+/// its source length divided by this.
+pub fn playback_speed(effect_id: &str, parameters: &Value) -> f64 {
+    match effect_id {
+        "speed" => speed_factor(parameters),
+        _ => 1.0,
+    }
+}
+
+/// `denoise` parameters that set hqdn3d's values directly
+const HQDN3D_VALUES: [&str; 4] = [
+    "luma_spatial",
+    "chroma_spatial",
+    "luma_temporal",
+    "chroma_temporal",
+];
+
+/// Playback speed of the `speed` effect (0.25 to 4.0)
+fn speed_factor(parameters: &Value) -> f64 {
+    parameters["speed"].as_f64().unwrap_or(1.0).clamp(0.25, 4.0)
+}
+
+/// Build an `atempo` chain for `factor`.  A single `atempo` only accepts
+/// 0.5–2.0, so larger changes are split into stages whose product is `factor`
+/// (e.g. 4.0 becomes `atempo=2,atempo=2`).
+fn atempo_chain(mut factor: f64) -> String {
+    let mut stages = Vec::new();
+    while factor > 2.0 {
+        stages.push(2.0);
+        factor /= 2.0;
+    }
+    while factor < 0.5 {
+        stages.push(0.5);
+        factor /= 0.5;
+    }
+    stages.push(factor);
+    stages
+        .iter()
+        .map(|s| format!("atempo={}", s))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// `drawtext` filter of the `text` and `text_overlay` effects
+fn text_filter(parameters: &Value) -> Result<String, Error> {
+    let text = parameters["text"].as_str().unwrap_or("");
+    let font_size = parameters["font_size"]
+        .as_u64()
+        .unwrap_or(48)
+        .clamp(1, 1000);
+    let color = ffmpeg_color(parameters["color"].as_str().unwrap_or("#ffffff"))?;
+    // Position as a fraction of the free space, so 0.5 centers the text
+    let x = parameters["x"].as_f64().unwrap_or(0.5).clamp(0.0, 1.0);
+    let y = parameters["y"].as_f64().unwrap_or(0.9).clamp(0.0, 1.0);
+
+    let mut filter = format!(
+        "drawtext=text={}",
+        escape_filter_value(&escape_drawtext(text))
+    );
+    if let Some(font) = parameters["font_file"].as_str().filter(|f| !f.is_empty()) {
+        filter.push_str(&format!(":fontfile={}", escape_filter_value(font)));
+    }
+    filter.push_str(&format!(
+        ":fontsize={}:fontcolor={}:x=(w-text_w)*{}:y=(h-text_h)*{}",
+        font_size, color, x, y
+    ));
+    Ok(filter)
+}
+
+/// `lut3d` filter for a .cube or .3dl file, which must exist
+fn lut_filter(path: &str) -> Result<String, Error> {
+    let has_lut_ext = Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.eq_ignore_ascii_case("cube") || e.eq_ignore_ascii_case("3dl"))
+        .unwrap_or(false);
+    if !has_lut_ext {
+        return Err(Error::InvalidPath(format!(
+            "LUT must be a .cube or .3dl file: {}",
+            path
+        )));
+    }
+    if !Path::new(path).is_file() {
+        return Err(Error::InvalidPath(format!("LUT file not found: {}", path)));
+    }
+    Ok(format!("lut3d=file={}", escape_filter_value(path)))
+}
+
+/// Convert a `#RRGGBB` (or `0xRRGGBB`) color into FFmpeg's `0xRRGGBB` form.
+pub fn ffmpeg_color(color: &str) -> Result<String, Error> {
+    let hex = color
+        .trim()
+        .trim_start_matches('#')
+        .trim_start_matches("0x")
+        .trim_start_matches("0X");
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(Error::Effect(format!("Invalid color: {}", color)));
+    }
+    Ok(format!("0x{}", hex.to_ascii_uppercase()))
+}
+
+/// Escape drawtext's own text expansion: a backslash makes the next character
+/// literal and `%` starts a `%{...}` expansion.  The result still needs
+/// [`escape_filter_value`].
+fn escape_drawtext(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '%') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Radial distortion coefficients undoing the barrel distortion of GoPro
+/// Hero cameras in their wide field of view
+const GOPRO_K1: f64 = -0.227;
+const GOPRO_K2: f64 = -0.022;
+
+/// `lenscorrection` about the frame centre with radial coefficients `k1` and
+/// `k2`; negative values pull the bulging edges of barrel distortion back in
+fn lens_correction(k1: f64, k2: f64) -> String {
+    format!("lenscorrection=k1={}:k2={}:i=bilinear", k1, k2)
+}
+
+/// Largest chromatic aberration shift of a channel, in pixels
+const MAX_CHANNEL_SHIFT: f64 = 20.0;
+
+/// `rgbashift` moving the red and blue channels by (horizontal, vertical)
+/// pixels; the uncovered edge repeats the nearest pixels
+fn rgbashift((rh, rv): (i32, i32), (bh, bv): (i32, i32)) -> String {
+    format!("rgbashift=rh={rh}:rv={rv}:bh={bh}:bv={bv}")
+}
+
+/// How far a split tone colour can move a channel curve (0 to 1)
+const SPLIT_TONE_STRENGTH: f64 = 0.2;
+
+/// A hex colour as red, green and blue fractions (0.0 to 1.0)
+fn rgb_fractions(color: &str) -> Result<[f64; 3], Error> {
+    let hex = ffmpeg_color(color)?;
+    let value = u32::from_str_radix(&hex[2..], 16)
+        .map_err(|_| Error::Effect(format!("Invalid color: {}", color)))?;
+    Ok([16, 8, 0].map(|shift| ((value >> shift) & 0xff) as f64 / 255.0))
+}
+
+/// Escape a string (e.g. a file path) for use as a filter option value.
+///
+/// Filter graphs are unescaped twice: once when the graph is split into
+/// filters and once when each filter's options are parsed.  The value is first
+/// escaped for the option level (`\`, `'` and the `:` separator, which also
+/// covers Windows drive letters) and then single-quoted for the graph level.
+pub fn escape_filter_value(path: &str) -> String {
+    let mut option_level = String::with_capacity(path.len());
+    for c in path.chars() {
+        if matches!(c, '\\' | '\'' | ':') {
+            option_level.push('\\');
+        }
+        option_level.push(c);
+    }
+    format!("'{}'", option_level.replace('\'', "'\\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn filter(effect_id: &str, parameters: Value) -> String {
+        get_ffmpeg_filter(effect_id, &parameters).unwrap()
+    }
+
+    #[test]
+    fn denoise_defaults_to_hqdn3d() {
+        assert_eq!(filter("denoise", json!({})), "hqdn3d=4:3:6:4.5");
+        assert_eq!(filter("denoise", json!({ "strength": 0.0 })), "null");
+        assert_eq!(
+            filter("denoise", json!({ "strength": 0.0, "luma_spatial": 2.0 })),
+            "hqdn3d=2:0:0:0"
+        );
+    }
+
+    #[test]
+    fn denoise_algorithms() {
+        assert_eq!(
+            filter(
+                "denoise",
+                json!({ "algorithm": "atadenoise", "strength": 1.0 })
+            ),
+            "atadenoise=0a=0.04:1a=0.04:2a=0.04:0b=0.08:1b=0.08:2b=0.08"
+        );
+        assert_eq!(
+            filter(
+                "denoise",
+                json!({ "algorithm": "nlmeans", "strength": 0.5 })
+            ),
+            "nlmeans=s=5.5"
+        );
+        assert!(get_ffmpeg_filter("denoise", &json!({ "algorithm": "bogus" })).is_err());
+    }
+
+    #[test]
+    fn denoise_nl_defaults_and_odd_sizes() {
+        assert_eq!(filter("denoise_nl", json!({})), "nlmeans=s=1:p=7:r=15");
+        assert_eq!(
+            filter(
+                "denoise_nl",
+                json!({ "sigma": 50.0, "patch": 8, "research": 200 })
+            ),
+            "nlmeans=s=30:p=9:r=99"
+        );
+    }
+
+    #[test]
+    fn lens_correct_clamps_coefficients() {
+        assert_eq!(
+            filter("lens_correct", json!({ "k1": 1.0, "k2": -1.0 })),
+            "lenscorrection=k1=1:k2=-1:i=bilinear"
+        );
+        assert_eq!(
+            filter("lens_correct", json!({ "k1": -2.0, "k2": 2.0 })),
+            "lenscorrection=k1=-1:k2=1:i=bilinear"
+        );
+        assert_eq!(
+            filter("fisheye", json!({})),
+            "lenscorrection=k1=-0.227:k2=-0.022:i=bilinear"
+        );
+    }
+
+    #[test]
+    fn chromatic_aberration_clamps_shifts() {
+        assert_eq!(
+            filter(
+                "chromatic_aberration",
+                json!({ "rh": 20.0, "rv": -20.0, "bh": 35.0, "bv": -35.0 })
+            ),
+            "rgbashift=rh=20:rv=-20:bh=20:bv=-20"
+        );
+    }
+
+    #[test]
+    fn fringe_shifts_red_and_blue_apart() {
+        assert_eq!(
+            filter("fringe", json!({ "amount": 4.0 })),
+            "rgbashift=rh=4:rv=0:bh=-4:bv=0"
+        );
+        assert_eq!(
+            filter("fringe", json!({ "amount": 50.0 })),
+            "rgbashift=rh=20:rv=0:bh=-20:bv=0"
+        );
+    }
+
+    #[test]
+    fn atempo_chain_splits_out_of_range_factors() {
+        assert_eq!(atempo_chain(0.25), "atempo=0.5,atempo=0.5");
+        assert_eq!(atempo_chain(4.0), "atempo=2,atempo=2");
+        assert_eq!(atempo_chain(1.5), "atempo=1.5");
+    }
+
+    #[test]
+    fn build_filter_chain_joins_video_effects() {
+        let chain = build_filter_chain(&[
+            ("brightness".to_string(), json!({ "value": 1.5 })),
+            ("contrast".to_string(), json!({ "value": 1.2 })),
+            ("saturation".to_string(), json!({ "value": 0.8 })),
+        ])
+        .unwrap();
+        assert_eq!(
+            chain.video.as_deref(),
+            Some("eq=brightness=0.5,eq=contrast=1.2,eq=saturation=0.8")
+        );
+        assert_eq!(chain.audio, None);
+    }
+
+    #[test]
+    fn chromakey_defaults_and_clamping() {
+        assert_eq!(
+            filter("chromakey", json!({})),
+            "chromakey=color=0x00FF00:similarity=0.1:blend=0"
+        );
+        assert_eq!(
+            filter(
+                "chromakey",
+                json!({ "color": "#0000ff", "similarity": 2.0, "blend": 0.25 })
+            ),
+            "chromakey=color=0x0000FF:similarity=1:blend=0.25"
+        );
+        assert!(get_ffmpeg_filter("chromakey", &json!({ "color": "green" })).is_err());
+    }
+
+    #[test]
+    fn zoompan_runs_frame_for_frame_on_video() {
+        let input = InputFormat {
+            width: 1440,
+            height: 1080,
+            frame_rate: 25.0,
+            still: false,
+        };
+        let parameters = json!({ "zoom_end": 1.5, "duration_frames": 50.0 });
+        let f = filter("zoompan", input.fill("zoompan", &parameters));
+        assert!(f.contains("min(in/49,1)"), "{f}");
+        assert!(f.ends_with(":d=1:s=1440x1080:fps=25"), "{f}");
+    }
+
+    #[test]
+    fn zoompan_animates_a_still() {
+        let input = InputFormat {
+            width: 4000,
+            height: 3000,
+            frame_rate: 30.0,
+            still: true,
+        };
+        let parameters = json!({ "duration_frames": 90 });
+        let f = filter("zoompan", input.fill("zoompan", &parameters));
+        assert!(f.contains("min(on/89,1)"), "{f}");
+        assert!(f.ends_with(":d=90:s=4000x3000:fps=30"), "{f}");
+        assert!(InputFormat::is_still("png_pipe", 0.04));
+        assert!(!InputFormat::is_still("mov,mp4,m4a,3gp,3g2,mj2", 10.0));
+    }
+
+    #[test]
+    fn grain_is_in_color_by_default() {
+        assert_eq!(
+            filter("grain", json!({"intensity": 0.2})),
+            "noise=all_s=10:all_f=t+u"
+        );
+        assert_eq!(
+            filter("grain", json!({"intensity": 0.2, "color": false})),
+            "noise=c0_s=10:c0_f=t+u"
+        );
+    }
+
+    #[test]
+    fn pixelate_keeps_the_input_size() {
+        // pixelize averages blocks in place, so unlike scaling down and up
+        // again it can't change the frame size
+        assert_eq!(
+            filter("pixelate", json!({ "block_size": 16 })),
+            "pixelize=w=16:h=16"
+        );
+        assert_eq!(
+            filter("pixelate", json!({ "block_size": 500 })),
+            "pixelize=w=128:h=128"
+        );
+    }
+
+    #[test]
+    fn lut3d_checks_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let cube = dir.path().join("look.cube");
+        std::fs::write(&cube, "LUT_3D_SIZE 2\n").unwrap();
+        let path = cube.to_str().unwrap();
+        assert_eq!(
+            filter("lut3d", json!({ "lut_path": path })),
+            format!("lut3d=file={}", escape_filter_value(path))
+        );
+        let missing = dir.path().join("missing.cube");
+        let wrong_ext = dir.path().join("look.png");
+        for path in [&missing, &wrong_ext] {
+            let parameters = json!({ "lut_path": path.to_str().unwrap() });
+            assert!(matches!(
+                get_ffmpeg_filter("lut3d", &parameters),
+                Err(Error::InvalidPath(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn text_overlay_shares_text_and_can_be_disabled() {
+        let parameters = json!({ "text": "It's 5:00", "font_size": 32 });
+        assert_eq!(
+            filter("text_overlay", parameters.clone()),
+            filter("text", parameters)
+        );
+        assert_eq!(
+            filter(
+                "text_overlay",
+                json!({ "text": "Hidden", "enabled": false })
+            ),
+            "null"
+        );
+    }
+}
@@ -3,9 +3,11 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 use std::sync::Arc;
+use tauri::Manager;
 use tokio::sync::Mutex;
 use waldiez_player_lib::commands;
 use waldiez_player_lib::commands::mpv::{MpvInner, MpvState};
+use waldiez_player_lib::render::RenderManager;
 
 fn main() {
     // Initialize logger
@@ -22,8 +24,27 @@ fn main() {
         .invoke_handler(tauri::generate_handler![
             // Media commands
             commands::media::get_media_info,
+            commands::media::get_media_fingerprint,
+            commands::media::invalidate_media_cache,
+            commands::media::clear_media_cache,
             commands::media::extract_thumbnail,
+            commands::media::extract_thumbnails,
+            commands::media::extract_storyboard,
+            commands::media::generate_preview_clip,
+            commands::media::extract_cover_art,
+            commands::media::extract_subtitles,
             commands::media::extract_audio_waveform,
+            commands::media::clear_waveform_cache,
+            commands::media::detect_scenes,
+            commands::media::analyze_video_quality,
+            commands::media::verify_media,
+            commands::media::detect_interlacing,
+            commands::media::analyze_frame_timing,
+            commands::media::extract_spectrogram,
+            commands::media::analyze_tempo,
+            commands::media::generate_proxy,
+            commands::media::cancel_proxy,
+            commands::media::extract_audio,
             // Project commands
             commands::project::create_project,
             commands::project::load_project,
@@ -32,16 +53,33 @@ fn main() {
             commands::render::start_render,
             commands::render::cancel_render,
             commands::render::get_render_progress,
+            commands::render::set_max_concurrent_renders,
+            commands::render::get_render_presets,
+            commands::render::save_render_preset,
+            commands::render::delete_render_preset,
             // Effect commands
             commands::effects::apply_effect,
+            commands::effects::cancel_effect,
+            commands::effects::get_effect_progress,
             commands::effects::get_available_effects,
             // yt-dlp commands
             commands::ytdlp::yt_check,
+            commands::ytdlp::yt_set_cookies_path,
+            commands::ytdlp::yt_clear_cookies_path,
             commands::ytdlp::yt_get_audio_url,
             commands::ytdlp::yt_get_video_info,
+            commands::ytdlp::yt_check_live,
+            commands::ytdlp::yt_get_thumbnail_url,
+            commands::ytdlp::yt_download_thumbnail,
+            commands::ytdlp::yt_get_playlist_info,
+            commands::ytdlp::yt_download_subtitles,
+            commands::ytdlp::yt_list_formats,
+            commands::ytdlp::yt_download_file,
+            commands::ytdlp::yt_cancel_download,
             // mpv commands
             commands::mpv::mpv_check,
             commands::mpv::mpv_load,
+            commands::mpv::mpv_get_property,
             commands::mpv::mpv_pause,
             commands::mpv::mpv_resume,
             commands::mpv::mpv_seek,
@@ -49,8 +87,33 @@ fn main() {
             commands::mpv::mpv_set_speed,
             commands::mpv::mpv_stop,
             commands::mpv::mpv_quit,
+            commands::mpv::mpv_playlist_append,
+            commands::mpv::mpv_playlist_remove,
+            commands::mpv::mpv_playlist_goto,
+            commands::mpv::mpv_set_audio_track,
+            commands::mpv::mpv_set_subtitle_track,
+            commands::mpv::mpv_get_track_list,
+            commands::mpv::mpv_add_subtitle_file,
+            commands::mpv::mpv_set_subtitle_delay,
+            commands::mpv::mpv_set_audio_delay,
+            commands::mpv::mpv_screenshot,
+            commands::mpv::mpv_set_video,
+            commands::mpv::mpv_set_loop,
+            commands::mpv::mpv_mute,
+            commands::mpv::mpv_toggle_mute,
+            commands::mpv::mpv_get_chapter_list,
+            commands::mpv::mpv_goto_chapter,
+            commands::mpv::mpv_get_hwdec_current,
+            commands::mpv::mpv_set_cache,
+            commands::mpv::mpv_set_video_zoom,
+            commands::mpv::mpv_set_pan,
+            commands::mpv::mpv_reconnect_on_crash,
+            commands::mpv::mpv_set_deinterlace,
+            commands::mpv::mpv_set_deinterlace_mode,
         ])
-        .setup(|_app| {
+        .setup(|app| {
+            // Presets looked up by name include the user's saved ones
+            RenderManager::set_preset_dir(app.path().app_data_dir().ok());
             log::info!("Waldiez Player initialized successfully");
             Ok(())
         })
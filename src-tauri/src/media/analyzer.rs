@@ -1,5 +1,14 @@
 //! Media file analyzer using FFmpeg
 
+use super::fingerprint::content_fingerprint;
+use super::integrity::{IntegrityChecker, IntegrityIssue, IntegrityReport};
+use super::interlace::{FieldOrder, InterlaceDetector, InterlaceReport};
+use super::quality::{QualityDetector, QualityThresholds, VideoQualityReport};
+use super::scenes::{SceneCut, SceneDetector};
+use super::spectrogram::render_spectrogram;
+use super::subtitles::{ass_to_text, to_srt, SubtitleCue};
+use super::tempo::{analyze_tempo, TempoAnalysis};
+use super::timing::{FrameTiming, FrameTimingCollector};
 use super::WaveformData;
 use crate::media::info::AudioInfo;
 use crate::media::info::ChapterInfo;
@@ -8,22 +17,53 @@ use crate::media::info::SubtitleInfo;
 use crate::{Error, Result};
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use image::ImageEncoder;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::Path;
+use std::time::Duration;
 
 use ffmpeg_next::format::input;
+use ffmpeg_next::format::stream::Disposition;
 use ffmpeg_next::media::Type;
 use ffmpeg_next::{self as ffmpeg};
 
+/// How a thumbnail fills a box whose aspect ratio differs from the frame's
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThumbnailFit {
+    /// Scale to exactly the box, distorting the picture
+    #[default]
+    Stretch,
+    /// Fit inside the box, filling the rest with a background color
+    Contain,
+    /// Fill the box, cropping the overflow around the center
+    Cover,
+}
+
+/// Relative difference between a stream's base and average frame rates
+/// above which `get_info` reports it as variable frame rate
+const VFR_RATE_TOLERANCE: f64 = 0.1;
+
+/// URL schemes opened through FFmpeg's network protocols rather than the
+/// filesystem
+const REMOTE_SCHEMES: &[&str] = &["http://", "https://", "rtsp://"];
+
+/// How long opening or reading a remote input may block by default
+const DEFAULT_PROBE_TIMEOUT: Duration = Duration::from_secs(15);
+
 /// Media analyzer for extracting information from media files
 pub struct MediaAnalyzer {
     path: std::path::PathBuf,
+    /// Whether `path` is a network URL rather than a local file
+    remote: bool,
+    probe_timeout: Duration,
 }
 
 impl MediaAnalyzer {
-    /// Create a new media analyzer for the given path
+    /// Create a new media analyzer for the given path or http(s)/rtsp URL
     pub fn new(path: &Path) -> Result<Self> {
-        if !path.exists() {
+        let remote = is_remote_url(path);
+        if !remote && !path.exists() {
             return Err(Error::NotFound(format!(
                 "File not found: {}",
                 path.display()
@@ -35,12 +75,34 @@ impl MediaAnalyzer {
 
         Ok(Self {
             path: path.to_path_buf(),
+            remote,
+            probe_timeout: DEFAULT_PROBE_TIMEOUT,
         })
     }
 
+    /// Limit how long connecting to or reading from a remote input may block,
+    /// so a dead URL fails instead of hanging.  Has no effect on local files.
+    pub fn with_probe_timeout(mut self, timeout: Duration) -> Self {
+        self.probe_timeout = timeout;
+        self
+    }
+
+    /// Open the input, applying the probe timeout to remote URLs
+    fn open(&self) -> Result<ffmpeg::format::context::Input> {
+        if !self.remote {
+            return Ok(input(&self.path)?);
+        }
+        // `timeout` bounds connecting, `rw_timeout` each read (microseconds)
+        let micros = self.probe_timeout.as_micros().to_string();
+        let mut options = ffmpeg::Dictionary::new();
+        options.set("timeout", &micros);
+        options.set("rw_timeout", &micros);
+        Ok(ffmpeg::format::input_with_dictionary(&self.path, options)?)
+    }
+
     /// Get comprehensive information about the media file
     pub fn get_info(&self) -> Result<MediaInfo> {
-        let context = input(&self.path)?;
+        let context = self.open()?;
 
         let path_str = self.path.to_string_lossy().to_string();
         let name = self
@@ -49,21 +111,42 @@ impl MediaAnalyzer {
             .map(|n| n.to_string_lossy().to_string())
             .unwrap_or_default();
 
-        let size = std::fs::metadata(&self.path)?.len();
-        let duration = context.duration() as f64 / ffmpeg::ffi::AV_TIME_BASE as f64;
-        let format = context
-            .format()
-            .name()
-            .split(',')
-            .next()
-            .unwrap_or("unknown")
-            .to_string();
+        let size = if self.remote {
+            remote_size(&context)
+        } else {
+            std::fs::metadata(&self.path)?.len()
+        };
+        let image_format = still_image_format(&context);
+        let duration = if image_format.is_some() {
+            0.0
+        } else {
+            context.duration() as f64 / ffmpeg::ffi::AV_TIME_BASE as f64
+        };
+        let format = image_format.clone().unwrap_or_else(|| {
+            context
+                .format()
+                .name()
+                .split(',')
+                .next()
+                .unwrap_or("unknown")
+                .to_string()
+        });
+
+        // An image is a single frame with no meaningful rate
+        let still = |mut info: super::info::VideoInfo| {
+            if image_format.is_some() {
+                info.frame_rate = 0.0;
+                info.frame_count = Some(1);
+            }
+            info
+        };
 
         // Extract video info
         let video = context
             .streams()
             .best(Type::Video)
-            .and_then(|stream| self.extract_video_info(&stream));
+            .and_then(|stream| self.extract_video_info(&stream))
+            .map(still);
 
         // Extract audio info
         let audio = context
@@ -71,12 +154,25 @@ impl MediaAnalyzer {
             .best(Type::Audio)
             .and_then(|stream| self.extract_audio_info(&stream));
 
+        // Extract every video and audio stream so callers can offer a track picker
+        let video_streams = context
+            .streams()
+            .filter(|s| s.parameters().medium() == Type::Video)
+            .filter_map(|stream| self.extract_video_info(&stream))
+            .map(still)
+            .collect();
+
+        let audio_streams = context
+            .streams()
+            .filter(|s| s.parameters().medium() == Type::Audio)
+            .filter_map(|stream| self.extract_audio_info(&stream))
+            .collect();
+
         // Extract subtitle info
         let subtitles = context
             .streams()
             .filter(|s| s.parameters().medium() == Type::Subtitle)
-            .enumerate()
-            .filter_map(|(i, stream)| self.extract_subtitle_info(i, &stream))
+            .filter_map(|stream| self.extract_subtitle_info(&stream))
             .collect();
 
         // Extract chapters
@@ -114,6 +210,8 @@ impl MediaAnalyzer {
             format,
             video,
             audio,
+            video_streams,
+            audio_streams,
             subtitles,
             chapters,
             metadata,
@@ -132,25 +230,67 @@ impl MediaAnalyzer {
 
         let v = decoder.video().ok()?; // if not video, return None
 
-        let width = v.width();
-        let height = v.height();
+        // Report the display orientation of rotated (e.g. phone) video
+        let rotation = stream_rotation(stream);
+        let (width, height) = if rotation % 180 == 0 {
+            (v.width(), v.height())
+        } else {
+            (v.height(), v.width())
+        };
 
         let fr = stream.avg_frame_rate();
         let frame_rate = fr.numerator() as f64 / fr.denominator().max(1) as f64;
+        // r_frame_rate is the lowest rate that represents every timestamp.
+        // A few dropped or duplicated frames move the average by well under
+        // VFR_RATE_TOLERANCE, so a bigger gap proves the rate varies;
+        // anything less is left to `analyze_frame_timing`
+        let base = stream.rate();
+        let base_rate = base.numerator() as f64 / base.denominator().max(1) as f64;
+        let is_vfr = (frame_rate > 0.0
+            && base_rate > 0.0
+            && (base_rate / frame_rate - 1.0).abs() > VFR_RATE_TOLERANCE)
+            .then_some(true);
 
         // bitrate is available on the stream parameters
         let br = v.bit_rate();
         let bit_rate = Some(br as u64).filter(|&b| b > 0);
 
+        // Pixel format name as ffmpeg reports it (e.g. "yuv420p10le")
+        let pixel_format = v
+            .format()
+            .descriptor()
+            .map(|d| d.name().to_string())
+            .unwrap_or_else(|| "unknown".to_string());
+
+        // Color metadata; `name()` is None for unspecified values
+        let color_space = v.color_space().name().map(String::from);
+        let color_primaries = v.color_primaries().name().map(String::from);
+        let color_transfer = v.color_transfer_characteristic().name().map(String::from);
+        // PQ (HDR10, Dolby Vision) or HLG
+        let is_hdr = matches!(
+            color_transfer.as_deref(),
+            Some("smpte2084" | "arib-std-b67")
+        );
+
         Some(super::info::VideoInfo {
+            index: stream.index(),
             codec: codec.name().to_string(),
             width,
             height,
             frame_rate,
+            is_vfr,
             bit_rate,
-            pixel_format: "unknown".to_string(), // you can fill this from `v.format()` if you want
-            color_space: None,
+            pixel_format,
+            color_space,
+            color_primaries,
+            color_transfer,
+            is_hdr,
+            mastering_display: mastering_display(stream),
+            content_light_level: content_light_level(stream),
             frame_count: Some(stream.frames() as u64).filter(|&f| f > 0),
+            language: stream.metadata().get("language").map(String::from),
+            field_order: declared_field_order(stream),
+            rotation,
         })
     }
 
@@ -174,34 +314,72 @@ impl MediaAnalyzer {
         let br = a.bit_rate();
         let bit_rate = Some(br as u64).filter(|&b| b > 0);
 
+        let disposition = stream.disposition();
         Some(AudioInfo {
+            index: stream.index(),
             codec: codec.name().to_string(),
             sample_rate,
             channels,
-            channel_layout: format!("{} channels", channels),
+            channel_layout: channel_layout_name(stream, channels),
             bit_rate,
             bits_per_sample: None,
+            language: stream.metadata().get("language").map(String::from),
+            title: stream.metadata().get("title").map(String::from),
+            is_default: disposition.contains(Disposition::DEFAULT),
+            is_forced: disposition.contains(Disposition::FORCED),
         })
     }
 
-    fn extract_subtitle_info(&self, index: usize, stream: &ffmpeg::Stream) -> Option<SubtitleInfo> {
+    fn extract_subtitle_info(&self, stream: &ffmpeg::Stream) -> Option<SubtitleInfo> {
         let params = stream.parameters();
         let codec = ffmpeg::decoder::find(params.id())?;
 
         let language = stream.metadata().get("language").map(String::from);
         let title = stream.metadata().get("title").map(String::from);
+        let disposition = stream.disposition();
 
         Some(SubtitleInfo {
-            index,
+            index: stream.index(),
             codec: codec.name().to_string(),
             language,
             title,
+            is_default: disposition.contains(Disposition::DEFAULT),
+            is_forced: disposition.contains(Disposition::FORCED),
         })
     }
 
+    /// Compute a content fingerprint for matching moved or renamed files
+    ///
+    /// Combines the file size, container duration and a hash of the start,
+    /// middle and end of the file.  Copies of a file produce the same
+    /// fingerprint; truncated or re-encoded files do not.
+    pub fn fingerprint(&self) -> Result<String> {
+        if self.remote {
+            return Err(Error::InvalidFormat(
+                "Remote media cannot be fingerprinted".to_string(),
+            ));
+        }
+        let context = input(&self.path)?;
+        content_fingerprint(&self.path, context.duration())
+    }
+
     /// Extract a thumbnail at the specified timestamp
-    pub fn extract_thumbnail(&self, timestamp: f64, width: u32, height: u32) -> Result<String> {
-        let mut context = input(&self.path)?;
+    ///
+    /// Returns the first frame whose presentation time is at or after
+    /// `timestamp`, or the last decodable frame if `timestamp` is past the end.
+    ///
+    /// `fit` decides how a frame whose aspect ratio differs from
+    /// `width`x`height` fills it; `background` fills the bars left by
+    /// [`ThumbnailFit::Contain`].
+    pub fn extract_thumbnail(
+        &self,
+        timestamp: f64,
+        width: u32,
+        height: u32,
+        fit: ThumbnailFit,
+        background: [u8; 3],
+    ) -> Result<String> {
+        let mut context = self.open()?;
 
         // Find video stream
         let video_stream_index = context
@@ -210,112 +388,451 @@ impl MediaAnalyzer {
             .ok_or_else(|| Error::Media("No video stream found".to_string()))?
             .index();
 
-        // Seek to timestamp
-        let seek_target = (timestamp * ffmpeg::ffi::AV_TIME_BASE as f64) as i64;
-        context.seek(seek_target, seek_target..seek_target)?;
+        // An image is its own thumbnail, whatever the timestamp
+        let timestamp = match still_image_format(&context) {
+            Some(_) => 0.0,
+            None => timestamp,
+        };
 
-        // Get decoder
-        let stream = context.stream(video_stream_index).unwrap();
-        let decoder_codec = ffmpeg::decoder::find(stream.parameters().id())
-            .ok_or_else(|| Error::Media("Could not find decoder".to_string()))?;
+        let mut decoder = open_video_decoder(&context.stream(video_stream_index).unwrap())?;
+        let frame = decode_frame_at(
+            &mut context,
+            &mut decoder,
+            video_stream_index,
+            timestamp,
+            timestamp > 0.0,
+        )?;
 
-        let mut decoder = ffmpeg::codec::context::Context::new_with_codec(decoder_codec)
-            .decoder()
-            .video()?;
+        let rotation = stream_rotation(&context.stream(video_stream_index).unwrap());
+        frame_to_png_data_url(&mut None, &frame, width, height, fit, background, rotation)
+    }
 
-        // Decode frames until we get one
-        let mut frame = ffmpeg::frame::Video::empty();
+    /// Extract thumbnails at several timestamps, returned in the order given
+    ///
+    /// Much faster than repeated [`extract_thumbnail`](Self::extract_thumbnail)
+    /// calls; see [`render_frames_at`](Self::render_frames_at).
+    pub fn extract_thumbnails(
+        &self,
+        timestamps: &[f64],
+        width: u32,
+        height: u32,
+        fit: ThumbnailFit,
+        background: [u8; 3],
+    ) -> Result<Vec<String>> {
+        self.render_frames_at(timestamps, |scaler, frame, rotation| {
+            frame_to_png_data_url(scaler, frame, width, height, fit, background, rotation)
+        })
+    }
 
-        for (stream, packet) in context.packets() {
-            if stream.index() == video_stream_index {
-                decoder.send_packet(&packet)?;
+    /// Extract stretched thumbnails at several timestamps, returned in the
+    /// order given, e.g. for a filmstrip
+    ///
+    /// Shorthand for [`extract_thumbnails`](Self::extract_thumbnails) with
+    /// [`ThumbnailFit::Stretch`].  Timestamps past the end give the last
+    /// decodable frame.
+    pub fn extract_thumbnails_at(
+        &self,
+        timestamps: &[f64],
+        width: u32,
+        height: u32,
+    ) -> Result<Vec<String>> {
+        self.extract_thumbnails(timestamps, width, height, ThumbnailFit::Stretch, [0, 0, 0])
+    }
 
-                if decoder.receive_frame(&mut frame).is_ok() {
-                    break;
+    /// Tile `cols`x`rows` thumbnails of `thumb_width`x`thumb_height`, taken
+    /// every `duration / (cols * rows)` seconds from the start, into one PNG
+    /// sprite sheet returned as a data URL
+    ///
+    /// Tiles run left to right, then top to bottom, so tile `i` shows time
+    /// `i * duration / (cols * rows)`.  A video with fewer frames than tiles
+    /// still fills the grid: tiles between two frames repeat the earlier one
+    /// and tiles past the end repeat the last.  Frames are scaled to cover
+    /// their tile.
+    pub fn extract_storyboard(
+        &self,
+        cols: u32,
+        rows: u32,
+        thumb_width: u32,
+        thumb_height: u32,
+    ) -> Result<String> {
+        if cols == 0 || rows == 0 || thumb_width == 0 || thumb_height == 0 {
+            return Err(Error::InvalidFormat(format!(
+                "Invalid storyboard of {cols}x{rows} tiles of {thumb_width}x{thumb_height}"
+            )));
+        }
+        let (sheet_width, sheet_height) = (
+            cols.checked_mul(thumb_width),
+            rows.checked_mul(thumb_height),
+        );
+        let (Some(sheet_width), Some(sheet_height)) = (sheet_width, sheet_height) else {
+            return Err(Error::InvalidFormat("Storyboard is too large".to_string()));
+        };
+        if sheet_width > MAX_STORYBOARD_SIZE || sheet_height > MAX_STORYBOARD_SIZE {
+            return Err(Error::InvalidFormat(format!(
+                "Storyboard of {sheet_width}x{sheet_height} exceeds {MAX_STORYBOARD_SIZE} pixels"
+            )));
+        }
+
+        let duration = {
+            let context = self.open()?;
+            (context.duration() as f64 / ffmpeg::ffi::AV_TIME_BASE as f64).max(0.0)
+        };
+        let tiles = cols * rows;
+        let spacing = duration / tiles as f64;
+        let timestamps: Vec<f64> = (0..tiles).map(|i| i as f64 * spacing).collect();
+
+        let images = self.render_frames_at(&timestamps, |scaler, frame, rotation| {
+            frame_to_image(
+                scaler,
+                frame,
+                thumb_width,
+                thumb_height,
+                ThumbnailFit::Cover,
+                [0, 0, 0],
+                rotation,
+            )
+        })?;
+        let mut sheet = image::RgbImage::new(sheet_width, sheet_height);
+        for (i, tile) in images.iter().enumerate() {
+            let (col, row) = (i as u32 % cols, i as u32 / cols);
+            image::imageops::replace(
+                &mut sheet,
+                tile,
+                (col * thumb_width) as i64,
+                (row * thumb_height) as i64,
+            );
+        }
+        png_data_url(&sheet)
+    }
+
+    /// Decode the frames at several timestamps and `render` each, returning
+    /// the results in the order given
+    ///
+    /// The file is opened once, timestamps are visited in order with one
+    /// decoder and scaler, and nearby timestamps are reached by decoding
+    /// forward instead of seeking.  Timestamps that land on a frame already
+    /// rendered reuse its result.  `render` receives the scaler to reuse,
+    /// the frame and the stream rotation (see [`stream_rotation`]).
+    fn render_frames_at<T: Clone>(
+        &self,
+        timestamps: &[f64],
+        mut render: impl FnMut(
+            &mut Option<ffmpeg::software::scaling::Context>,
+            &ffmpeg::frame::Video,
+            i32,
+        ) -> Result<T>,
+    ) -> Result<Vec<T>> {
+        let mut context = self.open()?;
+
+        let video_stream_index = context
+            .streams()
+            .best(Type::Video)
+            .ok_or_else(|| Error::Media("No video stream found".to_string()))?
+            .index();
+        let time_base = f64::from(context.stream(video_stream_index).unwrap().time_base());
+        let mut decoder = open_video_decoder(&context.stream(video_stream_index).unwrap())?;
+        let rotation = stream_rotation(&context.stream(video_stream_index).unwrap());
+        let mut scaler = None;
+
+        // An image has a single frame for every timestamp
+        let is_image = still_image_format(&context).is_some();
+        let mut order: Vec<usize> = (0..timestamps.len()).collect();
+        order.sort_by(|&a, &b| timestamps[a].total_cmp(&timestamps[b]));
+
+        let mut results: Vec<Option<T>> = vec![None; timestamps.len()];
+        // Time of the last decoded frame and its result
+        let mut current: Option<(f64, T)> = None;
+        let mut ended = false;
+
+        for i in order {
+            let timestamp = if is_image { 0.0 } else { timestamps[i] };
+            if let Some((time, result)) = &current {
+                // The frame already decoded is at or past this timestamp, or
+                // the stream has no more frames
+                if ended || timestamp <= *time + 0.0005 {
+                    results[i] = Some(result.clone());
+                    continue;
                 }
             }
+
+            let seek = match &current {
+                Some((time, _)) => timestamp - time > FORWARD_DECODE_LIMIT,
+                None => timestamp > 0.0,
+            };
+            let frame = match decode_frame_at(
+                &mut context,
+                &mut decoder,
+                video_stream_index,
+                timestamp,
+                seek,
+            ) {
+                Ok(frame) => frame,
+                // The previous frame was exactly the last one and the
+                // decoder is already drained
+                Err(_) if !seek && current.is_some() => {
+                    ended = true;
+                    results[i] = Some(current.as_ref().unwrap().1.clone());
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            let time = frame
+                .timestamp()
+                .or_else(|| frame.pts())
+                .map_or(timestamp, |pts| pts as f64 * time_base);
+            // Only the last frame of the stream comes back early
+            ended = time < timestamp - 0.0005;
+
+            let result = render(&mut scaler, &frame, rotation)?;
+            results[i] = Some(result.clone());
+            current = Some((time, result));
         }
+        Ok(results.into_iter().flatten().collect())
+    }
+
+    /// Extract embedded cover art (an attached picture stream) as a data URL
+    ///
+    /// Returns `None` if the file has no cover art.  JPEG and PNG art that
+    /// already fits within `max_size` is passed through unchanged; anything
+    /// else is decoded, downscaled to fit if needed and encoded as PNG.
+    pub fn extract_cover_art(&self, max_size: Option<u32>) -> Result<Option<String>> {
+        let mut context = self.open()?;
 
-        if frame.width() == 0 {
-            return Err(Error::Media("Could not decode frame".to_string()));
+        let Some(stream) = context.streams().find(|s| {
+            s.disposition()
+                .contains(ffmpeg::format::stream::Disposition::ATTACHED_PIC)
+        }) else {
+            return Ok(None);
+        };
+        let stream_index = stream.index();
+        let codec_id = stream.parameters().id();
+        let mut decoder = open_video_decoder(&stream)?;
+
+        // The demuxer delivers the picture as the stream's only packet
+        let Some(packet) = context
+            .packets()
+            .find(|(s, _)| s.index() == stream_index)
+            .map(|(_, packet)| packet)
+        else {
+            return Ok(None);
+        };
+
+        let mime = match codec_id {
+            ffmpeg::codec::Id::MJPEG => Some("image/jpeg"),
+            ffmpeg::codec::Id::PNG => Some("image/png"),
+            _ => None,
+        };
+        let fits = max_size.map_or(true, |max| {
+            decoder.width() <= max && decoder.height() <= max
+        });
+        if let (Some(mime), true) = (mime, fits) {
+            let data = packet.data().unwrap_or_default();
+            return Ok(Some(format!(
+                "data:{};base64,{}",
+                mime,
+                BASE64.encode(data)
+            )));
         }
 
-        // Scale to target size
-        let mut scaler = ffmpeg::software::scaling::context::Context::get(
-            frame.format(),
-            frame.width(),
-            frame.height(),
-            ffmpeg::format::Pixel::RGB24,
+        decoder.send_packet(&packet)?;
+        decoder.send_eof()?;
+        let mut frame = ffmpeg::frame::Video::empty();
+        decoder.receive_frame(&mut frame)?;
+
+        let (width, height) = fit_within(frame.width(), frame.height(), max_size);
+        frame_to_png_data_url(
+            &mut None,
+            &frame,
             width,
             height,
-            ffmpeg::software::scaling::flag::Flags::BILINEAR,
-        )?;
+            ThumbnailFit::Stretch,
+            [0; 3],
+            0,
+        )
+        .map(Some)
+    }
 
-        let mut rgb_frame = ffmpeg::frame::Video::empty();
-        scaler.run(&frame, &mut rgb_frame)?;
+    /// Extract a text subtitle stream (SRT, ASS, mov_text, ...) to an SRT file
+    ///
+    /// Returns the number of cues written.  Bitmap formats such as PGS and DVD
+    /// subtitles are images and cannot be converted.
+    pub fn extract_subtitles(&self, stream_index: usize, output_path: &Path) -> Result<usize> {
+        let mut context = self.open()?;
 
-        // IMPORTANT: ffmpeg frames can be padded (stride != width*3)
-        let stride = rgb_frame.stride(0);
-        let w = width as usize;
-        let h = height as usize;
+        let stream = context
+            .stream(stream_index)
+            .filter(|s| s.parameters().medium() == Type::Subtitle)
+            .ok_or_else(|| {
+                Error::Media(format!("Stream {} is not a subtitle stream", stream_index))
+            })?;
+        let codec_id = stream.parameters().id();
+        if matches!(
+            codec_id,
+            ffmpeg::codec::Id::DVD_SUBTITLE
+                | ffmpeg::codec::Id::DVB_SUBTITLE
+                | ffmpeg::codec::Id::HDMV_PGS_SUBTITLE
+                | ffmpeg::codec::Id::XSUB
+        ) {
+            return Err(bitmap_subtitle_error(codec_id));
+        }
+        let time_base = f64::from(stream.time_base());
+        let mut decoder = ffmpeg::codec::context::Context::from_parameters(stream.parameters())?
+            .decoder()
+            .subtitle()?;
 
-        let src = rgb_frame.data(0);
-        let row_bytes = w * 3;
+        let mut cues = Vec::new();
+        for (stream, packet) in context.packets() {
+            if stream.index() != stream_index {
+                continue;
+            }
+            let mut subtitle = ffmpeg::Subtitle::new();
+            if !decoder.decode(&packet, &mut subtitle)? {
+                continue;
+            }
 
-        // Pack into a tightly packed RGB buffer
-        let mut packed = vec![0u8; row_bytes * h];
-        for y in 0..h {
-            let src_row = &src[y * stride..y * stride + row_bytes];
-            let dst_row = &mut packed[y * row_bytes..(y + 1) * row_bytes];
-            dst_row.copy_from_slice(src_row);
-        }
-        // Encode PNG
-        let mut png_data = Vec::new();
-        {
-            let encoder = image::codecs::png::PngEncoder::new(&mut png_data);
-            encoder.write_image(&packed, width, height, image::ColorType::Rgb8.into())?;
+            let mut lines = Vec::new();
+            for rect in subtitle.rects() {
+                match rect {
+                    ffmpeg::subtitle::Rect::Text(text) => lines.push(text.get().trim().to_string()),
+                    ffmpeg::subtitle::Rect::Ass(ass) => lines.push(ass_to_text(ass.get())),
+                    ffmpeg::subtitle::Rect::Bitmap(_) => {
+                        return Err(bitmap_subtitle_error(codec_id))
+                    }
+                    ffmpeg::subtitle::Rect::None(_) => {}
+                }
+            }
+            lines.retain(|l| !l.is_empty());
+            let Some(pts) = packet.pts() else {
+                continue;
+            };
+            if lines.is_empty() {
+                continue;
+            }
+
+            // Display times are relative to the packet; prefer the packet
+            // duration, which containers set for most text formats
+            let packet_start = pts as f64 * time_base;
+            let start = packet_start + subtitle.start() as f64 / 1000.0;
+            let end = if packet.duration() > 0 {
+                packet_start + packet.duration() as f64 * time_base
+            } else if subtitle.end() > subtitle.start() && subtitle.end() != u32::MAX {
+                packet_start + subtitle.end() as f64 / 1000.0
+            } else {
+                start + DEFAULT_CUE_DURATION
+            };
+            cues.push(SubtitleCue {
+                start,
+                end,
+                text: lines.join("\n"),
+            });
         }
 
-        // Return as base64 data URL
-        let base64_data = BASE64.encode(&png_data);
-        Ok(format!("data:image/png;base64,{}", base64_data))
-        // // Convert to PNG using image crate
-        // let img = image::RgbImage::from_raw(width, height, rgb_frame.data(0).to_vec())
-        //     .ok_or_else(|| Error::Media("Failed to create image buffer".to_string()))?;
+        cues.sort_by(|a, b| a.start.total_cmp(&b.start));
+        std::fs::write(output_path, to_srt(&cues))?;
+        Ok(cues.len())
+    }
 
-        // let mut png_data = Vec::new();
-        // let encoder = image::codecs::png::PngEncoder::new(&mut png_data);
-        // // encoder.write_image(&img, width, height, image::ColorType::Rgb8.into())?;
-        // // encoder.encode(&img, width, height, image::ColorType::Rgb8.into())?;
+    /// Extract audio waveform data
+    ///
+    /// Uses the audio stream at `stream_index` when given, otherwise the best
+    /// audio stream.
+    pub fn extract_waveform(
+        &self,
+        num_samples: usize,
+        stream_index: Option<usize>,
+    ) -> Result<WaveformData> {
+        let (samples, _, duration) = self.decode_mono_audio(stream_index)?;
+        Ok(WaveformData::from_samples(&samples, duration, num_samples))
+    }
 
-        // // Return as base64 data URL
-        // let base64_data = BASE64.encode(&png_data);
-        // Ok(format!("data:image/png;base64,{}", base64_data))
+    /// Render the best audio stream as a spectrogram PNG data URL
+    ///
+    /// Frequencies run up to `max_freq` (Hz, default and limit half the
+    /// sample rate) on a linear axis, or a logarithmic one from 20 Hz with
+    /// `log_frequency`.  Loudness is shown in dB over a 100 dB range.
+    pub fn extract_spectrogram(
+        &self,
+        width: u32,
+        height: u32,
+        max_freq: Option<f64>,
+        log_frequency: bool,
+    ) -> Result<String> {
+        if width == 0 || height == 0 {
+            return Err(Error::InvalidFormat(
+                "Spectrogram size must not be zero".to_string(),
+            ));
+        }
+        let (samples, sample_rate, _) = self.decode_mono_audio(None)?;
+        if samples.is_empty() || sample_rate == 0 {
+            return Err(Error::Media("No audio could be decoded".to_string()));
+        }
+        let max_freq = max_freq.unwrap_or(sample_rate as f64 / 2.0);
+        let pixels = render_spectrogram(
+            &samples,
+            sample_rate,
+            width,
+            height,
+            max_freq,
+            log_frequency,
+        );
+        let image = image::RgbImage::from_raw(width, height, pixels)
+            .ok_or_else(|| Error::Media("Spectrogram buffer has the wrong size".to_string()))?;
+        png_data_url(&image)
     }
 
-    /// Extract audio waveform data
-    pub fn extract_waveform(&self, num_samples: usize) -> Result<WaveformData> {
-        let mut context = input(&self.path)?;
+    /// Detect the tempo of the best audio stream, with beat times (seconds)
+    /// up to `beat_window` seconds into the file
+    ///
+    /// The tempo is searched between 60 and 200 BPM; a steady pulse at
+    /// twice a tempo is reported as the faster one.
+    pub fn analyze_tempo(&self, beat_window: f64) -> Result<TempoAnalysis> {
+        let (samples, sample_rate, _) = self.decode_mono_audio(None)?;
+        if samples.is_empty() || sample_rate == 0 {
+            return Err(Error::Media("No audio could be decoded".to_string()));
+        }
+        analyze_tempo(&samples, sample_rate, beat_window.max(0.0))
+            .ok_or_else(|| Error::Media("Audio is too short to detect a tempo".to_string()))
+    }
+
+    /// Decode an audio stream (the best one if `stream_index` is `None`)
+    /// mixed down to mono, with its sample rate and the file duration
+    fn decode_mono_audio(&self, stream_index: Option<usize>) -> Result<(Vec<f32>, u32, f64)> {
+        let mut context = self.open()?;
 
         // Find audio stream
-        let audio_stream_index = context
-            .streams()
-            .best(Type::Audio)
-            .ok_or_else(|| Error::Media("No audio stream found".to_string()))?
-            .index();
+        let audio_stream_index = match stream_index {
+            Some(index) => {
+                let is_audio = context
+                    .stream(index)
+                    .map(|s| s.parameters().medium() == Type::Audio)
+                    .unwrap_or(false);
+                if !is_audio {
+                    let valid: Vec<String> = context
+                        .streams()
+                        .filter(|s| s.parameters().medium() == Type::Audio)
+                        .map(|s| s.index().to_string())
+                        .collect();
+                    return Err(Error::Media(format!(
+                        "Stream {} is not an audio stream (audio streams: [{}])",
+                        index,
+                        valid.join(", ")
+                    )));
+                }
+                index
+            }
+            None => context
+                .streams()
+                .best(Type::Audio)
+                .ok_or_else(|| Error::Media("No audio stream found".to_string()))?
+                .index(),
+        };
 
-        let stream = context.stream(audio_stream_index).unwrap();
         let duration = context.duration() as f64 / ffmpeg::ffi::AV_TIME_BASE as f64;
 
         // Get decoder
-        let decoder_codec = ffmpeg::decoder::find(stream.parameters().id())
-            .ok_or_else(|| Error::Media("Could not find audio decoder".to_string()))?;
+        let mut decoder = open_audio_decoder(&context.stream(audio_stream_index).unwrap())?;
 
-        let mut decoder = ffmpeg::codec::context::Context::new_with_codec(decoder_codec)
-            .decoder()
-            .audio()?;
-
-        // Collect all audio samples
+        // Collect all audio samples, mixed down to mono
         let mut all_samples: Vec<f32> = Vec::new();
         let mut frame = ffmpeg::frame::Audio::empty();
 
@@ -324,27 +841,1193 @@ impl MediaAnalyzer {
                 decoder.send_packet(&packet)?;
 
                 while decoder.receive_frame(&mut frame).is_ok() {
-                    // Convert to f32 samples (simplified - assumes planar float)
-                    let data = frame.data(0);
-                    let samples: Vec<f32> = data
-                        .chunks(4)
-                        .filter_map(|chunk| {
-                            if chunk.len() == 4 {
-                                Some(f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
-                            } else {
-                                None
-                            }
-                        })
-                        .collect();
-                    all_samples.extend(samples);
+                    append_mono_samples(&frame, &mut all_samples);
                 }
             }
         }
 
-        Ok(WaveformData::from_samples(
-            &all_samples,
-            duration,
-            num_samples,
-        ))
+        // Drain frames still buffered in the decoder
+        decoder.send_eof()?;
+        while decoder.receive_frame(&mut frame).is_ok() {
+            append_mono_samples(&frame, &mut all_samples);
+        }
+
+        Ok((all_samples, decoder.rate(), duration))
+    }
+
+    /// Detect hard scene cuts in the best video stream
+    ///
+    /// Frames are downscaled to grayscale and scored like FFmpeg's
+    /// `select='gt(scene,...)'`, so `threshold` (0.0 to 1.0) means the same
+    /// thing; 0.3 catches most cuts.  `on_progress` is called with the
+    /// fraction of the file processed so far.
+    pub fn detect_scenes(
+        &self,
+        threshold: f64,
+        mut on_progress: impl FnMut(f64),
+    ) -> Result<Vec<SceneCut>> {
+        let mut detector = SceneDetector::new(threshold);
+        let mut scaler = None;
+        self.walk_video_frames(|frame, time, progress| {
+            detector.push(time, &luma_thumbnail(&mut scaler, frame)?);
+            on_progress(progress);
+            Ok(())
+        })?;
+        on_progress(1.0);
+
+        Ok(detector.finish())
+    }
+
+    /// Measure whether the best video stream is interlaced
+    ///
+    /// Decodes a sample of frames from a tenth of the way in (skipping slates
+    /// and fades at the start) and classifies each one by comparing its
+    /// fields with the previous frame's.  Slower than
+    /// [`get_info`](Self::get_info), which only reports what the file
+    /// declares.
+    pub fn detect_interlacing(&self) -> Result<InterlaceReport> {
+        let mut context = self.open()?;
+        let video_stream_index = context
+            .streams()
+            .best(Type::Video)
+            .ok_or_else(|| Error::Media("No video stream found".to_string()))?
+            .index();
+        let mut decoder = open_video_decoder(&context.stream(video_stream_index).unwrap())?;
+
+        let duration = context.duration() as f64 / ffmpeg::ffi::AV_TIME_BASE as f64;
+        if duration > 0.0 {
+            let seek_target = (duration * 0.1 * ffmpeg::ffi::AV_TIME_BASE as f64) as i64;
+            context.seek(seek_target, ..seek_target)?;
+        }
+
+        let mut detector = InterlaceDetector::new();
+        let mut scaler = None;
+        let mut frames = 0;
+        let mut frame = ffmpeg::frame::Video::empty();
+
+        for (stream, packet) in context.packets() {
+            if stream.index() != video_stream_index {
+                continue;
+            }
+            decoder.send_packet(&packet)?;
+            while decoder.receive_frame(&mut frame).is_ok() {
+                // Keep every row: scaling vertically would blend the fields
+                let width = frame.width().min(INTERLACE_ANALYSIS_WIDTH);
+                let luma = luma_plane(&mut scaler, &frame, (width, frame.height()))?;
+                detector.push(&luma, width as usize);
+                frames += 1;
+            }
+            if frames >= INTERLACE_SAMPLE_FRAMES {
+                break;
+            }
+        }
+
+        Ok(detector.finish())
+    }
+
+    /// Measure the frame spacing of the best video stream and count its frames
+    /// exactly
+    ///
+    /// Reads every packet's timestamp without decoding, so it is much faster
+    /// than a decode but still reads the whole file.  Streams whose packets
+    /// carry no timestamps (e.g. raw elementary streams) are decoded instead.
+    pub fn analyze_frame_timing(&self) -> Result<FrameTiming> {
+        let mut context = self.open()?;
+        let (video_stream_index, time_base) = {
+            let stream = context
+                .streams()
+                .best(Type::Video)
+                .ok_or_else(|| Error::Media("No video stream found".to_string()))?;
+            (stream.index(), f64::from(stream.time_base()))
+        };
+
+        let mut collector = FrameTimingCollector::new(time_base);
+        let mut timestamped = true;
+        for (stream, packet) in context.packets() {
+            if stream.index() != video_stream_index {
+                continue;
+            }
+            match packet.pts() {
+                Some(pts) => collector.push(pts as f64 * time_base),
+                None => {
+                    timestamped = false;
+                    break;
+                }
+            }
+        }
+        if timestamped {
+            return Ok(collector.finish());
+        }
+
+        let mut collector = FrameTimingCollector::new(time_base);
+        self.walk_video_frames(|_, time, _| {
+            collector.push(time);
+            Ok(())
+        })?;
+        Ok(collector.finish())
+    }
+
+    /// Find black and frozen segments in the best video stream
+    ///
+    /// Fades to black are reported as black segments once they fall below the
+    /// luma threshold.  Files shorter than the minimum run lengths simply
+    /// produce an empty report.
+    pub fn analyze_video_quality(
+        &self,
+        thresholds: QualityThresholds,
+    ) -> Result<VideoQualityReport> {
+        let mut detector = QualityDetector::new(thresholds);
+        let mut scaler = None;
+        self.walk_video_frames(|frame, time, _| {
+            detector.push(time, &luma_thumbnail(&mut scaler, frame)?);
+            Ok(())
+        })?;
+
+        Ok(detector.finish())
+    }
+
+    /// Check the file for damage by reading every packet, and with
+    /// `full_decode` also decoding every audio and video packet
+    ///
+    /// Reading alone catches truncated files, unreadable data and packets
+    /// the demuxer flags as corrupt, and runs at disk speed; decoding also
+    /// catches damage inside the packets but is far slower.  A file that
+    /// cannot be opened (e.g. an MP4 missing its `moov` index) is reported as
+    /// corrupt rather than as an error.  `on_progress` is called with the
+    /// fraction of the file checked so far.
+    pub fn verify_integrity(
+        &self,
+        full_decode: bool,
+        mut on_progress: impl FnMut(f64),
+    ) -> Result<IntegrityReport> {
+        let mut context = match self.open() {
+            Ok(context) => context,
+            Err(e) => {
+                return Ok(IntegrityChecker::open_failed(
+                    full_decode,
+                    format!("Cannot open file: {}", e),
+                ))
+            }
+        };
+        let duration = (context.duration() as f64 / ffmpeg::ffi::AV_TIME_BASE as f64).max(0.0);
+
+        // Time base and, for a full decode, decoder of each stream
+        let mut streams: HashMap<usize, (f64, Option<ffmpeg::decoder::Opened>)> = HashMap::new();
+        for stream in context.streams() {
+            let decodable = matches!(stream.parameters().medium(), Type::Video | Type::Audio);
+            // Streams without a decoder in this build are only read
+            let decoder = (full_decode && decodable)
+                .then(|| {
+                    ffmpeg::codec::context::Context::from_parameters(stream.parameters())
+                        .and_then(|ctx| ctx.decoder().open())
+                        .ok()
+                })
+                .flatten();
+            streams.insert(stream.index(), (f64::from(stream.time_base()), decoder));
+        }
+
+        let mut checker = IntegrityChecker::new(full_decode);
+        let mut packet = ffmpeg::Packet::empty();
+        // SAFETY: allocates an empty frame for the decoders to fill
+        let mut frame = unsafe { ffmpeg::Frame::empty() };
+        let mut last_time = 0.0;
+        let mut failed_reads = 0;
+        loop {
+            match packet.read(&mut context) {
+                Ok(()) => failed_reads = 0,
+                Err(ffmpeg::Error::Eof) => break,
+                Err(e) => {
+                    checker.issue(
+                        IntegrityIssue::Read,
+                        Some(last_time),
+                        format!("Read error after {:.2}s: {}", last_time, e),
+                    );
+                    // The demuxer may never get past badly damaged data
+                    failed_reads += 1;
+                    if failed_reads >= MAX_CONSECUTIVE_READ_ERRORS {
+                        break;
+                    }
+                    continue;
+                }
+            }
+
+            let index = packet.stream();
+            let Some((time_base, decoder)) = streams.get_mut(&index) else {
+                continue;
+            };
+            let time = packet
+                .pts()
+                .or(packet.dts())
+                .map(|ts| ts as f64 * *time_base);
+            if let Some(time) = time {
+                last_time = time;
+            }
+            checker.packet(time.map(|t| t + packet.duration().max(0) as f64 * *time_base));
+            if packet.is_corrupt() {
+                checker.issue(
+                    IntegrityIssue::CorruptPacket,
+                    time,
+                    format!("Corrupt packet in stream {} at {:.2}s", index, last_time),
+                );
+            }
+            if let Some(decoder) = decoder {
+                if let Err(e) = decoder.send_packet(&packet) {
+                    checker.issue(
+                        IntegrityIssue::Decode,
+                        time,
+                        format!("Stream {} at {:.2}s: {}", index, last_time, e),
+                    );
+                }
+                drain_checked(decoder, &mut frame, index, *time_base, &mut checker);
+            }
+            if duration > 0.0 {
+                on_progress((last_time / duration).clamp(0.0, 1.0));
+            }
+        }
+
+        // Frames still buffered in the decoders
+        for (&index, (time_base, decoder)) in streams.iter_mut() {
+            if let Some(decoder) = decoder {
+                let _ = decoder.send_eof();
+                drain_checked(decoder, &mut frame, index, *time_base, &mut checker);
+            }
+        }
+        on_progress(1.0);
+
+        Ok(checker.finish(duration))
+    }
+
+    /// Decode every frame of the best video stream in order
+    ///
+    /// `on_frame` receives each frame with its presentation time in seconds
+    /// and the fraction of the file decoded so far.
+    fn walk_video_frames(
+        &self,
+        mut on_frame: impl FnMut(&ffmpeg::frame::Video, f64, f64) -> Result<()>,
+    ) -> Result<()> {
+        let mut context = self.open()?;
+
+        // Find video stream
+        let video_stream_index = context
+            .streams()
+            .best(Type::Video)
+            .ok_or_else(|| Error::Media("No video stream found".to_string()))?
+            .index();
+
+        let stream = context.stream(video_stream_index).unwrap();
+        let time_base = f64::from(stream.time_base());
+        let mut decoder = open_video_decoder(&stream)?;
+        let duration = context.duration() as f64 / ffmpeg::ffi::AV_TIME_BASE as f64;
+
+        // Frames without a timestamp reuse the previous one
+        let mut last_time = 0.0;
+        let mut handle_frame = |frame: &ffmpeg::frame::Video| -> Result<()> {
+            let time = frame
+                .timestamp()
+                .or_else(|| frame.pts())
+                .map(|pts| pts as f64 * time_base)
+                .unwrap_or(last_time);
+            last_time = time;
+            let progress = if duration > 0.0 {
+                (time / duration).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            on_frame(frame, time, progress)
+        };
+
+        let mut frame = ffmpeg::frame::Video::empty();
+        for (stream, packet) in context.packets() {
+            if stream.index() != video_stream_index {
+                continue;
+            }
+            decoder.send_packet(&packet)?;
+            while decoder.receive_frame(&mut frame).is_ok() {
+                handle_frame(&frame)?;
+            }
+        }
+
+        // Drain frames still buffered in the decoder
+        decoder.send_eof()?;
+        while decoder.receive_frame(&mut frame).is_ok() {
+            handle_frame(&frame)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Whether `path` is a URL for one of the [`REMOTE_SCHEMES`]
+fn is_remote_url(path: &Path) -> bool {
+    let path = path.to_string_lossy().to_ascii_lowercase();
+    REMOTE_SCHEMES.iter().any(|scheme| path.starts_with(scheme))
+}
+
+/// Size of a remote input as reported by the protocol (e.g. an HTTP
+/// `Content-Length`), or 0 if unknown.  Segmented streams such as HLS only
+/// know the size of their playlist, so they report 0.
+fn remote_size(context: &ffmpeg::format::context::Input) -> u64 {
+    if matches!(context.format().name(), "hls" | "dash") {
+        return 0;
+    }
+    // SAFETY: `pb` is the I/O context owned by the open input (null for
+    // demuxers that do their own I/O); `avio_size` only queries it
+    unsafe {
+        let pb = (*context.as_ptr()).pb;
+        if pb.is_null() {
+            0
+        } else {
+            ffmpeg::ffi::avio_size(pb).max(0) as u64
+        }
+    }
+}
+
+/// The image format (e.g. "png", "jpeg", "webp") if `context` is a still
+/// image rather than a video.  FFmpeg opens images with the `image2` or a
+/// `<codec>_pipe` demuxer.
+fn still_image_format(context: &ffmpeg::format::context::Input) -> Option<String> {
+    let demuxer = context.format();
+    if demuxer.name() != "image2" && !demuxer.name().ends_with("_pipe") {
+        return None;
+    }
+    let stream = context.streams().best(Type::Video)?;
+    Some(match stream.parameters().id() {
+        ffmpeg::codec::Id::MJPEG => "jpeg".to_string(),
+        id => id.name().to_string(),
+    })
+}
+
+/// Clockwise rotation (0, 90, 180 or 270 degrees) needed to display a video
+/// stream upright, from its display matrix or legacy `rotate` tag
+fn stream_rotation(stream: &ffmpeg::Stream) -> i32 {
+    let matrix = side_data_ints(stream, ffmpeg::packet::side_data::Type::DisplayMatrix)
+        .filter(|v| v.len() == 9)
+        .map(|v| v.into_iter().map(f64::from).collect::<Vec<_>>());
+
+    let degrees = match matrix {
+        // As av_display_rotation_get, which returns the counter-clockwise
+        // angle; the 16.16 fixed point scale cancels out
+        Some(m) => {
+            let scale_x = m[0].hypot(m[3]);
+            let scale_y = m[1].hypot(m[4]);
+            if scale_x == 0.0 || scale_y == 0.0 {
+                return 0;
+            }
+            (m[1] / scale_y).atan2(m[0] / scale_x).to_degrees()
+        }
+        None => match stream
+            .metadata()
+            .get("rotate")
+            .and_then(|r| r.parse::<f64>().ok())
+        {
+            Some(degrees) => degrees,
+            None => return 0,
+        },
+    };
+    // Snap to a quarter turn
+    ((degrees / 90.0).round() as i32 * 90).rem_euclid(360)
+}
+
+/// Name of an audio stream's channel layout as FFmpeg prints it (e.g.
+/// "stereo", "5.1(side)"), or "N channels" if the layout is unspecified
+fn channel_layout_name(stream: &ffmpeg::Stream, channels: u32) -> String {
+    let params = stream.parameters();
+    let layout = unsafe { &(*params.as_ptr()).ch_layout };
+    if layout.order == ffmpeg::ffi::AVChannelOrder::AV_CHANNEL_ORDER_UNSPEC {
+        return format!("{} channels", channels);
+    }
+    let mut name = [0 as std::os::raw::c_char; 64];
+    let written =
+        unsafe { ffmpeg::ffi::av_channel_layout_describe(layout, name.as_mut_ptr(), name.len()) };
+    if written <= 0 {
+        return format!("{} channels", channels);
+    }
+    // Truncated but still terminated if the name doesn't fit
+    unsafe { std::ffi::CStr::from_ptr(name.as_ptr()) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Open a decoder for a video stream using its codec parameters
+fn open_video_decoder(stream: &ffmpeg::Stream) -> Result<ffmpeg::decoder::Video> {
+    let ctx = ffmpeg::codec::context::Context::from_parameters(stream.parameters())?;
+    Ok(ctx.decoder().video()?)
+}
+
+/// How far ahead (seconds) [`MediaAnalyzer::extract_thumbnails`] decodes
+/// forward to its next timestamp rather than seeking
+const FORWARD_DECODE_LIMIT: f64 = 5.0;
+
+/// Largest width or height of a storyboard sprite sheet, in pixels
+const MAX_STORYBOARD_SIZE: u32 = 16384;
+
+/// Seek to `timestamp` (seconds) and decode forward to the first frame at or
+/// after it.
+///
+/// Seeking lands on the keyframe before the target, so frames earlier than the
+/// requested time are decoded and discarded.  If the stream ends first, the
+/// last decoded frame is returned instead.
+fn decode_frame_at(
+    context: &mut ffmpeg::format::context::Input,
+    decoder: &mut ffmpeg::decoder::Video,
+    stream_index: usize,
+    timestamp: f64,
+    seek: bool,
+) -> Result<ffmpeg::frame::Video> {
+    let time_base = f64::from(context.stream(stream_index).unwrap().time_base());
+
+    // Seek to the nearest keyframe at or before the target.  Without `seek`
+    // decoding continues from the current position, which must be before the
+    // target: a freshly opened input, one just past an earlier target, or an
+    // image (image demuxers cannot seek).
+    if seek {
+        let seek_target = (timestamp * ffmpeg::ffi::AV_TIME_BASE as f64) as i64;
+        context.seek(seek_target, ..seek_target)?;
+        decoder.flush();
+    }
+
+    // Half a millisecond of slack absorbs rounding in the time base conversion
+    let reached = |frame: &ffmpeg::frame::Video| {
+        frame
+            .timestamp()
+            .or_else(|| frame.pts())
+            .map(|pts| pts as f64 * time_base >= timestamp - 0.0005)
+            .unwrap_or(false)
+    };
+
+    let mut frame = ffmpeg::frame::Video::empty();
+    let mut last: Option<ffmpeg::frame::Video> = None;
+
+    for (stream, packet) in context.packets() {
+        if stream.index() != stream_index {
+            continue;
+        }
+        decoder.send_packet(&packet)?;
+        while decoder.receive_frame(&mut frame).is_ok() {
+            if reached(&frame) {
+                return Ok(frame);
+            }
+            last = Some(std::mem::replace(&mut frame, ffmpeg::frame::Video::empty()));
+        }
+    }
+
+    // Drain frames still buffered in the decoder
+    decoder.send_eof()?;
+    while decoder.receive_frame(&mut frame).is_ok() {
+        if reached(&frame) {
+            return Ok(frame);
+        }
+        last = Some(std::mem::replace(&mut frame, ffmpeg::frame::Video::empty()));
+    }
+
+    last.ok_or_else(|| Error::Media("Could not decode frame".to_string()))
+}
+
+/// Scale `width`x`height` down to fit a `max_size` square, keeping the aspect
+/// ratio.  Never upscales.
+fn fit_within(width: u32, height: u32, max_size: Option<u32>) -> (u32, u32) {
+    match max_size {
+        Some(max) if width > max || height > max => {
+            let scale = max as f64 / width.max(height) as f64;
+            (
+                ((width as f64 * scale).round() as u32).max(1),
+                ((height as f64 * scale).round() as u32).max(1),
+            )
+        }
+        _ => (width, height),
+    }
+}
+
+/// Scale a decoded frame into a `width`x`height` box with [`frame_to_image`]
+/// and encode it as a PNG data URL
+fn frame_to_png_data_url(
+    scaler: &mut Option<ffmpeg::software::scaling::Context>,
+    frame: &ffmpeg::frame::Video,
+    width: u32,
+    height: u32,
+    fit: ThumbnailFit,
+    background: [u8; 3],
+    rotation: i32,
+) -> Result<String> {
+    png_data_url(&frame_to_image(
+        scaler, frame, width, height, fit, background, rotation,
+    )?)
+}
+
+/// Scale a decoded frame into a `width`x`height` box according to `fit`.
+/// `background` fills the bars left by [`ThumbnailFit::Contain`].  `rotation`
+/// (clockwise degrees, see [`stream_rotation`]) turns the picture upright.
+/// `scaler` is reused while frames keep the same format and size.
+fn frame_to_image(
+    scaler: &mut Option<ffmpeg::software::scaling::Context>,
+    frame: &ffmpeg::frame::Video,
+    width: u32,
+    height: u32,
+    fit: ThumbnailFit,
+    background: [u8; 3],
+    rotation: i32,
+) -> Result<image::RgbImage> {
+    // Lay the picture out in the frame's stored orientation and rotate it
+    // upright at the end
+    let (width, height) = if rotation % 180 == 0 {
+        (width, height)
+    } else {
+        (height, width)
+    };
+
+    // Size of the scaled picture, which may be smaller (contain) or larger
+    // (cover) than the box in one dimension
+    let (scaled_width, scaled_height) = match fit {
+        ThumbnailFit::Stretch => (width, height),
+        ThumbnailFit::Contain | ThumbnailFit::Cover => {
+            let scale_x = width as f64 / frame.width() as f64;
+            let scale_y = height as f64 / frame.height() as f64;
+            let scale = if fit == ThumbnailFit::Contain {
+                scale_x.min(scale_y)
+            } else {
+                scale_x.max(scale_y)
+            };
+            (
+                ((frame.width() as f64 * scale).round() as u32).max(1),
+                ((frame.height() as f64 * scale).round() as u32).max(1),
+            )
+        }
+    };
+
+    // Scale to target size
+    let mut rgb_frame = ffmpeg::frame::Video::empty();
+    cached_scaler(
+        scaler,
+        frame,
+        ffmpeg::format::Pixel::RGB24,
+        (scaled_width, scaled_height),
+        ffmpeg::software::scaling::flag::Flags::BILINEAR,
+    )?
+    .run(frame, &mut rgb_frame)?;
+
+    // IMPORTANT: ffmpeg frames can be padded (stride != width*3)
+    let stride = rgb_frame.stride(0);
+    let (w, h) = (width as usize, height as usize);
+    let (sw, sh) = (scaled_width as usize, scaled_height as usize);
+    let src = rgb_frame.data(0);
+    let row_bytes = w * 3;
+
+    // Center the scaled picture: positive offsets place it inside the box
+    // (contain), negative ones crop it (cover)
+    let offset_x = (w as isize - sw as isize) / 2;
+    let offset_y = (h as isize - sh as isize) / 2;
+    let (dst_x, src_x) = (offset_x.max(0) as usize, (-offset_x).max(0) as usize);
+    let copy_bytes = sw.min(w) * 3;
+
+    // Pack into a tightly packed RGB buffer over the background color
+    let mut packed = background.repeat(w * h);
+    for y in 0..h {
+        let src_y = y as isize - offset_y;
+        if src_y < 0 || src_y >= sh as isize {
+            continue;
+        }
+        let src_start = src_y as usize * stride + src_x * 3;
+        let dst_start = y * row_bytes + dst_x * 3;
+        packed[dst_start..dst_start + copy_bytes]
+            .copy_from_slice(&src[src_start..src_start + copy_bytes]);
+    }
+    let image = image::RgbImage::from_raw(width, height, packed)
+        .ok_or_else(|| Error::Media("Thumbnail buffer has the wrong size".to_string()))?;
+    Ok(match rotation {
+        90 => image::imageops::rotate90(&image),
+        180 => image::imageops::rotate180(&image),
+        270 => image::imageops::rotate270(&image),
+        _ => image,
+    })
+}
+
+/// Encode an image as a base64 PNG data URL
+fn png_data_url(image: &image::RgbImage) -> Result<String> {
+    let mut png_data = Vec::new();
+    {
+        let encoder = image::codecs::png::PngEncoder::new(&mut png_data);
+        encoder.write_image(
+            image.as_raw(),
+            image.width(),
+            image.height(),
+            image::ColorType::Rgb8.into(),
+        )?;
+    }
+
+    // Return as base64 data URL
+    let base64_data = BASE64.encode(&png_data);
+    Ok(format!("data:image/png;base64,{}", base64_data))
+}
+
+/// How long a subtitle cue without an end time stays on screen, in seconds
+const DEFAULT_CUE_DURATION: f64 = 3.0;
+
+fn bitmap_subtitle_error(codec_id: ffmpeg::codec::Id) -> Error {
+    Error::InvalidFormat(format!(
+        "{} subtitles are bitmap images and cannot be converted to SRT",
+        codec_id.name()
+    ))
+}
+
+/// Size frames are reduced to before scene scoring
+const SCENE_ANALYSIS_SIZE: (u32, u32) = (160, 90);
+
+/// Failed reads in a row after which [`MediaAnalyzer::verify_integrity`]
+/// gives up on the rest of the file
+const MAX_CONSECUTIVE_READ_ERRORS: u32 = 100;
+
+/// Receive every frame `decoder` has ready, recording failures and frames
+/// the decoder flags as damaged
+fn drain_checked(
+    decoder: &mut ffmpeg::decoder::Opened,
+    frame: &mut ffmpeg::Frame,
+    index: usize,
+    time_base: f64,
+    checker: &mut IntegrityChecker,
+) {
+    loop {
+        match decoder.receive_frame(frame) {
+            Ok(()) => {
+                // SAFETY: the frame was just filled by the decoder
+                let error_flags = unsafe { (*frame.as_ptr()).decode_error_flags };
+                if frame.is_corrupt() || error_flags != 0 {
+                    let time = frame.pts().map(|pts| pts as f64 * time_base);
+                    checker.issue(
+                        IntegrityIssue::Decode,
+                        time,
+                        format!(
+                            "Damaged frame in stream {} at {:.2}s",
+                            index,
+                            time.unwrap_or(0.0)
+                        ),
+                    );
+                }
+            }
+            Err(ffmpeg::Error::Eof)
+            | Err(ffmpeg::Error::Other {
+                errno: ffmpeg::error::EAGAIN,
+            }) => return,
+            Err(e) => {
+                checker.issue(
+                    IntegrityIssue::Decode,
+                    None,
+                    format!("Stream {}: {}", index, e),
+                );
+                return;
+            }
+        }
+    }
+}
+
+/// Frames [`MediaAnalyzer::detect_interlacing`] classifies
+const INTERLACE_SAMPLE_FRAMES: u32 = 100;
+
+/// Maximum width frames are reduced to before interlace detection
+const INTERLACE_ANALYSIS_WIDTH: u32 = 720;
+
+/// Declared field order of a video stream, from its codec parameters
+fn declared_field_order(stream: &ffmpeg::Stream) -> Option<FieldOrder> {
+    // SAFETY: the parameters belong to the stream, which outlives this read
+    let order = ffmpeg::FieldOrder::from(unsafe { (*stream.parameters().as_ptr()).field_order });
+    // The second letter is the field displayed first
+    match order {
+        ffmpeg::FieldOrder::Progressive => Some(FieldOrder::Progressive),
+        ffmpeg::FieldOrder::TT | ffmpeg::FieldOrder::BT => Some(FieldOrder::Tff),
+        ffmpeg::FieldOrder::BB | ffmpeg::FieldOrder::TB => Some(FieldOrder::Bff),
+        ffmpeg::FieldOrder::Unknown => None,
+    }
+}
+
+/// Scale a frame down to [`SCENE_ANALYSIS_SIZE`] grayscale and return its
+/// tightly packed luma plane
+///
+/// The scaler is created on first use and rebuilt if the input format or size
+/// changes mid-stream.
+fn luma_thumbnail(
+    scaler: &mut Option<ffmpeg::software::scaling::Context>,
+    frame: &ffmpeg::frame::Video,
+) -> Result<Vec<u8>> {
+    luma_plane(scaler, frame, SCENE_ANALYSIS_SIZE)
+}
+
+/// Scale a frame to `size` grayscale and return its tightly packed luma plane
+fn luma_plane(
+    scaler: &mut Option<ffmpeg::software::scaling::Context>,
+    frame: &ffmpeg::frame::Video,
+    size: (u32, u32),
+) -> Result<Vec<u8>> {
+    let (width, height) = size;
+    let mut gray = ffmpeg::frame::Video::empty();
+    cached_scaler(
+        scaler,
+        frame,
+        ffmpeg::format::Pixel::GRAY8,
+        size,
+        ffmpeg::software::scaling::flag::Flags::AREA,
+    )?
+    .run(frame, &mut gray)?;
+
+    // Rows can be padded (stride != width)
+    let stride = gray.stride(0);
+    let w = width as usize;
+    let src = gray.data(0);
+    let mut packed = Vec::with_capacity(w * height as usize);
+    for y in 0..height as usize {
+        packed.extend_from_slice(&src[y * stride..y * stride + w]);
+    }
+    Ok(packed)
+}
+
+/// The scaler in `scaler`, recreated if it doesn't convert frames like
+/// `frame` to `format` at `size`
+fn cached_scaler<'a>(
+    scaler: &'a mut Option<ffmpeg::software::scaling::Context>,
+    frame: &ffmpeg::frame::Video,
+    format: ffmpeg::format::Pixel,
+    size: (u32, u32),
+    flags: ffmpeg::software::scaling::flag::Flags,
+) -> Result<&'a mut ffmpeg::software::scaling::Context> {
+    let stale = scaler.as_ref().map_or(true, |s| {
+        let (input, output) = (s.input(), s.output());
+        input.format != frame.format()
+            || input.width != frame.width()
+            || input.height != frame.height()
+            || output.format != format
+            || (output.width, output.height) != size
+    });
+    if stale {
+        *scaler = Some(ffmpeg::software::scaling::context::Context::get(
+            frame.format(),
+            frame.width(),
+            frame.height(),
+            format,
+            size.0,
+            size.1,
+            flags,
+        )?);
+    }
+    Ok(scaler.as_mut().unwrap())
+}
+
+/// Open a decoder for an audio stream using its codec parameters
+fn open_audio_decoder(stream: &ffmpeg::Stream) -> Result<ffmpeg::decoder::Audio> {
+    let ctx = ffmpeg::codec::context::Context::from_parameters(stream.parameters())?;
+    Ok(ctx.decoder().audio()?)
+}
+
+/// PCM sample types produced by ffmpeg decoders
+trait PcmSample: ffmpeg::frame::audio::Sample + Copy {
+    const SIZE: usize;
+
+    /// Read one sample from native-endian bytes
+    fn from_ne(bytes: &[u8]) -> Self;
+
+    /// Normalize to -1.0..=1.0
+    fn to_f32(self) -> f32;
+}
+
+impl PcmSample for u8 {
+    const SIZE: usize = 1;
+
+    fn from_ne(bytes: &[u8]) -> Self {
+        bytes[0]
+    }
+
+    fn to_f32(self) -> f32 {
+        (self as f32 - 128.0) / 128.0
+    }
+}
+
+impl PcmSample for i16 {
+    const SIZE: usize = 2;
+
+    fn from_ne(bytes: &[u8]) -> Self {
+        i16::from_ne_bytes([bytes[0], bytes[1]])
+    }
+
+    fn to_f32(self) -> f32 {
+        self as f32 / 32_768.0
+    }
+}
+
+impl PcmSample for i32 {
+    const SIZE: usize = 4;
+
+    fn from_ne(bytes: &[u8]) -> Self {
+        i32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+    }
+
+    fn to_f32(self) -> f32 {
+        (self as f64 / 2_147_483_648.0) as f32
+    }
+}
+
+impl PcmSample for f32 {
+    const SIZE: usize = 4;
+
+    fn from_ne(bytes: &[u8]) -> Self {
+        f32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+    }
+
+    fn to_f32(self) -> f32 {
+        self
+    }
+}
+
+impl PcmSample for f64 {
+    const SIZE: usize = 8;
+
+    fn from_ne(bytes: &[u8]) -> Self {
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(&bytes[..8]);
+        f64::from_ne_bytes(buf)
+    }
+
+    fn to_f32(self) -> f32 {
+        self as f32
+    }
+}
+
+/// Append a decoded audio frame to `out` as mono f32, averaging all channels
+///
+/// Handles packed and planar layouts of every sample format decoders emit
+/// (u8, s16, s32, flt, dbl).
+fn append_mono_samples(frame: &ffmpeg::frame::Audio, out: &mut Vec<f32>) {
+    use ffmpeg::format::Sample;
+
+    match frame.format() {
+        Sample::U8(_) => mix_to_mono::<u8>(frame, out),
+        Sample::I16(_) => mix_to_mono::<i16>(frame, out),
+        Sample::I32(_) => mix_to_mono::<i32>(frame, out),
+        Sample::F32(_) => mix_to_mono::<f32>(frame, out),
+        Sample::F64(_) => mix_to_mono::<f64>(frame, out),
+        // s64 is not produced by any audio decoder
+        Sample::I64(_) | Sample::None => {}
+    }
+}
+
+fn mix_to_mono<T: PcmSample>(frame: &ffmpeg::frame::Audio, out: &mut Vec<f32>) {
+    let samples = frame.samples();
+    let channels = usize::from(frame.channels()).max(1);
+
+    if frame.is_planar() {
+        // One plane per channel
+        let planes: Vec<&[T]> = (0..frame.planes()).map(|c| frame.plane::<T>(c)).collect();
+        if planes.is_empty() {
+            return;
+        }
+        out.extend((0..samples).map(|i| {
+            planes.iter().map(|plane| plane[i].to_f32()).sum::<f32>() / planes.len() as f32
+        }));
+    } else {
+        // Interleaved channels in the first plane
+        let data = frame.data(0);
+        out.extend(
+            data.chunks_exact(T::SIZE * channels)
+                .take(samples)
+                .map(|group| {
+                    group
+                        .chunks_exact(T::SIZE)
+                        .map(|bytes| T::from_ne(bytes).to_f32())
+                        .sum::<f32>()
+                        / channels as f32
+                }),
+        );
+    }
+}
+
+/// Side data of `kind` as native-endian 32-bit integers
+fn side_data_ints(
+    stream: &ffmpeg::Stream,
+    kind: ffmpeg::packet::side_data::Type,
+) -> Option<Vec<i32>> {
+    stream.side_data().find(|d| d.kind() == kind).map(|d| {
+        d.data()
+            .chunks_exact(4)
+            .map(|b| i32::from_ne_bytes([b[0], b[1], b[2], b[3]]))
+            .collect()
+    })
+}
+
+/// Mastering display metadata from the stream's side data
+///
+/// The payload is an `AVMasteringDisplayMetadata`: ten rationals (the red,
+/// green and blue primaries and the white point as x/y pairs, then the
+/// minimum and maximum luminance) followed by the `has_primaries` and
+/// `has_luminance` flags.
+fn mastering_display(stream: &ffmpeg::Stream) -> Option<super::info::MasteringDisplay> {
+    let v = side_data_ints(
+        stream,
+        ffmpeg::packet::side_data::Type::MasteringDisplayMetadata,
+    )?;
+    if v.len() < 22 {
+        return None;
+    }
+    let rational = |i: usize| {
+        let den = v[2 * i + 1];
+        (den != 0).then(|| v[2 * i] as f64 / den as f64)
+    };
+    let point = |i: usize| Some([rational(i)?, rational(i + 1)?]);
+    let (has_primaries, has_luminance) = (v[20] != 0, v[21] != 0);
+    if !has_primaries && !has_luminance {
+        return None;
+    }
+
+    let primaries = if has_primaries {
+        match (point(0), point(2), point(4)) {
+            (Some(r), Some(g), Some(b)) => Some([r, g, b]),
+            _ => None,
+        }
+    } else {
+        None
+    };
+    Some(super::info::MasteringDisplay {
+        primaries,
+        white_point: point(6).filter(|_| has_primaries),
+        min_luminance: rational(8).filter(|_| has_luminance),
+        max_luminance: rational(9).filter(|_| has_luminance),
+    })
+}
+
+/// Content light level metadata (`AVContentLightMetadata`: MaxCLL and
+/// MaxFALL) from the stream's side data
+fn content_light_level(stream: &ffmpeg::Stream) -> Option<super::info::ContentLightLevel> {
+    let v = side_data_ints(stream, ffmpeg::packet::side_data::Type::ContentLightLevel)?;
+    match v[..] {
+        [max_cll, max_fall, ..] => Some(super::info::ContentLightLevel {
+            max_cll: max_cll as u32,
+            max_fall: max_fall as u32,
+        }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_media::ffmpeg;
+    use super::*;
+    use std::path::PathBuf;
+    use std::time::Instant;
+
+    /// Encode a 10 s, 25 fps test pattern into `dir` with a single keyframe,
+    /// so every seek decodes from the start
+    fn test_clip(dir: &Path) -> PathBuf {
+        let path = dir.join("clip.mp4");
+        ffmpeg(
+            &[
+                "-f",
+                "lavfi",
+                "-i",
+                "testsrc=duration=10:size=320x240:rate=25",
+                "-c:v",
+                "mpeg4",
+                "-g",
+                "250",
+            ],
+            &path,
+        );
+        path
+    }
+
+    #[test]
+    #[ignore = "needs the ffmpeg CLI"]
+    fn batch_thumbnails_match_single_calls() {
+        let dir = tempfile::tempdir().unwrap();
+        let analyzer = MediaAnalyzer::new(&test_clip(dir.path())).unwrap();
+        let timestamps: Vec<f64> = (0..20).map(|i| i as f64 * 0.5).collect();
+        let fit = ThumbnailFit::Stretch;
+
+        let batch = analyzer
+            .extract_thumbnails(&timestamps, 160, 90, fit, [0, 0, 0])
+            .unwrap();
+        let single: Vec<String> = timestamps
+            .iter()
+            .map(|&t| {
+                analyzer
+                    .extract_thumbnail(t, 160, 90, fit, [0, 0, 0])
+                    .unwrap()
+            })
+            .collect();
+
+        assert_eq!(batch, single);
+        assert_eq!(
+            analyzer
+                .extract_thumbnails_at(&timestamps, 160, 90)
+                .unwrap(),
+            batch
+        );
+        // Past the end gives the last frame rather than an error
+        assert_eq!(
+            analyzer.extract_thumbnails_at(&[30.0], 160, 90).unwrap(),
+            vec![analyzer
+                .extract_thumbnail(30.0, 160, 90, fit, [0, 0, 0])
+                .unwrap()]
+        );
+    }
+
+    #[test]
+    #[ignore = "benchmark; needs the ffmpeg CLI"]
+    fn batch_thumbnails_beat_single_calls() {
+        let dir = tempfile::tempdir().unwrap();
+        let analyzer = MediaAnalyzer::new(&test_clip(dir.path())).unwrap();
+        let timestamps: Vec<f64> = (0..20).map(|i| i as f64 * 0.5).collect();
+        let fit = ThumbnailFit::Stretch;
+
+        let start = Instant::now();
+        analyzer
+            .extract_thumbnails(&timestamps, 160, 90, fit, [0, 0, 0])
+            .unwrap();
+        let batch_time = start.elapsed();
+
+        let start = Instant::now();
+        for &t in &timestamps {
+            analyzer
+                .extract_thumbnail(t, 160, 90, fit, [0, 0, 0])
+                .unwrap();
+        }
+        let single_time = start.elapsed();
+
+        assert!(
+            batch_time < single_time,
+            "batch took {batch_time:?}, {} single calls {single_time:?}",
+            timestamps.len()
+        );
+    }
+
+    #[test]
+    #[ignore = "needs the ffmpeg CLI"]
+    fn reports_pixel_format_and_color_space() {
+        let dir = tempfile::tempdir().unwrap();
+        let video_info = |name: &str, pix_fmt: &str, colorspace: &str, trc: &str| {
+            let path = dir.path().join(name);
+            ffmpeg(
+                &[
+                    "-f",
+                    "lavfi",
+                    "-i",
+                    "testsrc=duration=1:size=320x240:rate=25",
+                    "-c:v",
+                    "ffv1",
+                    "-pix_fmt",
+                    pix_fmt,
+                    "-colorspace",
+                    colorspace,
+                    "-color_trc",
+                    trc,
+                ],
+                &path,
+            );
+            MediaAnalyzer::new(&path)
+                .unwrap()
+                .get_info()
+                .unwrap()
+                .video
+                .unwrap()
+        };
+
+        let sdr = video_info("8bit.mkv", "yuv420p", "bt709", "bt709");
+        assert_eq!(sdr.pixel_format, "yuv420p");
+        assert_eq!(sdr.color_space.as_deref(), Some("bt709"));
+        assert!(!sdr.is_hdr);
+
+        let hdr = video_info("10bit.mkv", "yuv420p10le", "bt2020nc", "smpte2084");
+        assert_eq!(hdr.pixel_format, "yuv420p10le");
+        assert_eq!(hdr.color_space.as_deref(), Some("bt2020nc"));
+        assert!(hdr.is_hdr);
+    }
+
+    /// Pixels of a PNG data URL
+    fn decode_data_url(url: &str) -> image::RgbImage {
+        let data = url.strip_prefix("data:image/png;base64,").unwrap();
+        image::load_from_memory(&BASE64.decode(data).unwrap())
+            .unwrap()
+            .to_rgb8()
+    }
+
+    /// Mean absolute difference of two images' channel values
+    fn mean_difference(a: &image::RgbImage, b: &image::RgbImage) -> f64 {
+        assert_eq!(a.dimensions(), b.dimensions());
+        let total: u64 = a
+            .as_raw()
+            .iter()
+            .zip(b.as_raw())
+            .map(|(x, y)| x.abs_diff(*y) as u64)
+            .sum();
+        total as f64 / a.as_raw().len() as f64
+    }
+
+    #[test]
+    #[ignore = "needs the ffmpeg CLI"]
+    fn thumbnails_show_the_frame_at_the_timestamp() {
+        let dir = tempfile::tempdir().unwrap();
+        let clip = test_clip(dir.path());
+        let analyzer = MediaAnalyzer::new(&clip).unwrap();
+        let duration = analyzer.get_info().unwrap().duration;
+
+        // The first frame at or after each time, at 25 fps: 5.52 s is frame
+        // 138, and past the last frame (9.96 s) the last one
+        let cases = [(0.0, 0), (5.5, 138), (duration - 0.01, 249)];
+        let mut thumbnails = Vec::new();
+        for (timestamp, frame) in cases {
+            let url = analyzer
+                .extract_thumbnail(timestamp, 320, 240, ThumbnailFit::Stretch, [0, 0, 0])
+                .unwrap();
+            let expected_path = dir.path().join(format!("frame{frame}.png"));
+            let select = format!("select=eq(n\\,{frame})");
+            ffmpeg(
+                &[
+                    "-i",
+                    clip.to_str().unwrap(),
+                    "-vf",
+                    &select,
+                    "-frames:v",
+                    "1",
+                ],
+                &expected_path,
+            );
+            let expected = image::open(&expected_path).unwrap().to_rgb8();
+            let difference = mean_difference(&decode_data_url(&url), &expected);
+            assert!(difference < 2.0, "{timestamp} s differs by {difference}");
+            thumbnails.push(url);
+        }
+        assert_ne!(thumbnails[0], thumbnails[1]);
+        assert_ne!(thumbnails[1], thumbnails[2]);
+        assert_ne!(thumbnails[0], thumbnails[2]);
+    }
+
+    #[test]
+    #[ignore = "needs the ffmpeg CLI"]
+    fn rotated_video_is_reported_and_shown_upright() {
+        let dir = tempfile::tempdir().unwrap();
+        let clip = test_clip(dir.path());
+        let rotated = dir.path().join("rotated.mp4");
+        ffmpeg(
+            &[
+                "-display_rotation",
+                "90",
+                "-i",
+                clip.to_str().unwrap(),
+                "-c",
+                "copy",
+            ],
+            &rotated,
+        );
+        let analyzer = MediaAnalyzer::new(&rotated).unwrap();
+        let video = analyzer.get_info().unwrap().video.unwrap();
+        assert_eq!((video.width, video.height), (240, 320));
+        assert_eq!(video.rotation.rem_euclid(180), 90);
+
+        // The ffmpeg CLI rotates by the display matrix too
+        let expected_path = dir.path().join("upright.png");
+        ffmpeg(
+            &["-i", rotated.to_str().unwrap(), "-frames:v", "1"],
+            &expected_path,
+        );
+        let expected = image::open(&expected_path).unwrap().to_rgb8();
+        let thumbnail = analyzer
+            .extract_thumbnail(0.0, 240, 320, ThumbnailFit::Stretch, [0, 0, 0])
+            .unwrap();
+        let difference = mean_difference(&decode_data_url(&thumbnail), &expected);
+        assert!(difference < 2.0, "differs by {difference}");
     }
 }
@@ -0,0 +1,169 @@
+//! Extracting audio tracks to standalone files
+
+use super::process::run_ffmpeg;
+use super::MediaAnalyzer;
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// File format of extracted audio
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioFileFormat {
+    /// 16-bit PCM WAV (RF64 when larger than 4 GB)
+    #[default]
+    Wav,
+    /// Lossless FLAC, about half the size of WAV
+    Flac,
+}
+
+impl AudioFileFormat {
+    /// File extension, also used as the asset format
+    pub fn extension(self) -> &'static str {
+        match self {
+            AudioFileFormat::Wav => "wav",
+            AudioFileFormat::Flac => "flac",
+        }
+    }
+
+    fn codec(self) -> &'static str {
+        match self {
+            AudioFileFormat::Wav => "pcm_s16le",
+            AudioFileFormat::Flac => "flac",
+        }
+    }
+}
+
+/// Which audio to extract and how
+#[derive(Debug, Clone, Default)]
+pub struct AudioExtractOptions {
+    pub format: AudioFileFormat,
+    /// Container index of the audio stream; the best audio stream if `None`
+    pub stream_index: Option<usize>,
+    /// Start of the range, in seconds
+    pub start: Option<f64>,
+    /// End of the range, in seconds
+    pub end: Option<f64>,
+    /// Downmix (or upmix) to this many channels; the source layout is kept
+    /// if `None`
+    pub channels: Option<u32>,
+}
+
+/// An extracted audio file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtractedAudio {
+    pub path: String,
+    /// Duration in seconds
+    pub duration: f64,
+    pub sample_rate: u32,
+    pub channels: u32,
+    pub format: AudioFileFormat,
+    /// File size in bytes
+    pub size: u64,
+    /// ID of the project asset created for the file, if it was added to a
+    /// project
+    pub asset_id: Option<String>,
+}
+
+/// Most channels a FLAC stream can hold
+const FLAC_MAX_CHANNELS: u32 = 8;
+
+/// Extract an audio stream of `source` to `output`, calling `on_progress`
+/// with the fraction done (0.0 to 1.0)
+///
+/// The sample rate is passed through unchanged, and so are all channels
+/// unless `options.channels` asks for a different count.
+pub async fn extract_audio(
+    source: &Path,
+    output: &Path,
+    options: &AudioExtractOptions,
+    mut on_progress: impl FnMut(f64),
+) -> Result<ExtractedAudio> {
+    let info = MediaAnalyzer::new(source)?.get_info()?;
+    let stream = match options.stream_index {
+        Some(index) => info
+            .audio_streams
+            .iter()
+            .find(|s| s.index == index)
+            .ok_or_else(|| Error::NotFound(format!("No audio stream with index {index}")))?,
+        None => info
+            .audio
+            .as_ref()
+            .ok_or_else(|| Error::InvalidFormat("File has no audio stream".to_string()))?,
+    };
+
+    let start = options.start.unwrap_or(0.0).max(0.0);
+    let end = options
+        .end
+        .map(|end| {
+            if info.duration > 0.0 {
+                end.min(info.duration)
+            } else {
+                end
+            }
+        })
+        .unwrap_or(info.duration);
+    if options.end.is_some() && end <= start {
+        return Err(Error::InvalidFormat(format!(
+            "Invalid audio range {start:.3}s to {end:.3}s"
+        )));
+    }
+    let length = end - start;
+
+    let channels = options.channels.unwrap_or(stream.channels);
+    if channels == 0 || (options.format == AudioFileFormat::Flac && channels > FLAC_MAX_CHANNELS) {
+        return Err(Error::InvalidFormat(format!(
+            "{channels} channels are not supported in {:?}",
+            options.format
+        )));
+    }
+
+    if let Some(parent) = output.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    // Seeking before the input is sample accurate for audio, and much faster
+    // than decoding up to `start`
+    let mut args: Vec<String> = vec!["-y".into(), "-hide_banner".into()];
+    if start > 0.0 {
+        args.extend(["-ss".into(), format!("{start:.6}")]);
+    }
+    args.extend(["-i".into(), source.to_string_lossy().into_owned()]);
+    if options.end.is_some() {
+        args.extend(["-t".into(), format!("{length:.6}")]);
+    }
+    args.extend(["-map".into(), format!("0:{}", stream.index), "-vn".into()]);
+    args.extend(["-c:a".into(), options.format.codec().into()]);
+    if options.format == AudioFileFormat::Wav {
+        args.extend(["-rf64".into(), "auto".into()]);
+    }
+    if let Some(channels) = options.channels {
+        args.extend(["-ac".into(), channels.to_string()]);
+    }
+    args.extend(["-progress", "pipe:1", "-nostats"].map(String::from));
+    args.push(output.to_string_lossy().into_owned());
+
+    let result = run_ffmpeg(&args, None, |progress| {
+        if let Some(time) = progress.out_time.filter(|_| length > 0.0) {
+            on_progress((time / length).clamp(0.0, 1.0));
+        }
+    })
+    .await;
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(output);
+        return Err(e);
+    }
+    on_progress(1.0);
+
+    let extracted = MediaAnalyzer::new(output)?.get_info()?;
+    let audio = extracted.audio.as_ref();
+    Ok(ExtractedAudio {
+        path: extracted.path.clone(),
+        duration: extracted.duration,
+        sample_rate: audio.map_or(stream.sample_rate, |a| a.sample_rate),
+        channels: audio.map_or(channels, |a| a.channels),
+        format: options.format,
+        size: extracted.size,
+        asset_id: None,
+    })
+}
@@ -0,0 +1,90 @@
+//! Content fingerprints for matching media files by content rather than path
+
+use crate::Result;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Bytes hashed at the start, middle and end of a file
+const CHUNK_SIZE: u64 = 4 * 1024 * 1024;
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// 64-bit FNV-1a.  Used instead of `DefaultHasher` for hashes that are
+/// stored (fingerprints in project files, cache file names) and so must not
+/// change between Rust releases.
+pub(crate) struct Fnv1a(u64);
+
+impl Fnv1a {
+    pub(crate) fn new() -> Self {
+        Self(FNV_OFFSET)
+    }
+
+    pub(crate) fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 ^= b as u64;
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    pub(crate) fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Hash the file size, container duration and sampled content of `path`
+///
+/// Files up to three chunks long are hashed whole; larger ones only at the
+/// start, middle and end, so multi-GB files stay fast.  `duration` is the
+/// container duration in `AV_TIME_BASE` units.  The result is the size and
+/// hash as 32 hex digits, so files of different lengths never match.
+pub(crate) fn content_fingerprint(path: &Path, duration: i64) -> Result<String> {
+    let mut file = File::open(path)?;
+    let size = file.metadata()?.len();
+
+    let mut hasher = Fnv1a::new();
+    hasher.write(&size.to_le_bytes());
+    hasher.write(&duration.to_le_bytes());
+
+    let offsets = if size <= CHUNK_SIZE * 3 {
+        vec![0]
+    } else {
+        vec![0, size / 2 - CHUNK_SIZE / 2, size - CHUNK_SIZE]
+    };
+    let chunk_len = if offsets.len() == 1 { size } else { CHUNK_SIZE };
+
+    let mut buf = vec![0u8; chunk_len as usize];
+    for offset in offsets {
+        file.seek(SeekFrom::Start(offset))?;
+        file.read_exact(&mut buf)?;
+        hasher.write(&buf);
+    }
+
+    Ok(format!("{:016x}{:016x}", size, hasher.finish()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn copies_match_and_truncated_files_differ() {
+        let dir = tempfile::tempdir().unwrap();
+        let original = dir.path().join("original.bin");
+        // Larger than three chunks, so only samples are hashed
+        let content: Vec<u8> = (0..CHUNK_SIZE * 3 + 1000)
+            .map(|i| (i * 31 % 251) as u8)
+            .collect();
+        std::fs::write(&original, &content).unwrap();
+
+        let copy = dir.path().join("copy.bin");
+        std::fs::copy(&original, &copy).unwrap();
+        let truncated = dir.path().join("truncated.bin");
+        std::fs::write(&truncated, &content[..content.len() - 1]).unwrap();
+
+        let fingerprint = |path: &Path| content_fingerprint(path, 10_000_000).unwrap();
+        assert_eq!(fingerprint(&original), fingerprint(&copy));
+        assert_ne!(fingerprint(&original), fingerprint(&truncated));
+    }
+}
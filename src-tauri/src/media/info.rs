@@ -16,10 +16,14 @@ pub struct MediaInfo {
     pub duration: f64,
     /// Container format (e.g., "mp4", "mkv")
     pub format: String,
-    /// Video stream information (if present)
+    /// Best video stream information (if present)
     pub video: Option<VideoInfo>,
-    /// Audio stream information (if present)
+    /// Best audio stream information (if present)
     pub audio: Option<AudioInfo>,
+    /// All video streams
+    pub video_streams: Vec<VideoInfo>,
+    /// All audio streams
+    pub audio_streams: Vec<AudioInfo>,
     /// Subtitle streams
     pub subtitles: Vec<SubtitleInfo>,
     /// Chapter markers
@@ -32,6 +36,8 @@ pub struct MediaInfo {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct VideoInfo {
+    /// Stream index within the container
+    pub index: usize,
     /// Video codec (e.g., "h264", "hevc")
     pub codec: String,
     /// Width in pixels
@@ -40,39 +46,96 @@ pub struct VideoInfo {
     pub height: u32,
     /// Frame rate (frames per second)
     pub frame_rate: f64,
+    /// `Some(true)` when the base and average frame rates already prove a
+    /// variable frame rate; otherwise `None`, as only a full scan can tell
+    /// (see `MediaAnalyzer::analyze_frame_timing`)
+    pub is_vfr: Option<bool>,
     /// Bit rate in bits per second
     pub bit_rate: Option<u64>,
     /// Pixel format (e.g., "yuv420p")
     pub pixel_format: String,
-    /// Color space
+    /// Color space (e.g., "bt709", "bt2020nc")
     pub color_space: Option<String>,
+    /// Color primaries (e.g., "bt709", "bt2020")
+    pub color_primaries: Option<String>,
+    /// Transfer characteristics (e.g., "bt709", "smpte2084")
+    pub color_transfer: Option<String>,
+    /// Whether the transfer is HDR (PQ or HLG)
+    pub is_hdr: bool,
+    /// HDR10 mastering display metadata
+    pub mastering_display: Option<MasteringDisplay>,
+    /// HDR10 content light level metadata
+    pub content_light_level: Option<ContentLightLevel>,
     /// Total number of frames
     pub frame_count: Option<u64>,
+    /// Language code (e.g., "eng", "spa")
+    pub language: Option<String>,
+    /// Field order the stream declares, or `None` if it doesn't say; see
+    /// `MediaAnalyzer::detect_interlacing` to measure it
+    pub field_order: Option<super::FieldOrder>,
+    /// Clockwise rotation applied for display (0, 90, 180 or 270); `width`
+    /// and `height` are already swapped for 90 and 270
+    pub rotation: i32,
+}
+
+/// Color volume of the display an HDR video was mastered on (SMPTE ST 2086)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MasteringDisplay {
+    /// CIE 1931 xy chromaticity of the red, green and blue primaries
+    pub primaries: Option<[[f64; 2]; 3]>,
+    /// CIE 1931 xy chromaticity of the white point
+    pub white_point: Option<[f64; 2]>,
+    /// Minimum luminance in cd/m²
+    pub min_luminance: Option<f64>,
+    /// Maximum luminance in cd/m²
+    pub max_luminance: Option<f64>,
+}
+
+/// Content light levels of an HDR video (CTA-861.3)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContentLightLevel {
+    /// Maximum content light level in cd/m²
+    pub max_cll: u32,
+    /// Maximum frame-average light level in cd/m²
+    pub max_fall: u32,
 }
 
 /// Audio stream information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AudioInfo {
+    /// Stream index within the container
+    pub index: usize,
     /// Audio codec (e.g., "aac", "mp3")
     pub codec: String,
     /// Sample rate in Hz
     pub sample_rate: u32,
     /// Number of channels
     pub channels: u32,
-    /// Channel layout (e.g., "stereo", "5.1")
+    /// Channel layout as FFmpeg names it (e.g., "stereo", "5.1(side)"), or
+    /// "N channels" if the file doesn't specify one
     pub channel_layout: String,
     /// Bit rate in bits per second
     pub bit_rate: Option<u64>,
     /// Bits per sample
     pub bits_per_sample: Option<u32>,
+    /// Language code (e.g., "eng", "spa")
+    pub language: Option<String>,
+    /// Title/description (e.g., "Director's commentary")
+    pub title: Option<String>,
+    /// Whether players should pick this track by default
+    pub is_default: bool,
+    /// Whether the track is marked as forced
+    pub is_forced: bool,
 }
 
 /// Subtitle stream information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SubtitleInfo {
-    /// Stream index
+    /// Stream index within the container, as taken by `extract_subtitles`
     pub index: usize,
     /// Subtitle codec
     pub codec: String,
@@ -80,6 +143,10 @@ pub struct SubtitleInfo {
     pub language: Option<String>,
     /// Title/description
     pub title: Option<String>,
+    /// Whether players should pick this track by default
+    pub is_default: bool,
+    /// Whether the track only covers forced parts (e.g., foreign dialogue)
+    pub is_forced: bool,
 }
 
 /// Chapter marker information
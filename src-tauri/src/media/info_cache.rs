@@ -0,0 +1,136 @@
+//! Cache of probed media information
+
+use super::fingerprint::Fnv1a;
+use super::{file_stamp, source_key, MediaInfo};
+use crate::Result;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Entries kept in memory
+const MEMORY_CAPACITY: usize = 256;
+
+lazy_static! {
+    /// Recently used entries, least recently used first
+    static ref MEMORY: Mutex<VecDeque<(String, CacheEntry)>> = Mutex::new(VecDeque::new());
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    source: String,
+    size: u64,
+    modified_ms: u64,
+    info: MediaInfo,
+}
+
+/// Two-level cache of [`MediaInfo`] for local files
+///
+/// Entries are keyed by canonical path and record the file's size and
+/// modification time, so a file replaced in place is probed again.  Recent
+/// entries stay in memory; all of them are also kept in the user cache
+/// directory, one compact JSON file per source, and survive restarts.
+/// Remote URLs are never cached.
+pub struct MediaInfoCache;
+
+impl MediaInfoCache {
+    /// Directory holding the cache files
+    fn dir() -> Option<PathBuf> {
+        dirs::cache_dir().map(|d| d.join("io.waldiez.player").join("media_info"))
+    }
+
+    /// Cache file for a source
+    fn entry_path(source: &str) -> Option<PathBuf> {
+        let mut hasher = Fnv1a::new();
+        hasher.write(source.as_bytes());
+        Self::dir().map(|d| d.join(format!("{:016x}.json", hasher.finish())))
+    }
+
+    fn remember(source: String, entry: CacheEntry) {
+        let mut memory = MEMORY.lock().unwrap();
+        memory.retain(|(k, _)| *k != source);
+        if memory.len() >= MEMORY_CAPACITY {
+            memory.pop_front();
+        }
+        memory.push_back((source, entry));
+    }
+
+    /// Look up the info of a file, dropping the entry if the file changed
+    pub fn load(path: &Path) -> Option<MediaInfo> {
+        let (size, modified_ms) = file_stamp(path)?;
+        let source = source_key(path);
+        let current = |entry: &CacheEntry| {
+            entry.source == source && entry.size == size && entry.modified_ms == modified_ms
+        };
+
+        {
+            let mut memory = MEMORY.lock().unwrap();
+            if let Some(pos) = memory.iter().position(|(k, _)| *k == source) {
+                let (key, entry) = memory.remove(pos).unwrap();
+                if current(&entry) {
+                    let info = entry.info.clone();
+                    memory.push_back((key, entry));
+                    return Some(info);
+                }
+            }
+        }
+
+        let entry_path = Self::entry_path(&source)?;
+        let content = fs::read(&entry_path).ok()?;
+        let entry: CacheEntry = serde_json::from_slice(&content).ok()?;
+        if !current(&entry) {
+            let _ = fs::remove_file(&entry_path);
+            return None;
+        }
+        let info = entry.info.clone();
+        Self::remember(source, entry);
+        Some(info)
+    }
+
+    /// Store the info probed from a file
+    pub fn store(path: &Path, info: &MediaInfo) -> Result<()> {
+        let Some((size, modified_ms)) = file_stamp(path) else {
+            return Ok(());
+        };
+        let source = source_key(path);
+        let entry = CacheEntry {
+            source: source.clone(),
+            size,
+            modified_ms,
+            info: info.clone(),
+        };
+        let entry_path = Self::entry_path(&source);
+        let content = serde_json::to_vec(&entry)?;
+        Self::remember(source, entry);
+
+        let Some(entry_path) = entry_path else {
+            return Ok(());
+        };
+        if let Some(parent) = entry_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&entry_path, content)?;
+        Ok(())
+    }
+
+    /// Forget the cached info of one file
+    pub fn invalidate(path: &Path) -> Result<()> {
+        let source = source_key(path);
+        MEMORY.lock().unwrap().retain(|(k, _)| *k != source);
+        match Self::entry_path(&source) {
+            Some(entry_path) if entry_path.exists() => Ok(fs::remove_file(entry_path)?),
+            _ => Ok(()),
+        }
+    }
+
+    /// Forget all cached info
+    pub fn clear() -> Result<()> {
+        MEMORY.lock().unwrap().clear();
+        match Self::dir() {
+            Some(dir) if dir.exists() => Ok(fs::remove_dir_all(dir)?),
+            _ => Ok(()),
+        }
+    }
+}
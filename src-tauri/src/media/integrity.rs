@@ -0,0 +1,158 @@
+//! Media file integrity checking
+
+use serde::{Deserialize, Serialize};
+
+/// Overall judgement of a [`IntegrityReport`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IntegrityVerdict {
+    /// No problems found
+    Ok,
+    /// A few damaged packets or frames; the file plays but may glitch
+    Suspect,
+    /// Unreadable, truncated or badly damaged
+    Corrupt,
+}
+
+/// Result of [`MediaAnalyzer::verify_integrity`](super::MediaAnalyzer::verify_integrity)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrityReport {
+    pub verdict: IntegrityVerdict,
+    /// Whether every packet was decoded, rather than only read
+    pub full_decode: bool,
+    /// Packets read from all streams
+    pub packets: u64,
+    /// Failed reads from the container, e.g. damaged or missing data
+    pub read_errors: u64,
+    /// Packets the demuxer flagged as corrupt
+    pub corrupt_packets: u64,
+    /// Packets the decoders rejected and frames they flagged as damaged
+    /// (only with a full decode)
+    pub decode_errors: u64,
+    /// Whether the data stops well before the declared duration (e.g. an
+    /// unfinished download), or the file could not be opened at all (e.g.
+    /// an MP4 without its `moov` index)
+    pub truncated: bool,
+    /// Duration declared by the container, in seconds (0 if unknown)
+    pub duration: f64,
+    /// End of the last packet read, in seconds
+    pub readable_duration: f64,
+    /// Time of the first problem, in seconds, where known
+    pub first_error_time: Option<f64>,
+    /// Descriptions of the first few problems
+    pub messages: Vec<String>,
+}
+
+/// A kind of problem found while reading a file
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum IntegrityIssue {
+    Read,
+    CorruptPacket,
+    Decode,
+}
+
+/// Share of packets that may be damaged before a file counts as corrupt
+/// rather than suspect
+const CORRUPT_ERROR_RATIO: f64 = 0.01;
+
+/// Problems described in a report; the counts include the rest
+const MAX_MESSAGES: usize = 10;
+
+/// Data may end this share of the declared duration early (at least a
+/// second) before the file counts as truncated, since streams rarely end
+/// exactly together
+const TRUNCATION_TOLERANCE: f64 = 0.02;
+
+/// Tallies packets and problems into an [`IntegrityReport`]
+pub(crate) struct IntegrityChecker {
+    report: IntegrityReport,
+}
+
+impl IntegrityChecker {
+    /// Create a checker for a pass that decodes (`full_decode`) or only
+    /// reads packets
+    pub fn new(full_decode: bool) -> Self {
+        Self {
+            report: IntegrityReport {
+                verdict: IntegrityVerdict::Ok,
+                full_decode,
+                packets: 0,
+                read_errors: 0,
+                corrupt_packets: 0,
+                decode_errors: 0,
+                truncated: false,
+                duration: 0.0,
+                readable_duration: 0.0,
+                first_error_time: None,
+                messages: Vec::new(),
+            },
+        }
+    }
+
+    /// Report for a file that could not even be opened
+    pub fn open_failed(full_decode: bool, message: String) -> IntegrityReport {
+        let mut checker = Self::new(full_decode);
+        checker.report.truncated = true;
+        checker.report.messages.push(message);
+        checker.finish(0.0)
+    }
+
+    /// Record a packet read successfully, ending at `end_time` (seconds)
+    /// if it has a timestamp
+    pub fn packet(&mut self, end_time: Option<f64>) {
+        self.report.packets += 1;
+        if let Some(end) = end_time {
+            self.report.readable_duration = self.report.readable_duration.max(end);
+        }
+    }
+
+    /// Record a problem at `time` (seconds) if known
+    pub fn issue(&mut self, issue: IntegrityIssue, time: Option<f64>, message: String) {
+        let report = &mut self.report;
+        match issue {
+            IntegrityIssue::Read => report.read_errors += 1,
+            IntegrityIssue::CorruptPacket => report.corrupt_packets += 1,
+            IntegrityIssue::Decode => report.decode_errors += 1,
+        }
+        if report.first_error_time.is_none() {
+            report.first_error_time = time;
+        }
+        if report.messages.len() < MAX_MESSAGES {
+            report.messages.push(message);
+        }
+    }
+
+    /// Judge the file, given the `duration` (seconds, 0 if unknown) its
+    /// container declares
+    pub fn finish(mut self, duration: f64) -> IntegrityReport {
+        let report = &mut self.report;
+        report.duration = duration;
+        if duration > 0.0 {
+            let tolerance = (duration * TRUNCATION_TOLERANCE).max(1.0);
+            if report.readable_duration < duration - tolerance {
+                report.truncated = true;
+                report.messages.push(format!(
+                    "Data ends at {:.1}s of {:.1}s",
+                    report.readable_duration, duration
+                ));
+                if report.first_error_time.is_none() {
+                    report.first_error_time = Some(report.readable_duration);
+                }
+            }
+        }
+
+        let errors = report.read_errors + report.corrupt_packets + report.decode_errors;
+        report.verdict = if report.truncated
+            || report.packets == 0
+            || errors as f64 > report.packets as f64 * CORRUPT_ERROR_RATIO
+        {
+            IntegrityVerdict::Corrupt
+        } else if errors > 0 {
+            IntegrityVerdict::Suspect
+        } else {
+            IntegrityVerdict::Ok
+        };
+        self.report
+    }
+}
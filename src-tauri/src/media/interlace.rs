@@ -0,0 +1,222 @@
+//! Interlaced video detection
+
+use serde::{Deserialize, Serialize};
+
+/// Order in which the fields of a video are displayed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FieldOrder {
+    /// Whole frames, no fields
+    Progressive,
+    /// Interlaced, top field first
+    Tff,
+    /// Interlaced, bottom field first
+    Bff,
+}
+
+/// Result of [`MediaAnalyzer::detect_interlacing`](super::MediaAnalyzer::detect_interlacing)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InterlaceReport {
+    /// Field order most of the sampled frames agree on, or `None` if none
+    /// could be classified (e.g. a still picture)
+    pub field_order: Option<FieldOrder>,
+    /// Frames that looked progressive
+    pub progressive_frames: u32,
+    /// Frames that looked interlaced, top field first
+    pub tff_frames: u32,
+    /// Frames that looked interlaced, bottom field first
+    pub bff_frames: u32,
+    /// Frames with too little motion to tell
+    pub undetermined_frames: u32,
+}
+
+/// How much more combing one field pairing must show than the other to
+/// classify a frame
+const COMB_RATIO: f64 = 1.5;
+
+/// Mean comb value (0-510) below which a frame is too flat to classify
+const MIN_COMB: f64 = 2.0;
+
+/// Classifies consecutive frames as progressive or interlaced, like FFmpeg's
+/// `idet` filter.
+///
+/// Each frame is compared with the previous one by weaving fields from both:
+///
+/// - With top field first, the fields play as T₀ B₀ T₁ B₁, so the current top
+///   field woven with the previous bottom field is one field apart and combs
+///   little, while the previous top with the current bottom is three fields
+///   apart and combs a lot.  Bottom field first is the mirror image.
+/// - Progressive frames comb far less on their own than either cross-frame
+///   weave, which are both a whole frame apart.
+///
+/// Frames with no motion comb the same in every pairing and stay
+/// undetermined, so a still scene never looks interlaced.
+pub(crate) struct InterlaceDetector {
+    prev_width: usize,
+    prev_luma: Vec<u8>,
+    report: InterlaceReport,
+}
+
+impl InterlaceDetector {
+    /// Create a detector with no frames seen
+    pub fn new() -> Self {
+        Self {
+            prev_width: 0,
+            prev_luma: Vec::new(),
+            report: InterlaceReport {
+                field_order: None,
+                progressive_frames: 0,
+                tff_frames: 0,
+                bff_frames: 0,
+                undetermined_frames: 0,
+            },
+        }
+    }
+
+    /// Feed the full-height luma plane of the next frame, `width` pixels
+    /// wide
+    ///
+    /// A size change resets the comparison instead of classifying the frame.
+    pub fn push(&mut self, luma: &[u8], width: usize) {
+        if width > 0 && width == self.prev_width && luma.len() == self.prev_luma.len() {
+            let pixels = luma.len() as f64;
+            let own = comb(luma, luma, width) as f64;
+            // Current top field with the previous bottom field, and the other
+            // way round
+            let top_first = comb(luma, &self.prev_luma, width) as f64;
+            let bottom_first = comb(&self.prev_luma, luma, width) as f64;
+
+            let report = &mut self.report;
+            if top_first.max(bottom_first) / pixels < MIN_COMB {
+                report.undetermined_frames += 1;
+            } else if bottom_first > COMB_RATIO * top_first {
+                report.tff_frames += 1;
+            } else if top_first > COMB_RATIO * bottom_first {
+                report.bff_frames += 1;
+            } else if top_first.min(bottom_first) > COMB_RATIO * own {
+                report.progressive_frames += 1;
+            } else {
+                report.undetermined_frames += 1;
+            }
+        }
+        self.prev_width = width;
+        self.prev_luma.clear();
+        self.prev_luma.extend_from_slice(luma);
+    }
+
+    /// The classification counts and the majority field order
+    pub fn finish(mut self) -> InterlaceReport {
+        let report = &mut self.report;
+        let interlaced = report.tff_frames + report.bff_frames;
+        report.field_order = if interlaced > report.progressive_frames {
+            Some(if report.tff_frames >= report.bff_frames {
+                FieldOrder::Tff
+            } else {
+                FieldOrder::Bff
+            })
+        } else if report.progressive_frames > 0 {
+            Some(FieldOrder::Progressive)
+        } else {
+            None
+        };
+        self.report
+    }
+}
+
+/// Total combing of the frame woven from the even rows of `top` and the odd
+/// rows of `bottom`: how far each row strays from the average of its
+/// neighbours
+fn comb(top: &[u8], bottom: &[u8], width: usize) -> u64 {
+    let height = top.len() / width;
+    let row = |y: usize| {
+        let source = if y & 1 == 0 { top } else { bottom };
+        &source[y * width..(y + 1) * width]
+    };
+    let mut total = 0;
+    for y in 1..height.saturating_sub(1) {
+        let (above, line, below) = (row(y - 1), row(y), row(y + 1));
+        for x in 0..width {
+            let expected = above[x] as i32 + below[x] as i32;
+            total += (2 * line[x] as i32 - expected).unsigned_abs() as u64;
+        }
+    }
+    total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_media::ffmpeg;
+    use super::super::MediaAnalyzer;
+    use super::*;
+
+    const WIDTH: usize = 64;
+    const HEIGHT: usize = 64;
+
+    /// Luma of horizontal bands that have moved down `position` pixels
+    fn row_value(y: usize, position: f64) -> u8 {
+        (128.0 + 100.0 * ((y as f64 - position) / 6.0).sin()) as u8
+    }
+
+    /// A frame with each field at its own position, as an interlaced camera
+    /// captures it; equal positions make a progressive frame
+    fn frame(top_position: f64, bottom_position: f64) -> Vec<u8> {
+        (0..HEIGHT)
+            .flat_map(|y| {
+                let position = if y % 2 == 0 {
+                    top_position
+                } else {
+                    bottom_position
+                };
+                vec![row_value(y, position); WIDTH]
+            })
+            .collect()
+    }
+
+    fn detect(frames: impl Iterator<Item = Vec<u8>>) -> InterlaceReport {
+        let mut detector = InterlaceDetector::new();
+        for luma in frames {
+            detector.push(&luma, WIDTH);
+        }
+        detector.finish()
+    }
+
+    #[test]
+    fn moving_progressive_frames_are_progressive() {
+        let report = detect((0..20).map(|t| frame(3.0 * t as f64, 3.0 * t as f64)));
+        assert_eq!(report.field_order, Some(FieldOrder::Progressive));
+        assert_eq!(report.tff_frames + report.bff_frames, 0);
+    }
+
+    #[test]
+    fn top_field_first_frames_are_tff() {
+        // Fields 1.5 pixels of motion apart, the top one first
+        let report = detect((0..20).map(|t| frame(3.0 * t as f64, 3.0 * t as f64 + 1.5)));
+        assert_eq!(report.field_order, Some(FieldOrder::Tff));
+    }
+
+    #[test]
+    #[ignore = "needs the ffmpeg CLI"]
+    fn progressive_h264_is_not_interlaced() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("progressive.mp4");
+        ffmpeg(
+            &[
+                "-f",
+                "lavfi",
+                "-i",
+                "testsrc2=duration=4:size=640x360:rate=25",
+                "-c:v",
+                "libx264",
+                "-pix_fmt",
+                "yuv420p",
+            ],
+            &path,
+        );
+        let report = MediaAnalyzer::new(&path)
+            .unwrap()
+            .detect_interlacing()
+            .unwrap();
+        assert_eq!(report.field_order, Some(FieldOrder::Progressive));
+    }
+}
@@ -4,9 +4,55 @@
 //! using FFmpeg.
 
 mod analyzer;
+mod audio_extract;
+mod fingerprint;
 mod info;
+mod info_cache;
+mod integrity;
+mod interlace;
+mod preview;
+pub(crate) mod process;
+mod proxy;
+mod quality;
+mod scenes;
+mod spectrogram;
+mod subtitles;
+mod tempo;
+#[cfg(test)]
+mod test_media;
+mod timing;
 mod waveform;
 
-pub use analyzer::MediaAnalyzer;
+use std::fs;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+pub use analyzer::{MediaAnalyzer, ThumbnailFit};
+pub use audio_extract::{extract_audio, AudioExtractOptions, AudioFileFormat, ExtractedAudio};
 pub use info::MediaInfo;
-pub use waveform::WaveformData;
+pub use info_cache::MediaInfoCache;
+pub use integrity::{IntegrityReport, IntegrityVerdict};
+pub use interlace::{FieldOrder, InterlaceReport};
+pub use preview::generate_preview_clip;
+pub use proxy::{cancel_proxy, generate_proxy, ProxyCodec, ProxyProgress, ProxyResult};
+pub use quality::{QualityThresholds, VideoQualityReport};
+pub use scenes::SceneCut;
+pub use tempo::TempoAnalysis;
+pub use timing::FrameTiming;
+pub use waveform::{WaveformCache, WaveformData};
+
+/// Size and modification time (ms since the epoch) of a local file, which
+/// the caches compare to notice a file replaced in place
+pub(crate) fn file_stamp(path: &Path) -> Option<(u64, u64)> {
+    let meta = fs::metadata(path).ok()?;
+    let modified = meta.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    Some((meta.len(), modified.as_millis() as u64))
+}
+
+/// Canonical form of `path` that the caches key their entries by
+pub(crate) fn source_key(path: &Path) -> String {
+    path.canonicalize()
+        .unwrap_or_else(|_| path.to_path_buf())
+        .to_string_lossy()
+        .to_string()
+}
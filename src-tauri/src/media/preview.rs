@@ -0,0 +1,120 @@
+//! Short animated previews for hovering over assets
+
+use super::process::run_ffmpeg;
+use super::MediaAnalyzer;
+use crate::{Error, Result};
+use std::path::Path;
+
+/// Where previews sample the source, as fractions of its duration.  Fixed so
+/// the same source always gives the same preview.
+const SAMPLE_POINTS: [f64; 3] = [0.2, 0.5, 0.8];
+
+/// Frame rate of previews; smooth enough at thumbnail size and keeps WebP
+/// files small
+const PREVIEW_FPS: u32 = 15;
+
+/// Encode a silent preview of `source` lasting `duration` seconds and
+/// `width` pixels wide to `output`, an animated WebP (`.webp`, looping) or
+/// an MP4 (`.mp4`, for a `<video loop>`)
+///
+/// The preview joins equal segments taken around 20%, 50% and 80% of the
+/// source; a source no longer than `duration` is used whole.  Each segment
+/// is seeked to with ffmpeg's input seeking, which decodes from the
+/// keyframe before the target and drops frames up to it, so segments start
+/// on the exact frame.
+pub async fn generate_preview_clip(
+    source: &Path,
+    output: &Path,
+    duration: f64,
+    width: u32,
+) -> Result<()> {
+    let info = MediaAnalyzer::new(source)?.get_info()?;
+    if info.video.is_none() {
+        return Err(Error::InvalidFormat("File has no video stream".to_string()));
+    }
+    if info.duration <= 0.0 {
+        return Err(Error::InvalidFormat(
+            "Previews need a video with a known duration".to_string(),
+        ));
+    }
+
+    let codec_args: &[&str] = match output.extension().and_then(|e| e.to_str()) {
+        Some(e) if e.eq_ignore_ascii_case("webp") => &[
+            "-c:v",
+            "libwebp_anim",
+            "-lossless",
+            "0",
+            "-quality",
+            "70",
+            "-loop",
+            "0",
+        ],
+        Some(e) if e.eq_ignore_ascii_case("mp4") => &[
+            "-c:v",
+            "libx264",
+            "-preset",
+            "veryfast",
+            "-crf",
+            "28",
+            "-pix_fmt",
+            "yuv420p",
+            "-movflags",
+            "+faststart",
+        ],
+        _ => {
+            return Err(Error::InvalidFormat(format!(
+                "Previews are written as .webp or .mp4, not {}",
+                output.display()
+            )))
+        }
+    };
+
+    // (start, length) of each segment
+    let segments: Vec<(f64, f64)> = if info.duration <= duration {
+        vec![(0.0, info.duration)]
+    } else {
+        let length = duration / SAMPLE_POINTS.len() as f64;
+        SAMPLE_POINTS
+            .iter()
+            .map(|point| {
+                let start =
+                    (info.duration * point - length / 2.0).clamp(0.0, info.duration - length);
+                (start, length)
+            })
+            .collect()
+    };
+
+    let mut args: Vec<String> = vec!["-y".into(), "-hide_banner".into()];
+    let source = source.to_string_lossy().into_owned();
+    for (start, length) in &segments {
+        args.extend(["-ss".into(), format!("{start:.6}")]);
+        args.extend(["-t".into(), format!("{length:.6}")]);
+        args.extend(["-i".into(), source.clone()]);
+    }
+    // Scale each segment before joining them, so the concat filter sees
+    // matching sizes; the height follows the aspect ratio, rounded to even
+    let mut graph = String::new();
+    for i in 0..segments.len() {
+        graph.push_str(&format!(
+            "[{i}:v:0]setpts=PTS-STARTPTS,fps={PREVIEW_FPS},scale={width}:-2,setsar=1[v{i}];"
+        ));
+    }
+    for i in 0..segments.len() {
+        graph.push_str(&format!("[v{i}]"));
+    }
+    graph.push_str(&format!("concat=n={}:v=1:a=0[out]", segments.len()));
+    args.extend(["-filter_complex".into(), graph]);
+    args.extend(["-map", "[out]", "-an"].map(String::from));
+    args.extend(codec_args.iter().map(|s| s.to_string()));
+    args.extend(["-progress", "pipe:1", "-nostats"].map(String::from));
+    args.push(output.to_string_lossy().into_owned());
+
+    if let Some(parent) = output.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    if let Err(e) = run_ffmpeg(&args, None, |_| {}).await {
+        let _ = std::fs::remove_file(output);
+        return Err(e);
+    }
+    Ok(())
+}
@@ -0,0 +1,92 @@
+//! Running the `ffmpeg` command line tool for transcoding jobs
+
+use crate::{Error, Result};
+use std::process::Stdio;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
+use tokio::process::Command;
+use tokio::sync::Notify;
+
+/// Latest values of an `ffmpeg -progress` report
+#[derive(Debug, Default)]
+pub(crate) struct FfmpegProgress {
+    /// Frames written so far
+    pub frame: u64,
+    /// Output timestamp, in seconds
+    pub out_time: Option<f64>,
+}
+
+/// Run `ffmpeg` with `args` (which must include `-progress pipe:1`), calling
+/// `on_progress` at the end of each report block and killing it when
+/// `cancel` is notified
+pub(crate) async fn run_ffmpeg(
+    args: &[String],
+    cancel: Option<&Notify>,
+    mut on_progress: impl FnMut(&FfmpegProgress),
+) -> Result<()> {
+    let mut child = Command::new("ffmpeg")
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| Error::Media(format!("Failed to start ffmpeg: {e}")))?;
+
+    let stderr = child.stderr.take();
+    let stderr_task = tokio::spawn(async move {
+        let mut text = String::new();
+        if let Some(mut stderr) = stderr {
+            let _ = stderr.read_to_string(&mut text).await;
+        }
+        text
+    });
+
+    if let Some(stdout) = child.stdout.take() {
+        let mut lines = BufReader::new(stdout).lines();
+        let mut progress = FfmpegProgress::default();
+        let cancelled = async {
+            match cancel {
+                Some(cancel) => cancel.notified().await,
+                None => std::future::pending().await,
+            }
+        };
+        tokio::pin!(cancelled);
+        loop {
+            let line = tokio::select! {
+                line = lines.next_line() => match line {
+                    Ok(Some(line)) => line,
+                    _ => break,
+                },
+                _ = &mut cancelled => {
+                    let _ = child.kill().await;
+                    return Err(Error::Cancelled);
+                }
+            };
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let value = value.trim();
+            match key {
+                "frame" => progress.frame = value.parse().unwrap_or(progress.frame),
+                // Despite the name, out_time_ms is in microseconds ("N/A"
+                // before the first frame)
+                "out_time_ms" => {
+                    if let Ok(us) = value.parse::<i64>() {
+                        progress.out_time = Some(us.max(0) as f64 / 1_000_000.0);
+                    }
+                }
+                "progress" => on_progress(&progress),
+                _ => {}
+            }
+        }
+    }
+
+    let status = child.wait().await?;
+    if !status.success() {
+        let stderr = stderr_task.await.unwrap_or_default();
+        // The last lines carry the actual error
+        let tail: Vec<&str> = stderr.trim().lines().rev().take(3).collect();
+        let tail: Vec<&str> = tail.into_iter().rev().collect();
+        return Err(Error::Media(format!("ffmpeg failed: {}", tail.join(" "))));
+    }
+    Ok(())
+}
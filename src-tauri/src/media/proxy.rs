@@ -0,0 +1,175 @@
+//! Low resolution proxy media for smooth preview editing
+
+use super::process::run_ffmpeg;
+use super::{MediaAnalyzer, MediaInfo};
+use crate::{Error, Result};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+
+lazy_static! {
+    /// Cancellation signals of running proxy jobs, keyed by job ID
+    static ref PROXY_JOBS: Mutex<HashMap<String, Arc<Notify>>> = Mutex::new(HashMap::new());
+}
+
+/// Video codec of a proxy file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProxyCodec {
+    /// H.264 and AAC in MP4, small files
+    #[default]
+    H264,
+    /// ProRes 422 Proxy and PCM in MOV, larger but cheaper to decode
+    Prores,
+}
+
+/// Progress of a proxy job, reported after each `ffmpeg` progress block
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxyProgress {
+    pub job_id: String,
+    pub frames_done: u64,
+    /// Estimated from the source duration and frame rate
+    pub frames_total: u64,
+}
+
+/// A finished proxy file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxyResult {
+    pub path: String,
+    pub info: MediaInfo,
+}
+
+/// Transcode `source` into a proxy at most `max_height` pixels tall in
+/// `output_dir`, calling `on_progress` as frames are encoded
+///
+/// The proxy keeps the first video and audio streams and the aspect ratio,
+/// with keyframes every half second so scrubbing stays responsive.  It can
+/// be stopped with [`cancel_proxy`] using `job_id`, which deletes the
+/// partial file.
+pub async fn generate_proxy(
+    job_id: &str,
+    source: &Path,
+    output_dir: &Path,
+    max_height: u32,
+    codec: ProxyCodec,
+    mut on_progress: impl FnMut(ProxyProgress),
+) -> Result<ProxyResult> {
+    let source_info = MediaAnalyzer::new(source)?.get_info()?;
+    let video = source_info
+        .video
+        .as_ref()
+        .ok_or_else(|| Error::InvalidFormat("File has no video stream".to_string()))?;
+    let frames_total = (source_info.duration * video.frame_rate).ceil().max(1.0) as u64;
+    let keyframe_interval = ((video.frame_rate / 2.0).round() as u32).max(1);
+
+    std::fs::create_dir_all(output_dir)?;
+    let stem = source
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "proxy".to_string());
+    let extension = match codec {
+        ProxyCodec::H264 => "mp4",
+        ProxyCodec::Prores => "mov",
+    };
+    let output = output_dir.join(format!("{stem}_proxy_{max_height}p.{extension}"));
+
+    let cancel = Arc::new(Notify::new());
+    {
+        let mut jobs = PROXY_JOBS.lock().unwrap();
+        if jobs.contains_key(job_id) {
+            return Err(Error::Internal(format!(
+                "Proxy job {job_id} is already running"
+            )));
+        }
+        jobs.insert(job_id.to_string(), cancel.clone());
+    }
+    let args = ffmpeg_args(source, &output, max_height, codec, keyframe_interval);
+    let result = run_ffmpeg(&args, Some(&cancel), |progress| {
+        on_progress(ProxyProgress {
+            job_id: job_id.to_string(),
+            frames_done: progress.frame,
+            frames_total: frames_total.max(progress.frame),
+        })
+    })
+    .await;
+    PROXY_JOBS.lock().unwrap().remove(job_id);
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&output);
+        return Err(e);
+    }
+
+    let info = MediaAnalyzer::new(&output)?.get_info()?;
+    Ok(ProxyResult {
+        path: output.to_string_lossy().into_owned(),
+        info,
+    })
+}
+
+/// Stop a proxy job started with [`generate_proxy`]
+pub fn cancel_proxy(job_id: &str) -> Result<()> {
+    match PROXY_JOBS.lock().unwrap().get(job_id) {
+        Some(cancel) => {
+            cancel.notify_one();
+            Ok(())
+        }
+        None => Err(Error::NotFound(format!(
+            "No proxy job running for {job_id}"
+        ))),
+    }
+}
+
+/// `ffmpeg` arguments transcoding `source` into the proxy at `output`
+fn ffmpeg_args(
+    source: &Path,
+    output: &Path,
+    max_height: u32,
+    codec: ProxyCodec,
+    keyframe_interval: u32,
+) -> Vec<String> {
+    // Never upscale; the width follows the aspect ratio, rounded to even
+    let scale = format!("scale=-2:'trunc(min(ih,{max_height})/2)*2'");
+    let mut args: Vec<String> = vec!["-y".into(), "-hide_banner".into(), "-i".into()];
+    args.push(source.to_string_lossy().into_owned());
+    args.extend(["-map", "0:v:0", "-map", "0:a:0?", "-vf"].map(String::from));
+    args.push(scale);
+    args.extend(["-g".into(), keyframe_interval.to_string()]);
+    let codec_args: &[&str] = match codec {
+        ProxyCodec::H264 => &[
+            "-c:v",
+            "libx264",
+            "-preset",
+            "veryfast",
+            "-tune",
+            "fastdecode",
+            "-crf",
+            "23",
+            "-pix_fmt",
+            "yuv420p",
+            "-c:a",
+            "aac",
+            "-b:a",
+            "128k",
+            "-movflags",
+            "+faststart",
+        ],
+        ProxyCodec::Prores => &[
+            "-c:v",
+            "prores_ks",
+            "-profile:v",
+            "0",
+            "-pix_fmt",
+            "yuv422p10le",
+            "-c:a",
+            "pcm_s16le",
+        ],
+    };
+    args.extend(codec_args.iter().map(|s| s.to_string()));
+    args.extend(["-progress", "pipe:1", "-nostats"].map(String::from));
+    args.push(output.to_string_lossy().into_owned());
+    args
+}
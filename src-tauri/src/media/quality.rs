@@ -0,0 +1,180 @@
+//! Black and frozen frame detection for quality control
+
+use serde::{Deserialize, Serialize};
+
+/// Thresholds for [`MediaAnalyzer::analyze_video_quality`](super::MediaAnalyzer::analyze_video_quality)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct QualityThresholds {
+    /// Frames with a mean luma below this (0.0 to 1.0) count as black
+    pub black_luma: f64,
+    /// Minimum consecutive black frames to report a black segment
+    pub min_black_frames: u32,
+    /// Frames differing from the previous one by less than this mean
+    /// absolute difference (0.0 to 1.0) count as frozen
+    pub freeze_difference: f64,
+    /// Minimum consecutive identical frames to report a freeze
+    pub min_freeze_frames: u32,
+}
+
+impl Default for QualityThresholds {
+    fn default() -> Self {
+        Self {
+            // Limited-range black is 16/255 ≈ 0.063
+            black_luma: 0.1,
+            min_black_frames: 5,
+            freeze_difference: 0.002,
+            min_freeze_frames: 12,
+        }
+    }
+}
+
+/// A time range in seconds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeRange {
+    /// Start of the first frame in the range
+    pub start: f64,
+    /// End of the last frame in the range
+    pub end: f64,
+}
+
+/// Black and frozen segments found in a video
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VideoQualityReport {
+    /// Segments of black frames, including fades to and from black
+    pub black_segments: Vec<TimeRange>,
+    /// Segments where the picture does not change (black segments excluded)
+    pub freeze_segments: Vec<TimeRange>,
+}
+
+/// A run of consecutive matching frames
+struct Run {
+    start: f64,
+    frames: u32,
+}
+
+/// Finds black and frozen runs in a sequence of downscaled luma planes
+pub(crate) struct QualityDetector {
+    thresholds: QualityThresholds,
+    prev_luma: Vec<u8>,
+    prev_time: Option<f64>,
+    /// Duration of the most recent frame, used to close runs at end of stream
+    frame_interval: f64,
+    black: Option<Run>,
+    freeze: Option<Run>,
+    report: VideoQualityReport,
+}
+
+impl QualityDetector {
+    /// Create a detector using `thresholds`
+    pub fn new(thresholds: QualityThresholds) -> Self {
+        Self {
+            thresholds,
+            prev_luma: Vec::new(),
+            prev_time: None,
+            frame_interval: 0.0,
+            black: None,
+            freeze: None,
+            report: VideoQualityReport::default(),
+        }
+    }
+
+    /// Feed the luma plane of the next frame (same size for every frame)
+    pub fn push(&mut self, timestamp: f64, luma: &[u8]) {
+        if let Some(prev) = self.prev_time {
+            if timestamp > prev {
+                self.frame_interval = timestamp - prev;
+            }
+        }
+
+        let is_black = !luma.is_empty() && mean(luma) / 255.0 < self.thresholds.black_luma;
+        let is_frozen = !is_black
+            && !luma.is_empty()
+            && luma.len() == self.prev_luma.len()
+            && mean_abs_diff(luma, &self.prev_luma) / 255.0 < self.thresholds.freeze_difference;
+
+        track(
+            &mut self.black,
+            is_black.then_some((timestamp, 1)),
+            timestamp,
+            self.thresholds.min_black_frames,
+            &mut self.report.black_segments,
+        );
+        // A frozen frame repeats the previous one, so the run starts there
+        track(
+            &mut self.freeze,
+            is_frozen.then_some((self.prev_time.unwrap_or(timestamp), 2)),
+            timestamp,
+            self.thresholds.min_freeze_frames,
+            &mut self.report.freeze_segments,
+        );
+
+        self.prev_time = Some(timestamp);
+        self.prev_luma.clear();
+        self.prev_luma.extend_from_slice(luma);
+    }
+
+    /// Close any open runs at the end of the stream and return the report
+    pub fn finish(mut self) -> VideoQualityReport {
+        if let Some(last) = self.prev_time {
+            let end = last + self.frame_interval;
+            let min_black = self.thresholds.min_black_frames;
+            let min_freeze = self.thresholds.min_freeze_frames;
+            track(
+                &mut self.black,
+                None,
+                end,
+                min_black,
+                &mut self.report.black_segments,
+            );
+            track(
+                &mut self.freeze,
+                None,
+                end,
+                min_freeze,
+                &mut self.report.freeze_segments,
+            );
+        }
+        self.report
+    }
+}
+
+/// Extend `run` when the current frame matches (`matched` holds the start
+/// time and frame count for a new run), otherwise close it at `now` and
+/// record it if it is long enough.
+fn track(
+    run: &mut Option<Run>,
+    matched: Option<(f64, u32)>,
+    now: f64,
+    min_frames: u32,
+    out: &mut Vec<TimeRange>,
+) {
+    match (matched, run.as_mut()) {
+        (Some(_), Some(r)) => r.frames += 1,
+        (Some((start, frames)), None) => *run = Some(Run { start, frames }),
+        (None, Some(_)) => {
+            let r = run.take().unwrap();
+            if r.frames >= min_frames.max(1) {
+                out.push(TimeRange {
+                    start: r.start,
+                    end: now,
+                });
+            }
+        }
+        (None, None) => {}
+    }
+}
+
+fn mean(luma: &[u8]) -> f64 {
+    luma.iter().map(|&v| v as u64).sum::<u64>() as f64 / luma.len() as f64
+}
+
+fn mean_abs_diff(a: &[u8], b: &[u8]) -> f64 {
+    a.iter()
+        .zip(b)
+        .map(|(x, y)| x.abs_diff(*y) as u64)
+        .sum::<u64>() as f64
+        / a.len() as f64
+}
@@ -0,0 +1,68 @@
+//! Scene cut detection
+
+use serde::{Deserialize, Serialize};
+
+/// A detected scene cut
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SceneCut {
+    /// Time of the first frame of the new scene, in seconds
+    pub timestamp: f64,
+    /// Scene change score (0.0 to 1.0)
+    pub score: f64,
+}
+
+/// Scores consecutive frames the way FFmpeg's `select` filter computes its
+/// `scene` value and records the frames scoring above a threshold as cuts.
+///
+/// The score is the smaller of the mean absolute frame difference and its
+/// change since the previous frame, so steady fast motion does not register
+/// but a sudden jump does.
+pub(crate) struct SceneDetector {
+    threshold: f64,
+    prev_luma: Vec<u8>,
+    prev_mafd: f64,
+    cuts: Vec<SceneCut>,
+}
+
+impl SceneDetector {
+    /// Create a detector reporting cuts scoring above `threshold` (0.0 to 1.0)
+    pub fn new(threshold: f64) -> Self {
+        Self {
+            threshold,
+            prev_luma: Vec::new(),
+            prev_mafd: 0.0,
+            cuts: Vec::new(),
+        }
+    }
+
+    /// Feed the luma plane of the next frame
+    ///
+    /// Every frame must be scaled to the same size; a size change resets the
+    /// comparison instead of reporting a cut.
+    pub fn push(&mut self, timestamp: f64, luma: &[u8]) {
+        if !luma.is_empty() && luma.len() == self.prev_luma.len() {
+            let sad: u64 = luma
+                .iter()
+                .zip(&self.prev_luma)
+                .map(|(a, b)| a.abs_diff(*b) as u64)
+                .sum();
+            // Mean absolute frame difference, 0-255
+            let mafd = sad as f64 / luma.len() as f64;
+            let diff = (mafd - self.prev_mafd).abs();
+            self.prev_mafd = mafd;
+
+            let score = (mafd.min(diff) / 100.0).clamp(0.0, 1.0);
+            if score > self.threshold {
+                self.cuts.push(SceneCut { timestamp, score });
+            }
+        }
+        self.prev_luma.clear();
+        self.prev_luma.extend_from_slice(luma);
+    }
+
+    /// Return the cuts found so far, in stream order
+    pub fn finish(self) -> Vec<SceneCut> {
+        self.cuts
+    }
+}
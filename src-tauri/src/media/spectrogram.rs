@@ -0,0 +1,108 @@
+//! Spectrogram rendering
+
+use rustfft::{num_complex::Complex, FftPlanner};
+
+/// Lowest frequency shown with a logarithmic frequency axis, in Hz
+const LOG_MIN_FREQUENCY: f64 = 20.0;
+
+/// Magnitudes this far below full scale (dB) are drawn black
+const DYNAMIC_RANGE_DB: f32 = 100.0;
+
+/// Color ramp from silent to loud (black, purple, red, orange, yellow, white)
+const COLOR_STOPS: [[f32; 3]; 6] = [
+    [0.0, 0.0, 0.0],
+    [80.0, 18.0, 123.0],
+    [182.0, 54.0, 121.0],
+    [251.0, 136.0, 97.0],
+    [252.0, 253.0, 191.0],
+    [255.0, 255.0, 255.0],
+];
+
+/// Render mono `samples` as a `width`x`height` spectrogram, returned as
+/// tightly packed RGB rows
+///
+/// Time runs left to right and frequency bottom to top, up to `max_freq`
+/// (Hz), on a linear or logarithmic axis.  Each column is a Hann-windowed
+/// FFT centred on its share of the samples; each row shows the loudest bin
+/// it covers, in dB relative to full scale.
+pub(crate) fn render_spectrogram(
+    samples: &[f32],
+    sample_rate: u32,
+    width: u32,
+    height: u32,
+    max_freq: f64,
+    log_frequency: bool,
+) -> Vec<u8> {
+    let (width, height) = (width as usize, height as usize);
+    // Enough bins for every row on a linear axis
+    let fft_size = (height * 2).next_power_of_two().clamp(1024, 8192);
+    let fft = FftPlanner::<f32>::new().plan_fft_forward(fft_size);
+    let window: Vec<f32> = (0..fft_size)
+        .map(|i| {
+            let phase = 2.0 * std::f32::consts::PI * i as f32 / fft_size as f32;
+            0.5 - 0.5 * phase.cos()
+        })
+        .collect();
+    // A full-scale sine peaks at a quarter of the window length (Hann gain 0.5)
+    let full_scale = fft_size as f32 / 4.0;
+
+    let nyquist = sample_rate as f64 / 2.0;
+    let max_freq = max_freq.clamp(1.0, nyquist);
+    let bin_hz = sample_rate as f64 / fft_size as f64;
+    // Bins each row covers, top row first
+    let rows: Vec<(usize, usize)> = (0..height)
+        .map(|y| {
+            let frequency = |edge: f64| {
+                let position = 1.0 - edge / height as f64;
+                if log_frequency {
+                    let min = LOG_MIN_FREQUENCY.min(max_freq / 2.0);
+                    min * (max_freq / min).powf(position)
+                } else {
+                    max_freq * position
+                }
+            };
+            let low = (frequency(y as f64 + 1.0) / bin_hz).floor() as usize;
+            let high = (frequency(y as f64) / bin_hz).ceil() as usize;
+            let last = fft_size / 2;
+            (low.min(last), high.clamp(low + 1, last + 1))
+        })
+        .collect();
+
+    let mut pixels = vec![0u8; width * height * 3];
+    let mut buffer = vec![Complex::new(0.0f32, 0.0); fft_size];
+    let mut levels = vec![0.0f32; fft_size / 2 + 1];
+    for x in 0..width {
+        // Zero outside the audio
+        let center = (x as f64 + 0.5) / width as f64 * samples.len() as f64;
+        let start = center as isize - fft_size as isize / 2;
+        for (i, value) in buffer.iter_mut().enumerate() {
+            let sample = usize::try_from(start + i as isize)
+                .ok()
+                .and_then(|index| samples.get(index))
+                .copied()
+                .unwrap_or(0.0);
+            *value = Complex::new(sample * window[i], 0.0);
+        }
+        fft.process(&mut buffer);
+
+        for (level, bin) in levels.iter_mut().zip(&buffer) {
+            let decibels = 20.0 * (bin.norm() / full_scale).max(1e-10).log10();
+            *level = (1.0 + decibels / DYNAMIC_RANGE_DB).clamp(0.0, 1.0);
+        }
+        for (y, &(low, high)) in rows.iter().enumerate() {
+            let level = levels[low..high].iter().copied().fold(0.0, f32::max);
+            let offset = (y * width + x) * 3;
+            pixels[offset..offset + 3].copy_from_slice(&ramp(level));
+        }
+    }
+    pixels
+}
+
+/// Color for a level from 0.0 (silent) to 1.0 (full scale)
+fn ramp(level: f32) -> [u8; 3] {
+    let position = level * (COLOR_STOPS.len() - 1) as f32;
+    let index = (position as usize).min(COLOR_STOPS.len() - 2);
+    let t = position - index as f32;
+    let (from, to) = (COLOR_STOPS[index], COLOR_STOPS[index + 1]);
+    [0, 1, 2].map(|c| (from[c] + (to[c] - from[c]) * t).round() as u8)
+}
@@ -0,0 +1,80 @@
+//! Subtitle cue handling and SRT output
+
+use std::fmt::Write;
+
+/// A single subtitle cue
+#[derive(Debug, Clone)]
+pub(crate) struct SubtitleCue {
+    /// Start time in seconds
+    pub start: f64,
+    /// End time in seconds
+    pub end: f64,
+    /// Plain text, lines separated by `\n`
+    pub text: String,
+}
+
+/// Extract the plain text of an ASS dialogue line as produced by FFmpeg's
+/// subtitle decoders
+///
+/// Decoders emit `ReadOrder,Layer,Style,Name,MarginL,MarginR,MarginV,Effect,Text`;
+/// older ones a full `Dialogue:` line with timestamps.  Override tags such as
+/// `{\i1}` are dropped and `\N` line breaks converted.
+pub(crate) fn ass_to_text(line: &str) -> String {
+    let text = match line.strip_prefix("Dialogue:") {
+        Some(rest) => rest.splitn(10, ',').nth(9),
+        None => line.splitn(9, ',').nth(8),
+    }
+    .unwrap_or(line);
+
+    let mut out = String::with_capacity(text.len());
+    let mut in_tag = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => in_tag = true,
+            '}' if in_tag => in_tag = false,
+            _ if in_tag => {}
+            '\\' => match chars.peek() {
+                Some('N') | Some('n') => {
+                    chars.next();
+                    out.push('\n');
+                }
+                Some('h') => {
+                    chars.next();
+                    out.push(' ');
+                }
+                _ => out.push(c),
+            },
+            _ => out.push(c),
+        }
+    }
+    out.trim().to_string()
+}
+
+/// Format seconds as an SRT timestamp (`HH:MM:SS,mmm`)
+fn srt_timestamp(seconds: f64) -> String {
+    let total_ms = (seconds.max(0.0) * 1000.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02},{:03}",
+        total_ms / 3_600_000,
+        total_ms / 60_000 % 60,
+        total_ms / 1000 % 60,
+        total_ms % 1000
+    )
+}
+
+/// Render cues as an SRT document, numbered from 1 in start order
+pub(crate) fn to_srt(cues: &[SubtitleCue]) -> String {
+    let mut srt = String::new();
+    for (i, cue) in cues.iter().enumerate() {
+        let _ = write!(
+            srt,
+            "{}\n{} --> {}\n{}\n\n",
+            i + 1,
+            srt_timestamp(cue.start),
+            srt_timestamp(cue.end),
+            cue.text
+        );
+    }
+    srt
+}
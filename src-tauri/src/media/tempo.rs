@@ -0,0 +1,255 @@
+//! Tempo (BPM) and beat detection
+
+use rustfft::{num_complex::Complex, FftPlanner};
+use serde::{Deserialize, Serialize};
+
+/// Result of [`MediaAnalyzer::analyze_tempo`](super::MediaAnalyzer::analyze_tempo)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TempoAnalysis {
+    /// Dominant tempo in beats per minute (60 to 200)
+    pub bpm: f64,
+    /// How clearly the audio pulses at that tempo (0.0 to 1.0)
+    pub confidence: f64,
+    /// Beat times in seconds, up to the requested limit
+    pub beats: Vec<f64>,
+}
+
+/// Onset envelope sample rate, in Hz
+const ENVELOPE_RATE: f64 = 200.0;
+
+/// Spectrum frame length for the onset envelope, in samples
+const FRAME_SIZE: usize = 1024;
+
+/// Smoothing kernel applied to the onset envelope
+const SMOOTHING: [f64; 5] = [1.0 / 9.0, 2.0 / 9.0, 3.0 / 9.0, 2.0 / 9.0, 1.0 / 9.0];
+
+/// Tempo search range, in BPM
+const MIN_BPM: f64 = 60.0;
+const MAX_BPM: f64 = 200.0;
+
+/// Lags scoring at least this share of the best one count as candidates; the
+/// shortest wins, so a pulse is not reported at half its tempo
+const CANDIDATE_RATIO: f64 = 0.9;
+
+/// Estimate the tempo of mono `samples` and place beats on it, up to
+/// `beat_window` seconds
+///
+/// The onset envelope is the spectral flux (the rise in log magnitude across
+/// all bins) at [`ENVELOPE_RATE`], less its local average.  Its
+/// autocorrelation peaks at the beat period; the beat grid is then aligned
+/// to the phase with the strongest onsets.  Returns `None` if there is too
+/// little audio to cover a few beats at the slowest tempo.
+pub(crate) fn analyze_tempo(
+    samples: &[f32],
+    sample_rate: u32,
+    beat_window: f64,
+) -> Option<TempoAnalysis> {
+    let hop = (sample_rate as f64 / ENVELOPE_RATE).round().max(1.0) as usize;
+    let rate = sample_rate as f64 / hop as f64;
+    let envelope = onset_envelope(samples, hop);
+
+    let min_lag = (60.0 * rate / MAX_BPM).floor() as usize;
+    let max_lag = (60.0 * rate / MIN_BPM).ceil() as usize;
+    if min_lag < 2 || envelope.len() < max_lag * 4 {
+        return None;
+    }
+
+    // Autocorrelation over the tempo range, normalised by the overlap length
+    let energy = envelope.iter().map(|v| v * v).sum::<f64>() / envelope.len() as f64;
+    if energy <= 0.0 {
+        return None;
+    }
+    let correlation: Vec<f64> = (min_lag - 1..=max_lag + 1)
+        .map(|lag| {
+            let overlap = envelope.len() - lag;
+            let sum: f64 = envelope[..overlap]
+                .iter()
+                .zip(&envelope[lag..])
+                .map(|(a, b)| a * b)
+                .sum();
+            sum / overlap as f64
+        })
+        .collect();
+    // Index i of `correlation` is lag min_lag - 1 + i; the ends are only
+    // neighbours for interpolation
+    let best = (1..correlation.len() - 1)
+        .map(|i| correlation[i])
+        .fold(f64::MIN, f64::max);
+    let peak = (1..correlation.len() - 1)
+        .find(|&i| {
+            let value = correlation[i];
+            value >= best * CANDIDATE_RATIO
+                && value >= correlation[i - 1]
+                && value >= correlation[i + 1]
+        })
+        .unwrap_or(1);
+
+    // Parabolic interpolation between neighbouring lags
+    let (before, at, after) = (
+        correlation[peak - 1],
+        correlation[peak],
+        correlation[peak + 1],
+    );
+    let curvature = before - 2.0 * at + after;
+    let shift = if curvature < 0.0 {
+        (0.5 * (before - after) / curvature).clamp(-0.5, 0.5)
+    } else {
+        0.0
+    };
+    let period = (min_lag - 1 + peak) as f64 + shift;
+    let bpm = (60.0 * rate / period).clamp(MIN_BPM, MAX_BPM);
+    let confidence = (at / energy).clamp(0.0, 1.0);
+
+    Some(TempoAnalysis {
+        bpm,
+        confidence,
+        beats: beat_grid(&envelope, period, rate, beat_window),
+    })
+}
+
+/// Half-wave rectified spectral flux with its local average removed and
+/// lightly smoothed, one value per `hop` samples
+fn onset_envelope(samples: &[f32], hop: usize) -> Vec<f64> {
+    let fft = FftPlanner::<f32>::new().plan_fft_forward(FRAME_SIZE);
+    let window: Vec<f32> = (0..FRAME_SIZE)
+        .map(|i| {
+            let phase = 2.0 * std::f32::consts::PI * i as f32 / FRAME_SIZE as f32;
+            0.5 - 0.5 * phase.cos()
+        })
+        .collect();
+
+    let frames = samples.len() / hop;
+    let mut flux = Vec::with_capacity(frames);
+    let mut buffer = vec![Complex::new(0.0f32, 0.0); FRAME_SIZE];
+    let mut previous = vec![0.0f32; FRAME_SIZE / 2];
+    let mut current = vec![0.0f32; FRAME_SIZE / 2];
+    for frame in 0..frames {
+        // Frames are centred on their time
+        let start = (frame * hop) as isize - FRAME_SIZE as isize / 2;
+        for (i, value) in buffer.iter_mut().enumerate() {
+            let sample = usize::try_from(start + i as isize)
+                .ok()
+                .and_then(|index| samples.get(index))
+                .copied()
+                .unwrap_or(0.0);
+            *value = Complex::new(sample * window[i], 0.0);
+        }
+        fft.process(&mut buffer);
+        for (magnitude, bin) in current.iter_mut().zip(&buffer) {
+            *magnitude = (1.0 + 100.0 * bin.norm()).ln();
+        }
+        let rise: f32 = current
+            .iter()
+            .zip(&previous)
+            .map(|(now, before)| (now - before).max(0.0))
+            .sum();
+        flux.push(if frame == 0 { 0.0 } else { rise as f64 });
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    // Subtract a moving average (about a quarter second either side) so
+    // only sharp rises remain
+    let radius = (ENVELOPE_RATE / 4.0) as usize;
+    let mut prefix = vec![0.0; flux.len() + 1];
+    for (i, value) in flux.iter().enumerate() {
+        prefix[i + 1] = prefix[i] + value;
+    }
+    let onsets: Vec<f64> = (0..flux.len())
+        .map(|i| {
+            let (low, high) = (i.saturating_sub(radius), (i + radius + 1).min(flux.len()));
+            let mean = (prefix[high] - prefix[low]) / (high - low) as f64;
+            (flux[i] - mean).max(0.0)
+        })
+        .collect();
+
+    // Spread each onset over a few samples, so beat periods that fall
+    // between whole lags still line up in the autocorrelation
+    (0..onsets.len())
+        .map(|i| {
+            SMOOTHING
+                .iter()
+                .enumerate()
+                .filter_map(|(k, weight)| {
+                    let index = (i + k).checked_sub(SMOOTHING.len() / 2)?;
+                    onsets.get(index).map(|value| value * weight)
+                })
+                .sum()
+        })
+        .collect()
+}
+
+/// Beat times (seconds) every `period` envelope samples up to `beat_window`
+/// seconds, starting at the phase whose beats carry the most onset strength
+fn beat_grid(envelope: &[f64], period: f64, rate: f64, beat_window: f64) -> Vec<f64> {
+    let strength = |phase: f64| {
+        let mut total = 0.0;
+        let mut position = phase;
+        while (position.round() as usize) < envelope.len() {
+            total += envelope[position.round() as usize];
+            position += period;
+        }
+        total
+    };
+    let steps = period.ceil() as usize;
+    let phase = (0..steps)
+        .map(|step| step as f64)
+        .max_by(|a, b| strength(*a).total_cmp(&strength(*b)))
+        .unwrap_or(0.0);
+
+    let mut beats = Vec::new();
+    let mut position = phase;
+    while position < envelope.len() as f64 && position / rate <= beat_window {
+        beats.push(position / rate);
+        position += period;
+    }
+    beats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `seconds` of 10 ms, 2 kHz clicks at `bpm`
+    fn click_track(bpm: f64, seconds: f64, sample_rate: u32) -> Vec<f32> {
+        let mut samples = vec![0.0f32; (seconds * sample_rate as f64) as usize];
+        let interval = 60.0 / bpm * sample_rate as f64;
+        let click = (0.01 * sample_rate as f64) as usize;
+        let mut start = 0.0;
+        while (start as usize) < samples.len() {
+            for (i, sample) in samples[start as usize..].iter_mut().take(click).enumerate() {
+                let t = i as f64 / sample_rate as f64;
+                let decay = 1.0 - i as f64 / click as f64;
+                *sample = ((2.0 * std::f64::consts::PI * 2000.0 * t).sin() * decay) as f32;
+            }
+            start += interval;
+        }
+        samples
+    }
+
+    #[test]
+    fn detects_120_bpm_click_track() {
+        let samples = click_track(120.0, 20.0, 44100);
+        let analysis = analyze_tempo(&samples, 44100, 10.0).unwrap();
+        assert!(
+            (analysis.bpm - 120.0).abs() <= 1.0,
+            "detected {} BPM",
+            analysis.bpm
+        );
+        // Beats land on the clicks, half a second apart
+        assert!(analysis.beats.len() >= 19);
+        for pair in analysis.beats.windows(2) {
+            assert!((pair[1] - pair[0] - 0.5).abs() < 0.02);
+        }
+    }
+
+    #[test]
+    fn too_short_for_a_tempo() {
+        assert!(analyze_tempo(&click_track(120.0, 1.0, 44100), 44100, 10.0).is_none());
+    }
+
+    #[test]
+    fn silence_has_no_tempo() {
+        assert!(analyze_tempo(&vec![0.0; 44100 * 10], 44100, 10.0).is_none());
+    }
+}
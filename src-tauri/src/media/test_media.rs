@@ -0,0 +1,22 @@
+//! Test media generated with the ffmpeg CLI
+
+use std::path::Path;
+use std::process::Command;
+
+/// Make `output` with the ffmpeg CLI from `args`
+///
+/// Tests using this are `#[ignore]`d, as the CLI may be missing; run them
+/// with `cargo test -- --ignored`.
+pub(crate) fn ffmpeg(args: &[&str], output: &Path) {
+    let status = Command::new("ffmpeg")
+        .args(["-y", "-v", "error"])
+        .args(args)
+        .arg(output)
+        .status()
+        .expect("the ffmpeg CLI is needed to generate test media");
+    assert!(
+        status.success(),
+        "ffmpeg failed to make {}",
+        output.display()
+    );
+}
@@ -0,0 +1,93 @@
+//! Frame timing analysis for variable frame rate detection
+
+use serde::{Deserialize, Serialize};
+
+/// Result of [`MediaAnalyzer::analyze_frame_timing`](super::MediaAnalyzer::analyze_frame_timing)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FrameTiming {
+    /// Exact number of frames in the stream
+    pub frame_count: u64,
+    /// Shortest time between consecutive frames, in seconds
+    pub min_interval: f64,
+    /// Median time between consecutive frames, in seconds
+    pub median_interval: f64,
+    /// Longest time between consecutive frames, in seconds
+    pub max_interval: f64,
+    /// Frames per second over the whole stream
+    pub average_frame_rate: f64,
+    /// Whether frame intervals vary more than timestamp rounding explains
+    pub is_vfr: bool,
+}
+
+/// Intervals may differ from the median by this fraction before the stream
+/// counts as variable frame rate (29.97 fps in a 1/1000 time base alternates
+/// between 33 and 34 ms)
+const VFR_TOLERANCE: f64 = 0.1;
+
+/// Share of intervals at each end ignored when judging VFR, so a stray
+/// timestamp (e.g. around an edit) doesn't make a constant rate stream VFR
+const OUTLIER_FRACTION: f64 = 0.01;
+
+/// Collects frame timestamps and summarizes their spacing
+pub(crate) struct FrameTimingCollector {
+    timestamps: Vec<f64>,
+    /// Duration of one time base tick, in seconds
+    tick: f64,
+}
+
+impl FrameTimingCollector {
+    /// Create a collector for timestamps in a time base of `tick` seconds
+    pub fn new(tick: f64) -> Self {
+        Self {
+            timestamps: Vec::new(),
+            tick,
+        }
+    }
+
+    /// Record the presentation time (seconds) of a frame, in any order
+    pub fn push(&mut self, timestamp: f64) {
+        self.timestamps.push(timestamp);
+    }
+
+    /// Summarize the frames seen
+    pub fn finish(mut self) -> FrameTiming {
+        // Frames arrive in decode order when B-frames are used
+        self.timestamps.sort_by(f64::total_cmp);
+        let frame_count = self.timestamps.len() as u64;
+        let mut intervals: Vec<f64> = self
+            .timestamps
+            .windows(2)
+            .map(|w| w[1] - w[0])
+            .filter(|&d| d > 0.0)
+            .collect();
+        if intervals.is_empty() {
+            return FrameTiming {
+                frame_count,
+                min_interval: 0.0,
+                median_interval: 0.0,
+                max_interval: 0.0,
+                average_frame_rate: 0.0,
+                is_vfr: false,
+            };
+        }
+        intervals.sort_by(f64::total_cmp);
+
+        let last = intervals.len() - 1;
+        let median = intervals[last / 2];
+        let outliers = (intervals.len() as f64 * OUTLIER_FRACTION) as usize;
+        let (low, high) = (intervals[outliers], intervals[last - outliers]);
+        // Rounding to the time base can move a timestamp by a tick either way
+        let tolerance = (median * VFR_TOLERANCE).max(2.0 * self.tick);
+
+        let span = self.timestamps[self.timestamps.len() - 1] - self.timestamps[0];
+        FrameTiming {
+            frame_count,
+            min_interval: intervals[0],
+            median_interval: median,
+            max_interval: intervals[last],
+            average_frame_rate: (frame_count - 1) as f64 / span,
+            is_vfr: high - low > tolerance,
+        }
+    }
+}
@@ -1,6 +1,11 @@
 //! Audio waveform extraction and data structures
 
+use super::fingerprint::Fnv1a;
+use super::{file_stamp, source_key};
+use crate::Result;
 use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
 
 /// Waveform data for audio visualization
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -69,3 +74,134 @@ impl WaveformData {
         }
     }
 }
+
+/// On-disk cache of computed waveforms
+///
+/// Entries live in the user cache directory, one compact JSON file per
+/// (source path, bucket count, stream) combination.  Each entry records the
+/// source file's size and modification time, and is discarded when either
+/// no longer matches.
+pub struct WaveformCache;
+
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    source: String,
+    size: u64,
+    modified_ms: u64,
+    waveform: WaveformData,
+}
+
+impl WaveformCache {
+    /// Directory holding the cache files
+    fn dir() -> Option<PathBuf> {
+        dirs::cache_dir().map(|d| d.join("io.waldiez.player").join("waveforms"))
+    }
+
+    /// Cache file for a source, bucket count, and stream
+    fn entry_path(
+        source: &str,
+        num_samples: usize,
+        stream_index: Option<usize>,
+    ) -> Option<PathBuf> {
+        let mut hasher = Fnv1a::new();
+        hasher.write(source.as_bytes());
+        hasher.write(&(num_samples as u64).to_le_bytes());
+        // Offset so `None` and `Some(0)` differ
+        let stream = stream_index.map_or(0, |i| i as u64 + 1);
+        hasher.write(&stream.to_le_bytes());
+        Self::dir().map(|d| d.join(format!("{:016x}.json", hasher.finish())))
+    }
+
+    /// Look up a cached waveform, removing the entry if the source changed
+    pub fn load(
+        path: &Path,
+        num_samples: usize,
+        stream_index: Option<usize>,
+    ) -> Option<WaveformData> {
+        let source = source_key(path);
+        let entry_path = Self::entry_path(&source, num_samples, stream_index)?;
+        let content = fs::read(&entry_path).ok()?;
+        let entry: CacheEntry = serde_json::from_slice(&content).ok()?;
+
+        let (size, modified_ms) = file_stamp(path)?;
+        if entry.source != source || entry.size != size || entry.modified_ms != modified_ms {
+            let _ = fs::remove_file(&entry_path);
+            return None;
+        }
+        Some(entry.waveform)
+    }
+
+    /// Store a computed waveform
+    pub fn store(
+        path: &Path,
+        num_samples: usize,
+        stream_index: Option<usize>,
+        waveform: &WaveformData,
+    ) -> Result<()> {
+        let source = source_key(path);
+        let (Some(entry_path), Some((size, modified_ms))) = (
+            Self::entry_path(&source, num_samples, stream_index),
+            file_stamp(path),
+        ) else {
+            return Ok(());
+        };
+
+        if let Some(parent) = entry_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let entry = CacheEntry {
+            source,
+            size,
+            modified_ms,
+            waveform: waveform.clone(),
+        };
+        fs::write(&entry_path, serde_json::to_vec(&entry)?)?;
+        Ok(())
+    }
+
+    /// Remove every cached waveform
+    pub fn clear() -> Result<()> {
+        match Self::dir() {
+            Some(dir) if dir.exists() => Ok(fs::remove_dir_all(dir)?),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_media::ffmpeg;
+    use super::super::MediaAnalyzer;
+
+    #[test]
+    #[ignore = "needs the ffmpeg CLI"]
+    fn lossy_and_lossless_copies_look_alike() {
+        let dir = tempfile::tempdir().unwrap();
+        let waveform = |name: &str| {
+            let path = dir.path().join(name);
+            ffmpeg(
+                &["-f", "lavfi", "-i", "sine=frequency=440:duration=3"],
+                &path,
+            );
+            MediaAnalyzer::new(&path)
+                .unwrap()
+                .extract_waveform(100, None)
+                .unwrap()
+        };
+        let wav = waveform("sine.wav");
+        let mp3 = waveform("sine.mp3");
+
+        assert_eq!(wav.sample_count, mp3.sample_count);
+        assert!((wav.duration - mp3.duration).abs() < 0.1);
+        // The edges hold the encoder's padding
+        let inner = 1..wav.sample_count - 1;
+        let close = |a: &[f32], b: &[f32]| {
+            a[inner.clone()]
+                .iter()
+                .zip(&b[inner.clone()])
+                .all(|(x, y)| (x - y).abs() < 0.02)
+        };
+        assert!(close(&wav.peaks, &mp3.peaks), "{wav:?}\n{mp3:?}");
+        assert!(close(&wav.rms, &mp3.rms), "{wav:?}\n{mp3:?}");
+    }
+}
@@ -1,6 +1,7 @@
 //! Project manager for creating, loading, and saving projects
 
 use super::{AssetLibrary, Composition, Project, ProjectSettings};
+use crate::media::MediaAnalyzer;
 use crate::{Error, Result};
 use chrono::Utc;
 use std::fs;
@@ -80,6 +81,37 @@ impl ProjectManager {
         Ok(())
     }
 
+    /// Compute fingerprints for assets that do not have one yet
+    ///
+    /// Assets whose files are missing or unreadable are skipped.
+    pub fn fill_fingerprints(project: &mut Project) {
+        let assets = &mut project.assets;
+        let pending = assets
+            .images
+            .iter_mut()
+            .map(|a| (&a.path, &mut a.fingerprint))
+            .chain(
+                assets
+                    .audio
+                    .iter_mut()
+                    .map(|a| (&a.path, &mut a.fingerprint)),
+            )
+            .chain(
+                assets
+                    .video
+                    .iter_mut()
+                    .map(|a| (&a.path, &mut a.fingerprint)),
+            )
+            .filter(|(_, fingerprint)| fingerprint.is_none());
+
+        for (path, fingerprint) in pending {
+            match MediaAnalyzer::new(Path::new(path)).and_then(|a| a.fingerprint()) {
+                Ok(value) => *fingerprint = Some(value),
+                Err(e) => log::debug!("Could not fingerprint {}: {}", path, e),
+            }
+        }
+    }
+
     /// Export project to a specific format
     pub fn export(project: &Project, path: &Path, _format: &str) -> Result<()> {
         // For now, just save as JSON regardless of format
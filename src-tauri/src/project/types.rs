@@ -92,6 +92,10 @@ pub struct ImageAsset {
     pub height: u32,
     pub format: String,
     pub size: u64,
+    /// Content fingerprint (see `get_media_fingerprint`), used to verify
+    /// candidates when relinking a moved file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -105,6 +109,10 @@ pub struct AudioAsset {
     pub channels: u32,
     pub format: String,
     pub size: u64,
+    /// Content fingerprint (see `get_media_fingerprint`), used to verify
+    /// candidates when relinking a moved file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -120,6 +128,14 @@ pub struct VideoAsset {
     pub codec: String,
     pub format: String,
     pub size: u64,
+    /// Content fingerprint (see `get_media_fingerprint`), used to verify
+    /// candidates when relinking a moved file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<String>,
+    /// Low resolution copy for the preview (see `generate_proxy`); renders
+    /// still read `path`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy_path: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
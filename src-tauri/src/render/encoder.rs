@@ -0,0 +1,426 @@
+//! Video encoder selection, including hardware encoders
+
+use super::{HardwareAcceleration, RenderQuality, RenderSettings};
+use crate::{Error, Result};
+use std::future::Future;
+use std::process::Stdio;
+use tokio::process::Command;
+
+/// DRM render node used for VAAPI encoding
+const VAAPI_DEVICE: &str = "/dev/dri/renderD128";
+
+/// Highest libx264 constant rate factor
+const MAX_CRF: u8 = 51;
+
+/// libx264 speed presets, fastest first
+const X264_PRESETS: [&str; 10] = [
+    "ultrafast",
+    "superfast",
+    "veryfast",
+    "faster",
+    "fast",
+    "medium",
+    "slow",
+    "slower",
+    "veryslow",
+    "placebo",
+];
+
+/// A video encoder the render pipeline can drive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum VideoEncoder {
+    Libx264,
+    LibvpxVp9,
+    Nvenc,
+    VideoToolbox,
+    Vaapi,
+    Qsv,
+    Gif,
+    LibwebpAnim,
+    ProresKs,
+    Png,
+    Mjpeg,
+    Exr,
+}
+
+impl VideoEncoder {
+    /// FFmpeg encoder name
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Libx264 => "libx264",
+            Self::LibvpxVp9 => "libvpx-vp9",
+            Self::Nvenc => "h264_nvenc",
+            Self::VideoToolbox => "h264_videotoolbox",
+            Self::Vaapi => "h264_vaapi",
+            Self::Qsv => "h264_qsv",
+            Self::Gif => "gif",
+            Self::LibwebpAnim => "libwebp_anim",
+            Self::ProresKs => "prores_ks",
+            Self::Png => "png",
+            Self::Mjpeg => "mjpeg",
+            Self::Exr => "exr",
+        }
+    }
+
+    /// File extension of the frames, for encoders that write an image
+    /// sequence rather than a video
+    pub fn image_extension(self) -> Option<&'static str> {
+        match self {
+            Self::Png => Some("png"),
+            Self::Mjpeg => Some("jpg"),
+            Self::Exr => Some("exr"),
+            _ => None,
+        }
+    }
+
+    /// Options that must precede the inputs
+    pub fn global_args(self) -> Vec<String> {
+        match self {
+            Self::Vaapi => vec!["-vaapi_device".into(), VAAPI_DEVICE.into()],
+            _ => Vec::new(),
+        }
+    }
+
+    /// Filters appended to the graph to move frames into GPU memory
+    pub fn upload_filter(self) -> Option<&'static str> {
+        match self {
+            Self::Vaapi => Some("format=nv12,hwupload"),
+            _ => None,
+        }
+    }
+
+    /// Filters that end the graph, converting frames to what the encoder
+    /// takes
+    ///
+    /// GIF gets a palette generated from the whole clip and applied with
+    /// dithering, which looks far better than the default 256 colour
+    /// palette.
+    pub fn output_filter(self, settings: &RenderSettings) -> String {
+        match self {
+            Self::Gif => {
+                let colors = settings.max_colors.unwrap_or(256).clamp(2, 256);
+                format!(
+                    "split[palette_in][gif_in];\
+                     [palette_in]palettegen=max_colors={colors}:stats_mode=diff[palette];\
+                     [gif_in][palette]paletteuse=dither=sierra2_4a"
+                )
+            }
+            Self::LibwebpAnim => "format=yuva420p".to_string(),
+            Self::ProresKs => "format=yuv422p10le".to_string(),
+            Self::Png => "format=rgb24".to_string(),
+            // Full range YUV, as JPEG expects
+            Self::Mjpeg => "format=yuvj444p".to_string(),
+            Self::Exr => "format=gbrpf32le".to_string(),
+            _ => match self.upload_filter() {
+                Some(upload) => format!("format=yuv420p,{upload}"),
+                None => "format=yuv420p".to_string(),
+            },
+        }
+    }
+
+    /// Audio encoder for the same container, or `None` for formats without
+    /// sound
+    pub fn audio_codec(self) -> Option<&'static str> {
+        match self {
+            Self::LibvpxVp9 => Some("libopus"),
+            Self::Gif | Self::LibwebpAnim | Self::Png | Self::Mjpeg | Self::Exr => None,
+            Self::ProresKs => Some("pcm_s24le"),
+            _ => Some("aac"),
+        }
+    }
+
+    /// Whether the encoder supports `-pass 1` / `-pass 2`
+    pub fn supports_two_pass(self) -> bool {
+        matches!(self, Self::Libx264 | Self::LibvpxVp9)
+    }
+
+    /// Codec and rate control options: the target bitrate if set, otherwise
+    /// constant quality for the quality level
+    pub fn output_args(self, settings: &RenderSettings) -> Vec<String> {
+        let mut args: Vec<String> = vec!["-c:v".into(), self.name().into()];
+        match self {
+            // Loop forever; the palette already sets the quality
+            Self::Gif => {
+                args.extend(["-loop".into(), "0".into()]);
+                return args;
+            }
+            Self::LibwebpAnim => {
+                args.extend(["-loop".into(), "0".into()]);
+                let quality = match settings.quality {
+                    RenderQuality::Lossless => {
+                        args.extend(["-lossless".into(), "1".into()]);
+                        return args;
+                    }
+                    RenderQuality::High => 90,
+                    RenderQuality::Medium => 75,
+                    RenderQuality::Low => 50,
+                };
+                args.extend(["-quality".into(), quality.to_string()]);
+                return args;
+            }
+            // ProRes has fixed bitrates per profile: HQ, standard and LT
+            Self::ProresKs => {
+                let profile = match settings.quality {
+                    RenderQuality::Lossless | RenderQuality::High => 3,
+                    RenderQuality::Medium => 2,
+                    RenderQuality::Low => 1,
+                };
+                args.extend(["-profile:v".into(), profile.to_string()]);
+                return args;
+            }
+            // PNG and EXR (with zip compression) are lossless
+            Self::Png => return args,
+            Self::Exr => {
+                args.extend(["-compression".into(), "zip16".into()]);
+                return args;
+            }
+            // 1 (best) to 31
+            Self::Mjpeg => {
+                let scale = match settings.quality {
+                    RenderQuality::Lossless => 1,
+                    RenderQuality::High => 2,
+                    RenderQuality::Medium => 4,
+                    RenderQuality::Low => 8,
+                };
+                args.extend(["-q:v".into(), scale.to_string()]);
+                return args;
+            }
+            _ => {}
+        }
+        if self == Self::Libx264 {
+            if let Some(preset) = &settings.encoder_preset {
+                args.extend(["-preset".into(), preset.clone()]);
+            }
+        }
+        if let Some(bitrate) = settings.target_bitrate {
+            args.extend(["-b:v".into(), bitrate.to_string()]);
+            return args;
+        }
+        if let (Self::Libx264, Some(crf)) = (self, settings.crf) {
+            args.extend(["-crf".into(), crf.to_string()]);
+            return args;
+        }
+
+        // Constant quality values for Lossless, High, Medium and Low
+        let (option, values) = match self {
+            Self::Libx264 | Self::LibvpxVp9 => ("-crf", [0, 18, 23, 28]),
+            // -cq 0 means "automatic", so the best setting is 1
+            Self::Nvenc => ("-cq", [1, 19, 24, 30]),
+            // Higher is better (1 to 100)
+            Self::VideoToolbox => ("-q:v", [100, 75, 60, 40]),
+            Self::Vaapi => ("-qp", [1, 18, 23, 28]),
+            Self::Qsv => ("-global_quality", [1, 18, 23, 28]),
+            Self::Gif
+            | Self::LibwebpAnim
+            | Self::ProresKs
+            | Self::Png
+            | Self::Mjpeg
+            | Self::Exr => return args,
+        };
+        let value = match settings.quality {
+            RenderQuality::Lossless => values[0],
+            RenderQuality::High => values[1],
+            RenderQuality::Medium => values[2],
+            RenderQuality::Low => values[3],
+        };
+
+        match self {
+            // VP9 only honours -crf in constant quality mode (-b:v 0)
+            Self::LibvpxVp9 => args.extend(["-b:v".into(), "0".into()]),
+            Self::Nvenc => args.extend(["-rc".into(), "vbr".into()]),
+            _ => {}
+        }
+        args.extend([option.into(), value.to_string()]);
+        args
+    }
+}
+
+/// Pick the encoder for `settings`, probing hardware encoders by encoding a
+/// single test frame, since FFmpeg lists encoders whose hardware or drivers
+/// are missing.
+///
+/// `Auto` falls back to software encoding; an explicitly requested hardware
+/// encoder that does not work is an error.
+pub(crate) async fn select_encoder(settings: &RenderSettings) -> Result<VideoEncoder> {
+    select_encoder_with(settings, encoder_works).await
+}
+
+/// [`select_encoder`] with `works` telling whether a hardware encoder works
+async fn select_encoder_with<F, Fut>(settings: &RenderSettings, works: F) -> Result<VideoEncoder>
+where
+    F: Fn(VideoEncoder) -> Fut,
+    Fut: Future<Output = bool>,
+{
+    let software = match settings.format.as_str() {
+        "webm" => VideoEncoder::LibvpxVp9,
+        "gif" => VideoEncoder::Gif,
+        "webp" => VideoEncoder::LibwebpAnim,
+        "prores" => VideoEncoder::ProresKs,
+        "image-sequence" => match settings.image_format.as_deref().unwrap_or("png") {
+            "png" => VideoEncoder::Png,
+            "jpg" | "jpeg" => VideoEncoder::Mjpeg,
+            "exr" => VideoEncoder::Exr,
+            other => {
+                return Err(Error::Render(format!(
+                    "Unsupported image sequence format: {}",
+                    other
+                )))
+            }
+        },
+        _ => VideoEncoder::Libx264,
+    };
+    // CRF and presets are libx264 options, so they rule out hardware encoders
+    let x264_options = settings.crf.is_some() || settings.encoder_preset.is_some();
+    if x264_options {
+        check_x264_options(settings, software)?;
+    }
+    let requested = match settings.hardware_acceleration {
+        HardwareAcceleration::None => return Ok(software),
+        HardwareAcceleration::Nvenc => VideoEncoder::Nvenc,
+        HardwareAcceleration::VideoToolbox => VideoEncoder::VideoToolbox,
+        HardwareAcceleration::Vaapi => VideoEncoder::Vaapi,
+        HardwareAcceleration::Qsv => VideoEncoder::Qsv,
+        HardwareAcceleration::Auto => {
+            // Hardware encoders here are all H.264
+            if software != VideoEncoder::Libx264 || x264_options {
+                return Ok(software);
+            }
+            let candidates: &[VideoEncoder] = if cfg!(target_os = "macos") {
+                &[VideoEncoder::VideoToolbox]
+            } else {
+                &[VideoEncoder::Nvenc, VideoEncoder::Qsv, VideoEncoder::Vaapi]
+            };
+            for &encoder in candidates {
+                if works(encoder).await {
+                    return Ok(encoder);
+                }
+            }
+            return Ok(software);
+        }
+    };
+
+    if software != VideoEncoder::Libx264 {
+        return Err(Error::Render(format!(
+            "Hardware encoding is only available for H.264 output, not {}",
+            settings.format
+        )));
+    }
+    if x264_options {
+        return Err(Error::Render(format!(
+            "crf and encoderPreset only apply to libx264, not {}",
+            requested.name()
+        )));
+    }
+    if !works(requested).await {
+        return Err(Error::Render(format!(
+            "Encoder {} is not available on this system",
+            requested.name()
+        )));
+    }
+    Ok(requested)
+}
+
+/// Check the `crf` and `encoder_preset` settings for the `software` encoder
+fn check_x264_options(settings: &RenderSettings, software: VideoEncoder) -> Result<()> {
+    if software != VideoEncoder::Libx264 {
+        return Err(Error::Render(format!(
+            "crf and encoderPreset only apply to H.264 output, not {}",
+            settings.format
+        )));
+    }
+    if let Some(crf) = settings.crf {
+        if crf > MAX_CRF {
+            return Err(Error::Render(format!(
+                "crf must be between 0 and {MAX_CRF}, got {crf}"
+            )));
+        }
+        if settings.target_bitrate.is_some() {
+            return Err(Error::Render(
+                "Set either crf or a target bitrate, not both".to_string(),
+            ));
+        }
+    }
+    if let Some(preset) = &settings.encoder_preset {
+        if !X264_PRESETS.contains(&preset.as_str()) {
+            return Err(Error::Render(format!(
+                "Unknown encoder preset {preset}; expected one of {}",
+                X264_PRESETS.join(", ")
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Whether `ffmpeg` can encode a frame with `encoder`
+async fn encoder_works(encoder: VideoEncoder) -> bool {
+    let mut args = vec!["-hide_banner".to_string()];
+    args.extend(encoder.global_args());
+    args.extend(["-f", "lavfi", "-i", "color=s=256x256:d=0.1"].map(String::from));
+    if let Some(upload) = encoder.upload_filter() {
+        args.extend(["-vf".into(), upload.into()]);
+    }
+    args.extend(["-frames:v", "1", "-c:v", encoder.name(), "-f", "null", "-"].map(String::from));
+
+    Command::new("ffmpeg")
+        .args(&args)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .await
+        .map(|s| s.success())
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn settings(format: &str, hardware_acceleration: &str) -> RenderSettings {
+        serde_json::from_value(json!({
+            "resolution": [1280, 720],
+            "frameRate": 30.0,
+            "format": format,
+            "quality": "high",
+            "hardwareAcceleration": hardware_acceleration
+        }))
+        .unwrap()
+    }
+
+    async fn broken(_: VideoEncoder) -> bool {
+        false
+    }
+
+    async fn working(_: VideoEncoder) -> bool {
+        true
+    }
+
+    #[tokio::test]
+    async fn auto_falls_back_to_libx264_when_probing_fails() {
+        let encoder = select_encoder_with(&settings("mp4", "auto"), broken).await;
+        assert_eq!(encoder.unwrap(), VideoEncoder::Libx264);
+    }
+
+    #[tokio::test]
+    async fn auto_picks_a_working_hardware_encoder() {
+        let encoder = select_encoder_with(&settings("mp4", "auto"), working).await;
+        let expected = if cfg!(target_os = "macos") {
+            VideoEncoder::VideoToolbox
+        } else {
+            VideoEncoder::Nvenc
+        };
+        assert_eq!(encoder.unwrap(), expected);
+    }
+
+    #[tokio::test]
+    async fn auto_keeps_software_encoders_for_other_formats() {
+        let encoder = select_encoder_with(&settings("webm", "auto"), working).await;
+        assert_eq!(encoder.unwrap(), VideoEncoder::LibvpxVp9);
+    }
+
+    #[tokio::test]
+    async fn requested_encoder_that_fails_is_an_error() {
+        let encoder = select_encoder_with(&settings("mp4", "nvenc"), broken).await;
+        assert!(encoder.is_err());
+    }
+}
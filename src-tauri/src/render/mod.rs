@@ -1,19 +1,47 @@
 //! Render manager for handling video export
 
+mod encoder;
+mod pipeline;
+mod presets;
+
+use crate::media::process::run_ffmpeg;
 use crate::project::{Project, ProjectManager};
 use crate::{Error, Result};
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tauri::async_runtime::spawn;
-use tokio::time::sleep;
+use tauri::{AppHandle, Emitter};
+use tempfile::Builder;
+use tokio::sync::Notify;
 use uuid::Uuid;
 
+pub use presets::{RenderPreset, RenderPresetLibrary};
+
 lazy_static! {
     static ref RENDER_JOBS: Arc<Mutex<HashMap<String, Arc<Mutex<RenderJob>>>>> =
         Arc::new(Mutex::new(HashMap::new()));
+    static ref RENDER_QUEUE: Mutex<RenderQueue> = Mutex::new(RenderQueue {
+        waiting: VecDeque::new(),
+        running: 0,
+        max_concurrent: 1,
+    });
+}
+
+/// File name prefix of image sequence frames
+const FRAME_PREFIX: &str = "frame_";
+
+/// How long a finished render or effect job can still be looked up
+const FINISHED_JOB_RETENTION: Duration = Duration::from_secs(10 * 60);
+
+/// Jobs waiting for a render slot, in submission order
+struct RenderQueue {
+    waiting: VecDeque<String>,
+    running: usize,
+    max_concurrent: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -23,6 +51,118 @@ pub struct RenderSettings {
     pub frame_rate: f64,
     pub format: String,
     pub quality: RenderQuality,
+    /// Hardware encoder to use, if any
+    #[serde(default)]
+    pub hardware_acceleration: HardwareAcceleration,
+    /// Encode in two passes to hit `target_bitrate` more accurately
+    #[serde(default)]
+    pub two_pass: bool,
+    /// Average video bitrate in bits per second, replacing the constant
+    /// quality mode of `quality`
+    #[serde(default)]
+    pub target_bitrate: Option<u64>,
+    /// libx264 constant rate factor (0 to 51, lower is better), overriding
+    /// the mapping of `quality`
+    #[serde(default)]
+    pub crf: Option<u8>,
+    /// libx264 speed preset, e.g. "veryfast" or "slow"
+    #[serde(default)]
+    pub encoder_preset: Option<String>,
+    /// Frame rate of GIF and WebP output, which is usually lower than
+    /// `frame_rate` to keep files small
+    #[serde(default)]
+    pub animation_fps: Option<f64>,
+    /// Palette size of GIF output (2 to 256)
+    #[serde(default)]
+    pub max_colors: Option<u32>,
+    /// Start and end (seconds on the timeline) of the part to render; the
+    /// whole timeline if unset
+    #[serde(default)]
+    pub range: Option<(f64, f64)>,
+    /// Frame format of the "image-sequence" format: "png" (default), "jpg"
+    /// or "exr"
+    #[serde(default)]
+    pub image_format: Option<String>,
+    /// Image burnt into every frame, e.g. a logo
+    #[serde(default)]
+    pub watermark: Option<WatermarkSettings>,
+    /// Captions burnt into the picture
+    #[serde(default)]
+    pub subtitles: Option<BurnSubtitles>,
+}
+
+/// Captions drawn onto the rendered frames ("hard subs")
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BurnSubtitles {
+    /// ID of a caption asset in the project
+    #[serde(default)]
+    pub caption_id: Option<String>,
+    /// SRT or ASS file, used when `caption_id` is unset
+    #[serde(default)]
+    pub path: Option<String>,
+    /// Font size, overriding the file's styles
+    #[serde(default)]
+    pub font_size: Option<u32>,
+    /// Text color (`#RRGGBB`), overriding the file's styles
+    #[serde(default)]
+    pub color: Option<String>,
+}
+
+/// An image overlaid on the whole render
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatermarkSettings {
+    /// Image file; PNG keeps its transparency
+    pub image_path: String,
+    #[serde(default)]
+    pub position: WatermarkPosition,
+    /// 0.0 (invisible) to 1.0 (opaque)
+    #[serde(default = "default_watermark_opacity")]
+    pub opacity: f64,
+    /// Width as a fraction of the output width (0.01 to 1.0)
+    #[serde(default = "default_watermark_scale")]
+    pub scale: f64,
+}
+
+fn default_watermark_opacity() -> f64 {
+    0.5
+}
+
+fn default_watermark_scale() -> f64 {
+    0.15
+}
+
+/// Where a watermark sits, inset from the frame edges
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WatermarkPosition {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+    Center,
+}
+
+impl RenderSettings {
+    /// Whether the output is an animated image rather than a video
+    pub fn is_animation(&self) -> bool {
+        matches!(self.format.as_str(), "gif" | "webp")
+    }
+
+    /// Whether the output is a directory of numbered frames
+    pub fn is_image_sequence(&self) -> bool {
+        self.format == "image-sequence"
+    }
+
+    /// Frame rate of the rendered file
+    pub fn output_frame_rate(&self) -> f64 {
+        match self.animation_fps {
+            Some(fps) if fps > 0.0 && self.is_animation() => fps,
+            _ => self.frame_rate,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,6 +174,25 @@ pub enum RenderQuality {
     Lossless,
 }
 
+/// Hardware video encoding backend
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HardwareAcceleration {
+    /// Software encoding (libx264 or libvpx-vp9)
+    #[default]
+    None,
+    /// NVIDIA NVENC
+    Nvenc,
+    /// Apple VideoToolbox
+    VideoToolbox,
+    /// VA-API (Intel and AMD on Linux)
+    Vaapi,
+    /// Intel Quick Sync Video
+    Qsv,
+    /// The first working hardware encoder, falling back to software
+    Auto,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RenderProgress {
@@ -42,6 +201,69 @@ pub struct RenderProgress {
     pub progress: f64,
     pub message: String,
     pub output_path: Option<String>,
+    /// Seconds since the job started rendering (0 while queued)
+    pub elapsed_seconds: f64,
+    /// Estimated seconds until the job finishes, once there is progress to
+    /// extrapolate from
+    pub eta_seconds: Option<f64>,
+    /// Frames `ffmpeg` has encoded; in a two-pass render this counts the
+    /// current pass
+    pub frames_done: u64,
+    /// Frames the output will have, estimated from the timeline (0 while
+    /// queued)
+    pub frames_total: u64,
+}
+
+impl RenderProgress {
+    /// Progress of a job that hasn't started yet
+    pub(crate) fn new(job_id: String, message: &str) -> Self {
+        Self {
+            job_id,
+            status: RenderStatus::Queued,
+            progress: 0.0,
+            message: message.to_string(),
+            output_path: None,
+            elapsed_seconds: 0.0,
+            eta_seconds: None,
+            frames_done: 0,
+            frames_total: 0,
+        }
+    }
+
+    /// Record an update made `elapsed` seconds after the job started
+    /// running, refreshing the ETA
+    pub(crate) fn advance(
+        &mut self,
+        elapsed: f64,
+        status: RenderStatus,
+        progress: f64,
+        message: &str,
+    ) {
+        self.eta_seconds = match status {
+            RenderStatus::Rendering if progress > 0.0 => {
+                let estimate = elapsed * (1.0 - progress) / progress;
+                // Blend with the previous estimate, counted down by the time
+                // since, so the countdown doesn't jump with every update
+                let eta = match self.eta_seconds {
+                    Some(previous) => {
+                        let counted_down = previous - (elapsed - self.elapsed_seconds);
+                        counted_down + (estimate - counted_down) * ETA_SMOOTHING
+                    }
+                    None => estimate,
+                };
+                Some(eta.max(0.0))
+            }
+            RenderStatus::Completed => {
+                self.frames_done = self.frames_total;
+                Some(0.0)
+            }
+            _ => None,
+        };
+        self.elapsed_seconds = elapsed;
+        self.status = status;
+        self.progress = progress;
+        self.message = message.to_string();
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -54,6 +276,29 @@ pub enum RenderStatus {
     Cancelled,
 }
 
+impl RenderStatus {
+    /// Whether the job is over, however it ended
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            RenderStatus::Completed | RenderStatus::Failed | RenderStatus::Cancelled
+        )
+    }
+}
+
+/// Remove job `job_id` from `jobs` once [`FINISHED_JOB_RETENTION`] has
+/// passed, so a finished job's outcome stays readable for a while without
+/// the map growing for the whole session
+pub(crate) fn forget_finished_job<T: Send + 'static>(
+    jobs: &'static Mutex<HashMap<String, T>>,
+    job_id: String,
+) {
+    spawn(async move {
+        tokio::time::sleep(FINISHED_JOB_RETENTION).await;
+        jobs.lock().unwrap().remove(&job_id);
+    });
+}
+
 #[derive(Debug)]
 pub struct RenderJob {
     pub id: String,
@@ -61,87 +306,137 @@ pub struct RenderJob {
     pub settings: RenderSettings,
     pub output_path: PathBuf,
     pub progress: RenderProgress,
+    /// When the job left the queue and started rendering
+    started_at: Option<Instant>,
+    /// Signalled by `cancel_render` to stop the running ffmpeg process
+    cancel: Arc<Notify>,
+    /// Receives a `render-progress` event on every progress update
+    app: AppHandle,
 }
 
+/// Weight of the newest estimate when smoothing the ETA
+const ETA_SMOOTHING: f64 = 0.2;
+
 impl RenderJob {
-    fn new(project: Project, settings: RenderSettings, output_path: PathBuf) -> Self {
+    fn new(
+        app: AppHandle,
+        project: Project,
+        settings: RenderSettings,
+        output_path: PathBuf,
+    ) -> Self {
         let job_id = Uuid::new_v4().to_string();
         Self {
             id: job_id.clone(),
             project,
             settings,
             output_path,
-            progress: RenderProgress {
-                job_id,
-                status: RenderStatus::Queued,
-                progress: 0.0,
-                message: "Waiting to start".to_string(),
-                output_path: None,
-            },
+            progress: RenderProgress::new(job_id, "Waiting to start"),
+            started_at: None,
+            cancel: Arc::new(Notify::new()),
+            app,
         }
     }
 
     fn update_progress(&mut self, status: RenderStatus, progress: f64, message: &str) {
-        self.progress.status = status;
-        self.progress.progress = progress;
-        self.progress.message = message.to_string();
+        if status == RenderStatus::Rendering && self.started_at.is_none() {
+            self.started_at = Some(Instant::now());
+        }
+        let finishing = status.is_finished() && !self.progress.status.is_finished();
+        let elapsed = self
+            .started_at
+            .map_or(0.0, |start| start.elapsed().as_secs_f64());
+        self.progress.advance(elapsed, status, progress, message);
+        let _ = self.app.emit("render-progress", &self.progress);
+        if finishing {
+            forget_finished_job(&RENDER_JOBS, self.id.clone());
+        }
     }
 }
 
 pub struct RenderManager;
 
 impl RenderManager {
+    /// Queue a render of the project at `project_path`, returning the job ID
+    ///
+    /// Every progress update is emitted to `app` as a `render-progress`
+    /// event carrying the job's [`RenderProgress`].
     pub async fn start_render(
+        app: AppHandle,
         project_path: &str,
         settings: RenderSettings,
         output_path: &str,
     ) -> Result<String> {
         let project = ProjectManager::load(Path::new(project_path))?;
         let job = Arc::new(Mutex::new(RenderJob::new(
+            app,
             project,
             settings,
             PathBuf::from(output_path),
         )));
         let job_id = job.lock().unwrap().id.clone();
 
-        RENDER_JOBS
+        RENDER_JOBS.lock().unwrap().insert(job_id.clone(), job);
+        RENDER_QUEUE
             .lock()
             .unwrap()
-            .insert(job_id.clone(), job.clone());
+            .waiting
+            .push_back(job_id.clone());
+        Self::start_queued();
 
-        spawn(async move {
-            let (project, settings, output_path) = {
-                let mut job_lock = job.lock().unwrap();
-                job_lock.update_progress(RenderStatus::Rendering, 0.0, "Starting render...");
+        Ok(job_id)
+    }
 
-                // Make a clone of necessary data for the rendering task
-                let project = job_lock.project.clone();
-                let settings = job_lock.settings.clone();
-                let output_path = job_lock.output_path.clone();
+    /// Set how many jobs may render at once (at least 1).  Raising the limit
+    /// starts waiting jobs immediately; lowering it lets running jobs finish.
+    pub fn set_max_concurrent(n: usize) {
+        RENDER_QUEUE.lock().unwrap().max_concurrent = n.max(1);
+        Self::start_queued();
+    }
 
-                // job_lock is dropped here when the inner scope ends
-                (project, settings, output_path)
+    /// Start waiting jobs, oldest first, while render slots are free
+    fn start_queued() {
+        loop {
+            let job = {
+                let mut queue = RENDER_QUEUE.lock().unwrap();
+                if queue.running >= queue.max_concurrent {
+                    return;
+                }
+                let Some(job_id) = queue.waiting.pop_front() else {
+                    return;
+                };
+                let Some(job) = RENDER_JOBS.lock().unwrap().get(&job_id).cloned() else {
+                    continue;
+                };
+                queue.running += 1;
+                job
             };
 
-            let render_result = run_render_task(project, settings, output_path, job.clone()).await;
+            spawn(async move {
+                run_job(job).await;
+                RENDER_QUEUE.lock().unwrap().running -= 1;
+                Self::start_queued();
+            });
+        }
+    }
 
-            let mut job_lock = job.lock().unwrap();
-            match render_result {
-                Ok(path) => {
-                    job_lock.update_progress(RenderStatus::Completed, 1.0, "Render finished");
-                    job_lock.progress.output_path = Some(path);
-                }
-                Err(e) => {
-                    job_lock.update_progress(RenderStatus::Failed, 0.0, &e.to_string());
-                }
-            }
-        });
+    /// Read presets from the library in `dir` (usually the app data
+    /// directory), or only the built-ins if `None`
+    pub fn set_preset_dir(dir: Option<PathBuf>) {
+        presets::set_library_dir(dir);
+    }
 
-        Ok(job_id)
+    /// Settings of the preset called `name` (e.g. "youtube-1080p")
+    ///
+    /// The user's library wins, so edited presets apply; built-in presets
+    /// missing from it are still found.
+    pub fn preset(name: &str) -> Option<RenderSettings> {
+        presets::find(name)
     }
 
     pub fn cancel_render(job_id: &str) -> Result<()> {
-        if let Some(job_arc) = RENDER_JOBS.lock().unwrap().get(job_id) {
+        // Release the job map before touching the queue, which locks it too
+        let job_arc = RENDER_JOBS.lock().unwrap().get(job_id).cloned();
+        if let Some(job_arc) = job_arc {
             // Read status under a short-lived lock to avoid holding an immutable borrow
             let status = {
                 let job = job_arc.lock().unwrap();
@@ -159,7 +454,16 @@ impl RenderManager {
                     current_progress,
                     "Render cancelled by user",
                 );
-                // The render loop should check for this status and exit gracefully.
+                // Stores a permit if ffmpeg isn't running yet (e.g. between
+                // passes), so the next pass stops straight away
+                job.cancel.notify_one();
+                drop(job);
+                // A waiting job simply never starts
+                RENDER_QUEUE
+                    .lock()
+                    .unwrap()
+                    .waiting
+                    .retain(|id| id != job_id);
                 return Ok(());
             }
         }
@@ -167,47 +471,203 @@ impl RenderManager {
     }
 
     pub fn get_progress(job_id: &str) -> Result<RenderProgress> {
-        RENDER_JOBS
+        let mut progress = RENDER_JOBS
             .lock()
             .unwrap()
             .get(job_id)
             .map(|job| job.lock().unwrap().progress.clone())
-            .ok_or_else(|| Error::NotFound("Render job not found".to_string()))
+            .ok_or_else(|| Error::NotFound("Render job not found".to_string()))?;
+
+        if progress.status == RenderStatus::Queued {
+            let queue = RENDER_QUEUE.lock().unwrap();
+            if let Some(ahead) = queue.waiting.iter().position(|id| id == job_id) {
+                progress.message = match ahead {
+                    0 => "Waiting for a free render slot".to_string(),
+                    1 => "Waiting for 1 job ahead".to_string(),
+                    n => format!("Waiting for {} jobs ahead", n),
+                };
+            }
+        }
+        Ok(progress)
     }
 }
 
+/// Render a job that has been given a slot and record the outcome
+async fn run_job(job: Arc<Mutex<RenderJob>>) {
+    let (project, settings, output_path) = {
+        let mut job_lock = job.lock().unwrap();
+        // Cancelled between leaving the queue and starting
+        if job_lock.progress.status == RenderStatus::Cancelled {
+            return;
+        }
+        job_lock.update_progress(RenderStatus::Rendering, 0.0, "Starting render...");
+
+        // Make a clone of necessary data for the rendering task
+        let project = job_lock.project.clone();
+        let settings = job_lock.settings.clone();
+        let output_path = job_lock.output_path.clone();
+
+        // job_lock is dropped here when the inner scope ends
+        (project, settings, output_path)
+    };
+
+    let render_result = run_render_task(project, settings, output_path.clone(), job.clone()).await;
+
+    let mut job_lock = job.lock().unwrap();
+    match render_result {
+        Ok(path) => {
+            // Set first so the final event carries the path
+            job_lock.progress.output_path = Some(path);
+            job_lock.update_progress(RenderStatus::Completed, 1.0, "Render finished");
+        }
+        // Keep the status and message set by `cancel_render`, and remove
+        // the partial output
+        Err(_) if job_lock.progress.status == RenderStatus::Cancelled => {
+            if job_lock.settings.is_image_sequence() {
+                remove_frames(&output_path);
+            } else {
+                let _ = std::fs::remove_file(&output_path);
+            }
+        }
+        Err(e) => {
+            job_lock.update_progress(RenderStatus::Failed, 0.0, &e.to_string());
+        }
+    }
+}
+
+/// Delete the frames of an image sequence from `directory`, leaving any
+/// other files
+fn remove_frames(directory: &Path) {
+    let Ok(entries) = std::fs::read_dir(directory) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        if entry
+            .file_name()
+            .to_string_lossy()
+            .starts_with(FRAME_PREFIX)
+        {
+            let _ = std::fs::remove_file(entry.path());
+        }
+    }
+}
+
+/// Render the project by running `ffmpeg` on the plan from
+/// [`pipeline::build_render_plan`], once or as two passes.
 async fn run_render_task(
-    _project: Project,
-    _settings: RenderSettings,
-    _output_path: PathBuf,
-    _job: Arc<Mutex<RenderJob>>,
+    project: Project,
+    settings: RenderSettings,
+    output_path: PathBuf,
+    job: Arc<Mutex<RenderJob>>,
 ) -> Result<String> {
-    // TODO: This is the core rendering logic.
-    // 1. Setup ffmpeg_next contexts (input, output, filter graph).
-    // 2. Build a complex filter graph based on the project timeline.
-    //    - Each track item is an input source.
-    //    - Effects are chained filters.
-    //    - Transitions are complex filter chains (e.g., using xfade).
-    //    - Audio tracks need to be mixed using amix.
-    // 3. Loop through time, read frames from sources, process through graph, and write to output.
-    // 4. Update progress periodically by calling `job.lock().unwrap().update_progress(...)`.
-    // 5. Check for cancellation `job.lock().unwrap().progress.status == RenderStatus::Cancelled`.
-
-    // For now, we'll simulate a long render and then succeed.
-    for i in 1..=10 {
-        // Check for cancellation
-        if _job.lock().unwrap().progress.status == RenderStatus::Cancelled {
-            return Err(Error::Render("Render was cancelled".to_string()));
-        }
+    let encoder = encoder::select_encoder(&settings).await?;
+    let plan = pipeline::build_render_plan(&project, &settings, encoder)?;
+    let output = output_path.to_string_lossy().into_owned();
+
+    // Image sequences go into the output directory as numbered frames
+    if let Some(extension) = encoder.image_extension() {
+        std::fs::create_dir_all(&output_path)?;
+        let pattern = output_path
+            .join(format!("{FRAME_PREFIX}%05d.{extension}"))
+            .to_string_lossy()
+            .into_owned();
+        run_ffmpeg_pass(
+            &plan,
+            &["-f", "image2", &pattern],
+            &job,
+            (0.0, 1.0),
+            "Rendering",
+        )
+        .await?;
+        return Ok(output);
+    }
 
-        sleep(std::time::Duration::from_secs(1)).await;
-        let progress = i as f64 / 10.0;
-        _job.lock().unwrap().update_progress(
-            RenderStatus::Rendering,
-            progress,
-            &format!("Rendering... {}%", (progress * 100.0) as u32),
-        );
+    if !settings.two_pass {
+        run_ffmpeg_pass(&plan, &[&output], &job, (0.0, 1.0), "Rendering").await?;
+        return Ok(output);
     }
 
-    Ok(_output_path.to_string_lossy().to_string())
+    if settings.target_bitrate.is_none() {
+        return Err(Error::Render(
+            "Two-pass encoding needs a target bitrate".to_string(),
+        ));
+    }
+    if !encoder.supports_two_pass() {
+        return Err(Error::Render(format!(
+            "{} does not support two-pass encoding",
+            encoder.name()
+        )));
+    }
+
+    // The encoder's statistics files are removed with the directory
+    let log_dir = Builder::new().prefix("waldiez_passlog_").tempdir()?;
+    let log_file = log_dir
+        .path()
+        .join("passlog")
+        .to_string_lossy()
+        .into_owned();
+    run_ffmpeg_pass(
+        &plan,
+        &["-pass", "1", "-passlogfile", &log_file, "-f", "null", "-"],
+        &job,
+        (0.0, 0.5),
+        "Analysing (pass 1 of 2)",
+    )
+    .await?;
+    run_ffmpeg_pass(
+        &plan,
+        &["-pass", "2", "-passlogfile", &log_file, &output],
+        &job,
+        (0.5, 1.0),
+        "Encoding (pass 2 of 2)",
+    )
+    .await?;
+
+    Ok(output)
+}
+
+/// Run `ffmpeg` with the plan's arguments followed by `output_args`,
+/// mapping its progress reports onto the `progress_range` of the job and
+/// killing it if the job is cancelled.
+async fn run_ffmpeg_pass(
+    plan: &pipeline::RenderPlan,
+    output_args: &[&str],
+    job: &Arc<Mutex<RenderJob>>,
+    progress_range: (f64, f64),
+    label: &str,
+) -> Result<()> {
+    let args: Vec<String> = plan
+        .args
+        .iter()
+        .map(String::as_str)
+        .chain(["-progress", "pipe:1", "-nostats"])
+        .chain(output_args.iter().copied())
+        .map(String::from)
+        .collect();
+    let cancel = job.lock().unwrap().cancel.clone();
+    let (start, end) = progress_range;
+    run_ffmpeg(&args, Some(&cancel), |progress| {
+        let fraction = match progress.out_time {
+            Some(time) if plan.duration > 0.0 => time / plan.duration,
+            _ => progress.frame as f64 / plan.total_frames.max(1) as f64,
+        };
+        let mut job = job.lock().unwrap();
+        if job.progress.status == RenderStatus::Rendering {
+            job.progress.frames_done = progress.frame;
+            job.progress.frames_total = plan.total_frames.max(progress.frame);
+            job.update_progress(
+                RenderStatus::Rendering,
+                start + (end - start) * fraction.clamp(0.0, 1.0),
+                &format!(
+                    "{}: frame {} of {}",
+                    label, progress.frame, plan.total_frames
+                ),
+            );
+        }
+    })
+    .await
+    .map_err(|e| match e {
+        Error::Media(message) => Error::Render(message),
+        e => e,
+    })
 }
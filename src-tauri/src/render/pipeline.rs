@@ -0,0 +1,716 @@
+//! Translation of a project timeline into an FFmpeg command line
+
+use super::encoder::VideoEncoder;
+use super::{BurnSubtitles, RenderSettings, WatermarkPosition, WatermarkSettings};
+use crate::effects;
+use crate::project::{Project, Track, TrackItem, TrackType, Transform, Transition};
+use crate::{Error, Result};
+use serde_json::Value;
+use std::fmt::Write;
+
+/// Input, filter graph and codec arguments for `ffmpeg` (output options and
+/// the output path still to be appended), and the number of frames and
+/// seconds it writes
+#[derive(Debug, Clone)]
+pub(crate) struct RenderPlan {
+    pub args: Vec<String>,
+    pub total_frames: u64,
+    /// Output length, in seconds
+    pub duration: f64,
+}
+
+/// A timeline item resolved to its source file and cut to the render range
+struct Clip<'a> {
+    item: &'a TrackItem,
+    path: &'a str,
+    /// Start in the output, in seconds
+    start: f64,
+    /// Position in the source file of the first frame, in seconds
+    in_point: f64,
+    /// Length on the timeline, in seconds
+    duration: f64,
+    /// Source seconds played per timeline second, from `speed` effects
+    speed: f64,
+    /// Format of the source video, for effects that need it
+    input: Option<effects::InputFormat>,
+}
+
+impl<'a> Clip<'a> {
+    /// The part of `item` inside the timeline range `range_start` to
+    /// `range_end`, or `None` if it lies outside
+    ///
+    /// A sped-up or slowed-down item covers its source length divided by its
+    /// speed on the timeline.
+    fn cut(
+        item: &'a TrackItem,
+        path: &'a str,
+        (range_start, range_end): (f64, f64),
+    ) -> Option<Self> {
+        let trimmed = item.out_point - item.in_point;
+        let length = if trimmed > 0.0 {
+            trimmed
+        } else {
+            item.duration
+        };
+        let speed = item_speed(item);
+        let start = item.start_time.max(range_start);
+        let end = (item.start_time + length / speed).min(range_end);
+        (end > start).then(|| Clip {
+            item,
+            path,
+            start: start - range_start,
+            in_point: item.in_point.max(0.0) + (start - item.start_time) * speed,
+            duration: end - start,
+            speed,
+            input: None,
+        })
+    }
+
+    /// `-ss`/`-t`/`-i` arguments reading just this clip from its file, plus
+    /// what plays in the `handle` timeline seconds before its in point
+    fn input_args(&self, handle: f64) -> [String; 6] {
+        [
+            "-ss".into(),
+            (self.in_point - handle * self.speed).to_string(),
+            "-t".into(),
+            ((self.duration + handle) * self.speed).to_string(),
+            "-i".into(),
+            self.path.to_string(),
+        ]
+    }
+}
+
+/// A stretch of the output: a clip, or background where there is none
+struct Piece<'c, 'a> {
+    clip: Option<&'c Clip<'a>>,
+    /// Length on the timeline, in seconds
+    duration: f64,
+    /// `xfade` transition from the previous piece and its length; the piece
+    /// is rendered that much longer, starting early, so the timeline keeps
+    /// its length
+    transition: Option<(&'static str, f64)>,
+}
+
+/// Transition types `xfade` knows, after normalising to lowercase letters
+const XFADE_TRANSITIONS: &[&str] = &[
+    "fade",
+    "fadeblack",
+    "fadewhite",
+    "dissolve",
+    "wipeleft",
+    "wiperight",
+    "wipeup",
+    "wipedown",
+    "slideleft",
+    "slideright",
+    "slideup",
+    "slidedown",
+    "circleopen",
+    "circleclose",
+    "pixelize",
+    "radial",
+];
+
+/// Build the `ffmpeg` arguments that render `project`
+///
+/// Only the first visible video track is rendered.  Each item is trimmed to
+/// its in/out points, run through its effect chain, scaled, rotated and faded
+/// by its [`Transform`], and composited onto a canvas of the output
+/// resolution filled with the project background colour.  Gaps between items
+/// are rendered as background, and item transitions become `xfade`
+/// cross-fades ending where the earlier piece ends.  Unmuted audio tracks are mixed into the
+/// soundtrack (see [`audio_mix`]) if the output format has audio.
+///
+/// With a `range` in the settings, only that part of the timeline is
+/// rendered: items are cut to it and start times re-based so the output
+/// starts at the beginning of the range.
+pub(crate) fn build_render_plan(
+    project: &Project,
+    settings: &RenderSettings,
+    encoder: VideoEncoder,
+) -> Result<RenderPlan> {
+    let track = project
+        .composition
+        .tracks
+        .iter()
+        .find(|t| matches!(t.track_type, TrackType::Video) && t.is_visible && !t.items.is_empty())
+        .ok_or_else(|| Error::Render("Nothing to render: no visible video track".to_string()))?;
+
+    let range = match settings.range {
+        Some((start, end)) if start >= 0.0 && end > start => (start, end),
+        Some((start, end)) => {
+            return Err(Error::Render(format!(
+                "Invalid render range {start} to {end}"
+            )))
+        }
+        None => (0.0, f64::INFINITY),
+    };
+
+    let fps = settings.output_frame_rate();
+    let mut clips = Vec::with_capacity(track.items.len());
+    for item in &track.items {
+        let asset = project
+            .assets
+            .video
+            .iter()
+            .find(|a| a.id == item.asset_id)
+            .ok_or_else(|| Error::NotFound(format!("Video asset {} not found", item.asset_id)))?;
+        let still = effects::InputFormat::is_still(&asset.format, asset.duration);
+        let input = effects::InputFormat {
+            width: asset.width,
+            height: asset.height,
+            // A still is held for as long as the output needs
+            frame_rate: if still { fps } else { asset.frame_rate },
+            still,
+        };
+        clips.extend(Clip::cut(item, &asset.path, range).map(|clip| Clip {
+            input: Some(input),
+            ..clip
+        }));
+    }
+    if clips.is_empty() {
+        return Err(Error::Render(if settings.range.is_some() {
+            "Nothing to render: no items in the render range".to_string()
+        } else {
+            "Nothing to render: track is empty".to_string()
+        }));
+    }
+    clips.sort_by(|a, b| a.start.total_cmp(&b.start));
+
+    let (width, height) = settings.resolution;
+    let background = effects::ffmpeg_color(&project.settings.background_color).map_err(|_| {
+        Error::Render(format!(
+            "Invalid background color: {}",
+            project.settings.background_color
+        ))
+    })?;
+    let canvas =
+        |duration: f64| format!("color=c={background}:s={width}x{height}:r={fps}:d={duration}");
+
+    let mut args: Vec<String> = vec!["-y".into(), "-hide_banner".into()];
+    args.extend(encoder.global_args());
+    // Overlapping items are placed back to back
+    let mut pieces = Vec::with_capacity(clips.len() * 2);
+    let mut cursor = 0.0;
+    for clip in &clips {
+        let gap = clip.start - cursor;
+        if gap > 0.0 {
+            pieces.push(Piece {
+                clip: None,
+                duration: gap,
+                transition: None,
+            });
+            cursor += gap;
+        }
+        pieces.push(Piece {
+            clip: Some(clip),
+            duration: clip.duration,
+            transition: None,
+        });
+        cursor += clip.duration;
+    }
+    // An item's "in" transition, or else the previous item's "out" one,
+    // joins it to what comes before (which may be background)
+    for i in 1..pieces.len() {
+        let transition = find_transition(pieces[i].clip, "in")
+            .or_else(|| find_transition(pieces[i - 1].clip, "out"));
+        if let Some(transition) = transition {
+            let name = xfade_name(&transition.transition_type)?;
+            let duration = transition
+                .duration
+                .min(pieces[i - 1].duration)
+                .min(pieces[i].duration);
+            if duration > 0.0 {
+                pieces[i].transition = Some((name, duration));
+            }
+        }
+    }
+
+    let mut graph = String::new();
+    let mut input = 0;
+    for (i, piece) in pieces.iter().enumerate() {
+        let lead = piece.transition.map_or(0.0, |(_, duration)| duration);
+        let Some(clip) = piece.clip else {
+            let _ = write!(
+                graph,
+                "{},setsar=1,settb=AVTB[seg{i}];",
+                canvas(piece.duration + lead)
+            );
+            continue;
+        };
+
+        // Play the lead from the source before the in point where there is
+        // enough of it, and hold the first frame for the rest
+        let handle = lead.min(clip.in_point / clip.speed);
+        args.extend(clip.input_args(handle));
+        let mut chain = String::new();
+        if lead > handle {
+            // Padded before the effects, so in source time
+            let _ = write!(
+                chain,
+                "tpad=start_duration={}:start_mode=clone,",
+                (lead - handle) * clip.speed
+            );
+        }
+        if let Some(video) = effect_chain(clip)? {
+            let _ = write!(chain, "{video},");
+        }
+        chain.push_str(&transform_filters(&clip.item.transform));
+
+        let (x, y) = overlay_position(&clip.item.transform);
+        let _ = write!(
+            graph,
+            "[{input}:v]{chain}[fg{i}];{}[bg{i}];\
+             [bg{i}][fg{i}]overlay=x={x}:y={y},setsar=1,settb=AVTB[seg{i}];",
+            canvas(piece.duration + lead),
+        );
+        input += 1;
+    }
+
+    // Concatenate runs of pieces without transitions, cross-fading the
+    // output so far into each piece that has one
+    let mut run = String::from("[seg0]");
+    let mut run_length = 1;
+    let mut length = pieces[0].duration;
+    for (i, piece) in pieces.iter().enumerate().skip(1) {
+        match piece.transition {
+            None => {
+                let _ = write!(run, "[seg{i}]");
+                run_length += 1;
+            }
+            Some((name, duration)) => {
+                if run_length > 1 {
+                    let _ = write!(
+                        graph,
+                        "{run}concat=n={run_length}:v=1:a=0,fps={fps},settb=AVTB[run{i}];"
+                    );
+                    run = format!("[run{i}]");
+                }
+                let _ = write!(
+                    graph,
+                    "{run}[seg{i}]xfade=transition={name}:duration={duration}:offset={}[xfade{i}];",
+                    length - duration
+                );
+                run = format!("[xfade{i}]");
+                run_length = 1;
+            }
+        }
+        length += piece.duration;
+    }
+    let _ = write!(graph, "{run}concat=n={run_length}:v=1:a=0,fps={fps}");
+    if let Some(subtitles) = &settings.subtitles {
+        graph.push_str(&subtitle_filters(project, subtitles, range.0)?);
+    }
+    if let Some(watermark) = &settings.watermark {
+        graph.push_str(&watermark_filters(watermark, width, height)?);
+    }
+    let _ = write!(graph, ",{}[out]", encoder.output_filter(settings));
+
+    let audio_codec = encoder.audio_codec();
+    let audio = match audio_codec {
+        Some(_) => audio_mix(project, range, input, cursor)?,
+        None => None,
+    };
+    if let Some(mix) = &audio {
+        for clip in &mix.clips {
+            args.extend(clip.input_args(0.0));
+        }
+        let _ = write!(graph, ";{}", mix.graph);
+    }
+
+    args.extend([
+        "-filter_complex".into(),
+        graph,
+        "-map".into(),
+        "[out]".into(),
+    ]);
+    args.extend(encoder.output_args(settings));
+    if let (Some(_), Some(codec)) = (&audio, audio_codec) {
+        args.extend(["-map", "[aout]", "-c:a", codec, "-b:a", "192k"].map(String::from));
+    }
+
+    Ok(RenderPlan {
+        args,
+        total_frames: (cursor * fps).ceil().max(1.0) as u64,
+        duration: cursor,
+    })
+}
+
+/// Audio clips to add as inputs and the filter graph mixing them into
+/// `[aout]`
+struct AudioMix<'a> {
+    clips: Vec<Clip<'a>>,
+    graph: String,
+}
+
+/// Mix the items of every unmuted audio track into one soundtrack `duration`
+/// seconds long, or `None` if there is nothing to hear
+///
+/// Each item is trimmed like a video clip, run through the audio half of its
+/// effect chain, scaled by its track's opacity (used as the track volume)
+/// and delayed to its place on the timeline; `amix` then sums them without
+/// normalising, so levels stay as set.  Inputs are numbered from
+/// `first_input`.
+fn audio_mix(
+    project: &Project,
+    range: (f64, f64),
+    first_input: usize,
+    duration: f64,
+) -> Result<Option<AudioMix<'_>>> {
+    let tracks = project
+        .composition
+        .tracks
+        .iter()
+        .filter(|t| matches!(t.track_type, TrackType::Audio) && !t.is_muted);
+
+    let mut clips = Vec::new();
+    let mut volumes = Vec::new();
+    for track in tracks {
+        for item in &track.items {
+            clips.extend(Clip::cut(item, audio_path(project, track, item)?, range));
+            volumes.resize(clips.len(), track.opacity.max(0.0));
+        }
+    }
+    if clips.is_empty() {
+        return Ok(None);
+    }
+
+    let mut graph = String::new();
+    let mut labels = String::new();
+    for (i, (clip, volume)) in clips.iter().zip(&volumes).enumerate() {
+        let input = first_input + i;
+        let _ = write!(graph, "[{input}:a]");
+        if let Some(audio) = effect_chains(clip)?.audio {
+            let _ = write!(graph, "{audio},");
+        }
+        let delay = (clip.start * 1000.0).round() as u64;
+        let _ = write!(
+            graph,
+            "aresample=48000,volume={volume},adelay={delay}:all=1[a{i}];"
+        );
+        let _ = write!(labels, "[a{i}]");
+    }
+    // Pad or cut the mix to the length of the video
+    let _ = write!(
+        graph,
+        "{labels}amix=inputs={}:duration=longest:normalize=0,\
+         apad=whole_dur={duration},atrim=end={duration}[aout]",
+        clips.len()
+    );
+    Ok(Some(AudioMix { clips, graph }))
+}
+
+/// Source file of an audio item, which may use a video asset for its sound
+fn audio_path<'a>(project: &'a Project, track: &Track, item: &TrackItem) -> Result<&'a str> {
+    let assets = &project.assets;
+    assets
+        .audio
+        .iter()
+        .find(|a| a.id == item.asset_id)
+        .map(|a| a.path.as_str())
+        .or_else(|| {
+            assets
+                .video
+                .iter()
+                .find(|a| a.id == item.asset_id)
+                .map(|a| a.path.as_str())
+        })
+        .ok_or_else(|| {
+            Error::NotFound(format!(
+                "Audio asset {} on track {} not found",
+                item.asset_id, track.name
+            ))
+        })
+}
+
+/// The first transition of `clip`'s item at `position` ("in" or "out")
+fn find_transition<'a>(clip: Option<&Clip<'a>>, position: &str) -> Option<&'a Transition> {
+    clip?
+        .item
+        .transitions
+        .iter()
+        .find(|t| t.position == position)
+}
+
+/// FFmpeg `xfade` name for a project transition type, ignoring case and
+/// separators (e.g. "wipe-left" is `wipeleft`)
+fn xfade_name(transition_type: &str) -> Result<&'static str> {
+    let name: String = transition_type
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    let name = match name.as_str() {
+        "crossfade" => "fade",
+        "crossdissolve" => "dissolve",
+        other => other,
+    };
+    XFADE_TRANSITIONS
+        .iter()
+        .find(|t| **t == name)
+        .copied()
+        .ok_or_else(|| Error::Render(format!("Unsupported transition: {}", transition_type)))
+}
+
+/// The video half of a clip's enabled effects as one filter chain
+fn effect_chain(clip: &Clip) -> Result<Option<String>> {
+    Ok(effect_chains(clip)?.video)
+}
+
+/// A clip's enabled effects as video and audio filter chains, given its
+/// input format where an effect needs it
+fn effect_chains(clip: &Clip) -> Result<effects::FilterChain> {
+    let mut list = enabled_effects(clip.item);
+    if let Some(input) = &clip.input {
+        for (effect_id, parameters) in &mut list {
+            *parameters = input.fill(effect_id, parameters);
+        }
+    }
+    effects::build_filter_chain(&list)
+}
+
+/// Combined playback speed of an item's enabled effects
+fn item_speed(item: &TrackItem) -> f64 {
+    enabled_effects(item)
+        .iter()
+        .map(|(effect_id, parameters)| effects::playback_speed(effect_id, parameters))
+        .product()
+}
+
+/// An item's enabled effects with their parameters as JSON objects
+fn enabled_effects(item: &TrackItem) -> Vec<(String, Value)> {
+    item.effects
+        .iter()
+        .filter(|e| e.enabled)
+        .map(|e| {
+            let parameters = Value::Object(e.parameters.clone().into_iter().collect());
+            (e.effect_type.clone(), parameters)
+        })
+        .collect()
+}
+
+/// Scale, rotation (degrees, clockwise) and opacity, ending in RGBA so the
+/// overlay respects transparency
+fn transform_filters(transform: &Transform) -> String {
+    let mut filters = format!(
+        "scale=w=iw*{}:h=ih*{},format=rgba",
+        transform.scale.x.abs(),
+        transform.scale.y.abs()
+    );
+    if transform.rotation != 0.0 {
+        let _ = write!(
+            filters,
+            ",rotate=a={}*PI/180:c=none:ow=rotw(a):oh=roth(a)",
+            transform.rotation
+        );
+    }
+    let opacity = transform.opacity.clamp(0.0, 1.0);
+    if opacity < 1.0 {
+        let _ = write!(filters, ",colorchannelmixer=aa={opacity}");
+    }
+    filters
+}
+
+/// Filters, each preceded by a comma, drawing the captions of `subtitles`
+/// onto a render starting `offset` seconds into the timeline
+fn subtitle_filters(project: &Project, subtitles: &BurnSubtitles, offset: f64) -> Result<String> {
+    let path = match (&subtitles.caption_id, &subtitles.path) {
+        (Some(id), _) => project
+            .assets
+            .captions
+            .iter()
+            .find(|c| &c.id == id)
+            .map(|c| c.path.as_str())
+            .ok_or_else(|| Error::NotFound(format!("Caption asset {} not found", id)))?,
+        (None, Some(path)) => path.as_str(),
+        (None, None) => {
+            return Err(Error::Render(
+                "Burnt-in subtitles need a caption asset or a file".to_string(),
+            ))
+        }
+    };
+    if !std::path::Path::new(path).is_file() {
+        return Err(Error::InvalidPath(format!(
+            "Subtitle file not found: {}",
+            path
+        )));
+    }
+
+    let mut filter = format!("subtitles=filename={}", effects::escape_filter_value(path));
+    let mut style = Vec::new();
+    if let Some(size) = subtitles.font_size {
+        style.push(format!("FontSize={}", size.max(1)));
+    }
+    if let Some(color) = &subtitles.color {
+        // ASS colors are &HAABBGGRR, with alpha 00 for opaque
+        let rgb = effects::ffmpeg_color(color)?;
+        let (red, green, blue) = (&rgb[2..4], &rgb[4..6], &rgb[6..8]);
+        style.push(format!("PrimaryColour=&H00{blue}{green}{red}"));
+    }
+    if !style.is_empty() {
+        let _ = write!(
+            filter,
+            ":force_style={}",
+            effects::escape_filter_value(&style.join(","))
+        );
+    }
+
+    // Cue times are on the timeline, so a render starting partway through
+    // shifts its frames there and back around the filter
+    if offset > 0.0 {
+        Ok(format!(
+            ",setpts=PTS+{offset}/TB,{filter},setpts=PTS-{offset}/TB"
+        ))
+    } else {
+        Ok(format!(",{filter}"))
+    }
+}
+
+/// Graph text overlaying `watermark` on the chain it follows, for a
+/// `width`x`height` render; the chain carries on after the overlay
+///
+/// The image is read by a `movie` source; overlay repeats its single frame
+/// for the whole render.
+fn watermark_filters(watermark: &WatermarkSettings, width: u32, height: u32) -> Result<String> {
+    let path = &watermark.image_path;
+    if !std::path::Path::new(path).is_file() {
+        return Err(Error::InvalidPath(format!(
+            "Watermark image not found: {}",
+            path
+        )));
+    }
+    let image_width = (width as f64 * watermark.scale.clamp(0.01, 1.0))
+        .round()
+        .max(1.0);
+    let opacity = watermark.opacity.clamp(0.0, 1.0);
+    // Inset from the edges by a share of the frame height
+    let margin = (height as f64 * WATERMARK_MARGIN).round();
+    let (x, y) = match watermark.position {
+        WatermarkPosition::TopLeft => (format!("{margin}"), format!("{margin}")),
+        WatermarkPosition::TopRight => (format!("W-w-{margin}"), format!("{margin}")),
+        WatermarkPosition::BottomLeft => (format!("{margin}"), format!("H-h-{margin}")),
+        WatermarkPosition::BottomRight => (format!("W-w-{margin}"), format!("H-h-{margin}")),
+        WatermarkPosition::Center => ("(W-w)/2".to_string(), "(H-h)/2".to_string()),
+    };
+    Ok(format!(
+        "[main];movie={},scale={image_width}:-1,format=rgba,\
+         colorchannelmixer=aa={opacity}[watermark];[main][watermark]overlay=x={x}:y={y}",
+        effects::escape_filter_value(path)
+    ))
+}
+
+/// Distance of a watermark from the frame edges, as a share of the height
+const WATERMARK_MARGIN: f64 = 0.03;
+
+/// Overlay x/y expressions placing the item's anchor (0.0 to 1.0 within the
+/// item) at `position` (canvas pixels)
+fn overlay_position(transform: &Transform) -> (String, String) {
+    (
+        format!("{}-overlay_w*{}", transform.position.x, transform.anchor.x),
+        format!("{}-overlay_h*{}", transform.position.y, transform.anchor.y),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// An audio track of `volume` holding one item of `asset` from `start`
+    /// for `duration` seconds
+    fn audio_track(asset: &str, volume: f64, start: f64, duration: f64) -> Value {
+        let origin = json!({ "x": 0.0, "y": 0.0 });
+        json!({
+            "id": format!("track-{asset}"),
+            "name": asset,
+            "type": "audio",
+            "items": [{
+                "id": format!("item-{asset}"),
+                "assetId": asset,
+                "startTime": start,
+                "duration": duration,
+                "inPoint": 0.0,
+                "outPoint": duration,
+                "transform": {
+                    "position": origin,
+                    "scale": { "x": 1.0, "y": 1.0 },
+                    "rotation": 0.0,
+                    "anchor": origin,
+                    "opacity": 1.0
+                },
+                "effects": [],
+                "transitions": [],
+                "keyframes": []
+            }],
+            "isVisible": true,
+            "isMuted": false,
+            "isLocked": false,
+            "opacity": volume,
+            "blendMode": "normal"
+        })
+    }
+
+    fn audio_asset(id: &str) -> Value {
+        json!({
+            "id": id,
+            "name": id,
+            "path": format!("/media/{id}.wav"),
+            "duration": 10.0,
+            "sampleRate": 48000,
+            "channels": 2,
+            "format": "wav",
+            "size": 0
+        })
+    }
+
+    #[test]
+    fn overlapping_audio_items_are_mixed() {
+        let project: Project = serde_json::from_value(json!({
+            "id": "00000000-0000-0000-0000-000000000000",
+            "name": "Mix",
+            "version": "1.0.0",
+            "createdAt": "2024-01-01T00:00:00Z",
+            "updatedAt": "2024-01-01T00:00:00Z",
+            "settings": {
+                "resolution": { "width": 1280, "height": 720 },
+                "frameRate": 30.0,
+                "backgroundColor": "#000000",
+                "duration": "auto"
+            },
+            "assets": {
+                "images": [],
+                "audio": [audio_asset("music"), audio_asset("voice")],
+                "video": [],
+                "captions": [],
+                "fonts": []
+            },
+            "composition": {
+                "tracks": [
+                    audio_track("music", 0.5, 0.0, 4.0),
+                    audio_track("voice", 1.0, 2.5, 3.0)
+                ],
+                "markers": []
+            }
+        }))
+        .unwrap();
+
+        let mix = audio_mix(&project, (0.0, f64::INFINITY), 1, 6.0)
+            .unwrap()
+            .unwrap();
+        assert_eq!(mix.clips.len(), 2);
+        let graph = &mix.graph;
+        assert!(
+            graph.contains("[1:a]aresample=48000,volume=0.5,adelay=0:all=1[a0];"),
+            "{graph}"
+        );
+        assert!(
+            graph.contains("[2:a]aresample=48000,volume=1,adelay=2500:all=1[a1];"),
+            "{graph}"
+        );
+        assert!(graph.contains("[a0][a1]amix=inputs=2:"), "{graph}");
+        assert!(
+            graph.ends_with("apad=whole_dur=6,atrim=end=6[aout]"),
+            "{graph}"
+        );
+    }
+}
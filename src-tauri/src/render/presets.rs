@@ -0,0 +1,232 @@
+//! Render settings for common delivery targets, built in and user saved
+
+use super::{HardwareAcceleration, RenderQuality, RenderSettings};
+use crate::{Error, Result};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Name of the library file in the app data directory
+const LIBRARY_FILE: &str = "render_presets.json";
+
+lazy_static! {
+    /// Serializes reads and writes of the library file
+    static ref LIBRARY_LOCK: Mutex<()> = Mutex::new(());
+    /// Directory of the library [`RenderManager::preset`](super::RenderManager::preset)
+    /// reads, once set
+    static ref LIBRARY_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+}
+
+/// Named render settings offered as a one-click export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenderPreset {
+    /// Identifier, unique within the library
+    pub name: String,
+    /// Label for the UI; the name if empty
+    #[serde(default)]
+    pub label: String,
+    /// What the preset is tuned for
+    #[serde(default)]
+    pub description: String,
+    pub settings: RenderSettings,
+}
+
+/// The user's render presets, kept as JSON in the app data directory
+///
+/// The library starts out with the built-in presets, which can then be
+/// edited or deleted like the user's own.
+pub struct RenderPresetLibrary {
+    path: PathBuf,
+}
+
+impl RenderPresetLibrary {
+    /// The library in `dir` (usually the app data directory)
+    pub fn new(dir: &Path) -> Self {
+        Self {
+            path: dir.join(LIBRARY_FILE),
+        }
+    }
+
+    /// All presets, in the order they were added
+    pub fn list(&self) -> Result<Vec<RenderPreset>> {
+        let _lock = LIBRARY_LOCK.lock().unwrap();
+        self.read()
+    }
+
+    /// Add a preset, replacing the one with the same name
+    pub fn save(&self, preset: RenderPreset) -> Result<()> {
+        if preset.name.trim().is_empty() {
+            return Err(Error::Render("Preset name must not be empty".to_string()));
+        }
+        let _lock = LIBRARY_LOCK.lock().unwrap();
+        let mut presets = self.read()?;
+        match presets.iter_mut().find(|p| p.name == preset.name) {
+            Some(existing) => *existing = preset,
+            None => presets.push(preset),
+        }
+        self.write(&presets)
+    }
+
+    /// Remove the preset called `name`
+    pub fn delete(&self, name: &str) -> Result<()> {
+        let _lock = LIBRARY_LOCK.lock().unwrap();
+        let mut presets = self.read()?;
+        let count = presets.len();
+        presets.retain(|p| p.name != name);
+        if presets.len() == count {
+            return Err(Error::NotFound(format!("Render preset {} not found", name)));
+        }
+        self.write(&presets)
+    }
+
+    /// Read the library file, creating it with the built-in presets on
+    /// first use
+    fn read(&self) -> Result<Vec<RenderPreset>> {
+        if !self.path.exists() {
+            let presets = presets();
+            self.write(&presets)?;
+            return Ok(presets);
+        }
+        Ok(serde_json::from_slice(&fs::read(&self.path)?)?)
+    }
+
+    fn write(&self, presets: &[RenderPreset]) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(presets)?)?;
+        Ok(())
+    }
+}
+
+/// Settings of the preset called `name` in the library set with
+/// [`set_library_dir`], or else of the built-in one
+pub(super) fn find(name: &str) -> Option<RenderSettings> {
+    let dir = LIBRARY_DIR.lock().unwrap().clone();
+    let saved = dir
+        .and_then(|dir| RenderPresetLibrary::new(&dir).list().ok())
+        .and_then(|presets| presets.into_iter().find(|p| p.name == name));
+    saved
+        .or_else(|| presets().into_iter().find(|p| p.name == name))
+        .map(|preset| preset.settings)
+}
+
+/// Read presets by name from the library in `dir`, or only the built-ins if
+/// `None`
+pub(super) fn set_library_dir(dir: Option<PathBuf>) {
+    *LIBRARY_DIR.lock().unwrap() = dir;
+}
+
+/// All built-in presets, in the order the UI lists them
+fn presets() -> Vec<RenderPreset> {
+    vec![
+        // YouTube's recommended upload settings: H.264 and AAC in MP4, 8 Mbps
+        // for 1080p and 35 Mbps for 2160p at 24 to 30 fps
+        preset(
+            "youtube-1080p",
+            "YouTube 1080p",
+            "1920x1080, 30 fps, H.264 at 8 Mbps",
+            settings(
+                (1920, 1080),
+                30.0,
+                "mp4",
+                RenderQuality::High,
+                Some(8_000_000),
+            ),
+        ),
+        preset(
+            "youtube-4k",
+            "YouTube 4K",
+            "3840x2160, 30 fps, H.264 at 35 Mbps",
+            settings(
+                (3840, 2160),
+                30.0,
+                "mp4",
+                RenderQuality::High,
+                Some(35_000_000),
+            ),
+        ),
+        preset(
+            "instagram-reel",
+            "Instagram Reel",
+            "1080x1920 vertical, 30 fps, H.264",
+            settings((1080, 1920), 30.0, "mp4", RenderQuality::High, None),
+        ),
+        preset(
+            "twitter",
+            "X (Twitter)",
+            "1280x720, 30 fps, H.264 at 5 Mbps",
+            settings(
+                (1280, 720),
+                30.0,
+                "mp4",
+                RenderQuality::Medium,
+                Some(5_000_000),
+            ),
+        ),
+        // 2.5 Mbps video plus 192 kbps audio fits Discord's 10 MB upload
+        // limit for clips up to about 29 seconds
+        preset(
+            "discord",
+            "Discord",
+            "1280x720, 30 fps, H.264 at 2.5 Mbps",
+            settings(
+                (1280, 720),
+                30.0,
+                "mp4",
+                RenderQuality::Medium,
+                Some(2_500_000),
+            ),
+        ),
+        preset(
+            "lossless",
+            "Lossless",
+            "1920x1080, 30 fps, lossless H.264, for archiving (large files)",
+            settings((1920, 1080), 30.0, "mp4", RenderQuality::Lossless, None),
+        ),
+        preset(
+            "prores-master",
+            "ProRes master",
+            "1920x1080, 30 fps, ProRes 422 HQ with PCM audio, for archiving and further editing",
+            settings((1920, 1080), 30.0, "prores", RenderQuality::Lossless, None),
+        ),
+    ]
+}
+
+fn preset(name: &str, label: &str, description: &str, settings: RenderSettings) -> RenderPreset {
+    RenderPreset {
+        name: name.to_string(),
+        label: label.to_string(),
+        description: description.to_string(),
+        settings,
+    }
+}
+
+fn settings(
+    resolution: (u32, u32),
+    frame_rate: f64,
+    format: &str,
+    quality: RenderQuality,
+    target_bitrate: Option<u64>,
+) -> RenderSettings {
+    RenderSettings {
+        resolution,
+        frame_rate,
+        format: format.to_string(),
+        quality,
+        hardware_acceleration: HardwareAcceleration::None,
+        two_pass: false,
+        target_bitrate,
+        crf: None,
+        encoder_preset: None,
+        animation_fps: None,
+        max_colors: None,
+        range: None,
+        image_format: None,
+        watermark: None,
+        subtitles: None,
+    }
+}
@@ -33,7 +33,16 @@ export type MpvEventPayload =
     | { type: "Duration"; value: number }
     | { type: "Paused"; value: boolean }
     | { type: "Volume"; value: number }
-    | { type: "Ended" };
+    | { type: "Muted"; value: boolean }
+    | { type: "Ended" }
+    | { type: "PlaylistPos"; value: number }
+    | { type: "PlaylistCount"; value: number }
+    | { type: "Loop"; value: "off" | "file" | "playlist" }
+    | { type: "Buffering"; value: number }
+    | { type: "Zoom"; value: number }
+    | { type: "Pan"; value: { x: number; y: number } }
+    | { type: "Crashed" }
+    | { type: "Stats"; value: { avsync: number; dropped_frames: number; video_bitrate: number } };
 
 // ── yt-dlp ─────────────────────────────────────────────────────────────────
 